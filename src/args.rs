@@ -1,5 +1,8 @@
+use chrono::{DateTime, TimeZone, Utc};
 use structopt::StructOpt;
 
+use crate::config;
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "MinecraftCompose", about = "Manage minecraft servers")]
 pub struct Args {
@@ -31,6 +34,30 @@ pub struct Args {
     )]
     pub verbosity: u64,
 
+    #[structopt(
+        long,
+        value_name = "RUNTIME",
+        possible_values = &["docker", "podman"],
+        help = "Sets the container runtime, overriding the config"
+    )]
+    pub runtime: Option<config::RuntimeType>,
+
+    #[structopt(
+        short = "H",
+        long,
+        value_name = "HOST",
+        help = "Sets the container runtime host to connect to, overriding the config"
+    )]
+    pub host: Option<String>,
+
+    #[structopt(
+        long,
+        value_name = "CONTEXT",
+        conflicts_with = "host",
+        help = "Sets the docker context to connect to, overriding the config"
+    )]
+    pub context: Option<String>,
+
     #[structopt(subcommand)]
     pub subcommand: SubCommand,
 }
@@ -38,10 +65,10 @@ pub struct Args {
 #[derive(Debug, StructOpt)]
 pub enum SubCommand {
     #[structopt(about = "Creates and starts the server container")]
-    Up,
+    Up(UpArgs),
 
     #[structopt(about = "Stops and destroys the server container")]
-    Down,
+    Down(StopArgs),
 
     #[structopt(about = "Creates the server container")]
     Create,
@@ -50,22 +77,373 @@ pub enum SubCommand {
     Destroy,
 
     #[structopt(about = "Starts the server container")]
-    Start,
+    Start(WaitArgs),
 
     #[structopt(about = "Stops the server container")]
-    Stop,
+    Stop(StopArgs),
 
     #[structopt(about = "Displays the container status")]
-    Status,
+    Status(StatusArgs),
+
+    #[structopt(about = "Pings the server for its version, MOTD and player count")]
+    Ping(PingArgs),
+
+    #[structopt(about = "Lists the players online using the query protocol")]
+    Players {
+        #[structopt(
+            long,
+            value_name = "FORMAT",
+            default_value = "text",
+            possible_values = &["text", "json"],
+            help = "Sets the output format"
+        )]
+        format: OutputFormat,
+    },
 
     #[structopt(about = "Connects a console to the server")]
     Console,
 
+    #[structopt(about = "Runs commands on the server and prints the responses")]
+    Exec {
+        #[structopt(
+            value_name = "COMMAND",
+            help = "The commands to run, read from stdin if none are given"
+        )]
+        commands: Vec<String>,
+
+        #[structopt(
+            long,
+            value_name = "FILE",
+            conflicts_with = "commands",
+            help = "Reads the commands to run from a file, one per line"
+        )]
+        input: Option<String>,
+    },
+
     #[structopt(about = "Displays the server's logs")]
-    Logs,
+    Logs(LogsArgs),
+
+    #[structopt(about = "Displays game events such as players joining, deaths and crashes")]
+    Events(EventsArgs),
+
+    #[structopt(about = "Sends the configured notifications for game events until stopped")]
+    Watch,
 
     #[structopt(about = "Manage datapacks for the server")]
     Datapacks(DatapackCommand),
+
+    #[structopt(about = "Manage mods for the server")]
+    Mods(ModCommand),
+
+    #[structopt(about = "Manage plugins for the server")]
+    Plugins(PluginCommand),
+
+    #[structopt(about = "Backs up the world and manages existing backups")]
+    Backup {
+        #[structopt(subcommand)]
+        command: Option<BackupCommand>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct UpArgs {
+    #[structopt(
+        long,
+        help = "Stops, destroys and recreates the container if the config has changed"
+    )]
+    pub recreate: bool,
+
+    #[structopt(flatten)]
+    pub stop: StopArgs,
+
+    #[structopt(flatten)]
+    pub wait: WaitArgs,
+}
+
+const DEFAULT_WAIT_TIMEOUT: u64 = 300;
+
+#[derive(Debug, StructOpt)]
+pub struct WaitArgs {
+    #[structopt(
+        long,
+        help = "Waits for the server to start, failing if the container exits or is unhealthy"
+    )]
+    pub wait: bool,
+
+    #[structopt(
+        long,
+        value_name = "SECONDS",
+        requires = "wait",
+        help = "How long to wait for the server to start, defaults to 300"
+    )]
+    pub wait_timeout: Option<u64>,
+}
+
+impl WaitArgs {
+    // Returns how long to wait for the server, or None when not waiting
+    pub fn timeout(&self) -> Option<u64> {
+        if self.wait {
+            Some(self.wait_timeout.unwrap_or(DEFAULT_WAIT_TIMEOUT))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct LogsArgs {
+    #[structopt(
+        long,
+        help = "Prints the logs and exits instead of following new output"
+    )]
+    pub no_follow: bool,
+
+    #[structopt(
+        long,
+        value_name = "LINES",
+        help = "How many lines to show from the end of the logs, or all. Defaults to 10, or all with --since or --until"
+    )]
+    pub tail: Option<LogTail>,
+
+    #[structopt(
+        long,
+        value_name = "TIME",
+        help = "Shows the logs after a timestamp, e.g. 2024-06-01T12:00:00Z, or a relative time, e.g. 30m"
+    )]
+    pub since: Option<LogTime>,
+
+    #[structopt(
+        long,
+        value_name = "TIME",
+        help = "Shows the logs before a timestamp, e.g. 2024-06-01T12:00:00Z, or a relative time, e.g. 30m"
+    )]
+    pub until: Option<LogTime>,
+
+    #[structopt(long, help = "Prefixes each line with its timestamp")]
+    pub timestamps: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct EventsArgs {
+    #[structopt(
+        long,
+        help = "Prints the events already in the logs and exits instead of following new events"
+    )]
+    pub no_follow: bool,
+
+    #[structopt(
+        long,
+        value_name = "TIME",
+        help = "Shows the events after a timestamp, e.g. 2024-06-01T12:00:00Z, or a relative time, e.g. 30m"
+    )]
+    pub since: Option<LogTime>,
+
+    #[structopt(
+        long,
+        value_name = "TIME",
+        help = "Shows the events before a timestamp, e.g. 2024-06-01T12:00:00Z, or a relative time, e.g. 30m"
+    )]
+    pub until: Option<LogTime>,
+
+    #[structopt(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        possible_values = &["text", "json"],
+        help = "Sets the output format, json prints one event per line"
+    )]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogTail {
+    Lines(u64),
+    All,
+}
+
+impl std::str::FromStr for LogTail {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "all" => Ok(LogTail::All),
+            lines => lines
+                .parse()
+                .map(LogTail::Lines)
+                .map_err(|_| format!("The tail \"{}\" must be a number of lines or all", value)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogTime {
+    At(DateTime<Utc>),
+    // A number of seconds before now
+    Ago(i64),
+}
+
+impl LogTime {
+    pub fn timestamp(&self, now: DateTime<Utc>) -> i64 {
+        match self {
+            LogTime::At(time) => time.timestamp(),
+            LogTime::Ago(seconds) => now.timestamp().saturating_sub(*seconds),
+        }
+    }
+}
+
+// Parses a duration such as 90s, 30m or 1h30m into seconds
+fn parse_duration(value: &str) -> Option<i64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return None,
+        };
+        seconds = number
+            .parse::<i64>()
+            .ok()?
+            .checked_mul(unit)?
+            .checked_add(seconds)?;
+        number.clear();
+    }
+
+    if number.is_empty() && !value.is_empty() {
+        Some(seconds)
+    } else {
+        None
+    }
+}
+
+impl std::str::FromStr for LogTime {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            Ok(LogTime::At(time.with_timezone(&Utc)))
+        } else if let Ok(timestamp) = value.parse() {
+            Utc.timestamp_opt(timestamp, 0)
+                .single()
+                .map(LogTime::At)
+                .ok_or_else(|| format!("The timestamp \"{}\" is out of range", value))
+        } else if let Some(seconds) = parse_duration(value) {
+            Ok(LogTime::Ago(seconds))
+        } else {
+            Err(format!(
+                "The time \"{}\" is not valid, use a timestamp such as 2024-06-01T12:00:00Z or a duration such as 30m",
+                value
+            ))
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct StatusArgs {
+    #[structopt(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        possible_values = &["text", "json"],
+        help = "Sets the output format"
+    )]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PingArgs {
+    #[structopt(
+        long,
+        value_name = "ADDRESS",
+        help = "Pings a server at HOST[:PORT] instead of the configured server"
+    )]
+    pub address: Option<ServerAddress>,
+
+    #[structopt(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        possible_values = &["text", "json"],
+        help = "Sets the output format"
+    )]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ServerAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl std::str::FromStr for ServerAddress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (host, port) = match value.rsplit_once(':') {
+            // A bare IPv6 address has colons but no port
+            Some((host, _)) if host.contains(':') && !host.ends_with(']') => (value, None),
+            Some((host, port)) => (host, Some(port)),
+            None => (value, None),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(format!("The address \"{}\" has no host", value));
+        }
+
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| format!("The port \"{}\" is not valid", port))?,
+            None => 25565,
+        };
+
+        Ok(ServerAddress {
+            host: host.to_owned(),
+            port,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("Unknown format \"{}\"", value)),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct StopArgs {
+    #[structopt(
+        long,
+        value_name = "SECONDS",
+        help = "Warns players in game for the given number of seconds before stopping"
+    )]
+    pub warn: Option<u64>,
+
+    #[structopt(
+        long,
+        value_name = "SECONDS",
+        default_value = "60",
+        help = "How long to wait for the server to exit before killing it"
+    )]
+    pub timeout: u64,
 }
 
 #[derive(Debug, StructOpt)]
@@ -73,3 +451,113 @@ pub enum DatapackCommand {
     #[structopt(about = "Syncs datapacks to the server")]
     Sync,
 }
+
+#[derive(Debug, StructOpt)]
+pub enum ModCommand {
+    #[structopt(about = "Syncs mods to the server")]
+    Sync,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum PluginCommand {
+    #[structopt(about = "Syncs plugins to the server")]
+    Sync,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum BackupCommand {
+    #[structopt(about = "Creates a new backup, the default if no command is given")]
+    Create,
+
+    #[structopt(about = "Lists the existing backups")]
+    List,
+
+    #[structopt(about = "Deletes backups according to the retention policy")]
+    Prune,
+
+    #[structopt(about = "Restores a backup, replacing the current world")]
+    Restore {
+        #[structopt(help = "The id of the backup to restore, or \"latest\"")]
+        id: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! server_address_tests {
+        ($($name:ident: $value:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let expected: Option<(&str, u16)> = $expected;
+                assert_eq!(
+                    expected.map(|(host, port)| ServerAddress {
+                        host: host.to_owned(),
+                        port
+                    }),
+                    $value.parse::<ServerAddress>().ok()
+                );
+            }
+        )*
+        }
+    }
+
+    server_address_tests! {
+        test_server_address_host: "mc.example.com", Some(("mc.example.com", 25565)),
+        test_server_address_host_and_port: "mc.example.com:25570", Some(("mc.example.com", 25570)),
+        test_server_address_ipv6: "::1", Some(("::1", 25565)),
+        test_server_address_ipv6_and_port: "[::1]:25570", Some(("::1", 25570)),
+        test_server_address_invalid_port: "mc.example.com:port", None,
+        test_server_address_port_out_of_range: "mc.example.com:70000", None,
+        test_server_address_empty: "", None,
+        test_server_address_port_only: ":25565", None,
+    }
+
+    macro_rules! log_tail_tests {
+        ($($name:ident: $value:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, $value.parse::<LogTail>().ok());
+            }
+        )*
+        }
+    }
+
+    log_tail_tests! {
+        test_log_tail_lines: "100", Some(LogTail::Lines(100)),
+        test_log_tail_all: "all", Some(LogTail::All),
+        test_log_tail_negative: "-1", None,
+        test_log_tail_invalid: "some", None,
+    }
+
+    macro_rules! log_time_tests {
+        ($($name:ident: $value:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, $value.parse::<LogTime>().ok());
+            }
+        )*
+        }
+    }
+
+    log_time_tests! {
+        test_log_time_rfc3339: "2024-06-01T12:00:00Z", Some(LogTime::At(Utc.timestamp(1717243200, 0))),
+        test_log_time_offset: "2024-06-01T14:00:00+02:00", Some(LogTime::At(Utc.timestamp(1717243200, 0))),
+        test_log_time_unix: "1717243200", Some(LogTime::At(Utc.timestamp(1717243200, 0))),
+        test_log_time_unix_out_of_range: "99999999999999", None,
+        test_log_time_duration_overflow: "9223372036854775807s1s", None,
+        test_log_time_seconds: "90s", Some(LogTime::Ago(90)),
+        test_log_time_minutes: "30m", Some(LogTime::Ago(1800)),
+        test_log_time_combined: "1h30m", Some(LogTime::Ago(5400)),
+        test_log_time_days: "2d", Some(LogTime::Ago(172800)),
+        test_log_time_missing_unit: "1h30", None,
+        test_log_time_missing_number: "h", None,
+        test_log_time_unknown_unit: "3w", None,
+        test_log_time_empty: "", None,
+        test_log_time_date_only: "2024-06-01", None,
+    }
+}
@@ -1,8 +1,57 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::error;
+use std::fmt;
 use toml;
 
+const RCON_PASSWORD_ENV_VAR: &str = "MINECRAFT_COMPOSE_RCON_PASSWORD";
+const DEFAULT_SERVER_MEMORY: &str = "1G";
+const GAME_CONTAINER_PORT: u16 = 25565;
+const RCON_CONTAINER_PORT: u16 = 25575;
+const DATA_TARGET: &str = "/data";
+// Set from other sections of the config or relied on by the port mappings, so they
+// cannot be overridden by container.env
+const MANAGED_ENV_VARS: [&str; 18] = [
+    "EULA",
+    "VERSION",
+    "RCON_PASSWORD",
+    "ENABLE_RCON",
+    "OVERRIDE_SERVER_PROPERTIES",
+    "MEMORY",
+    "JVM_OPTS",
+    "USE_AIKAR_FLAGS",
+    "TYPE",
+    "PAPER_BUILD",
+    "PURPUR_BUILD",
+    "FABRIC_LOADER_VERSION",
+    "FABRIC_INSTALLER_VERSION",
+    "QUILT_LOADER_VERSION",
+    "QUILT_INSTALLER_VERSION",
+    "FORGE_VERSION",
+    "NEOFORGE_VERSION",
+    "SERVER_PORT",
+];
+// The lifecycle events sent by the subcommands, followed by the game events sent by watch
+const NOTIFICATION_EVENTS: [&str; 10] = [
+    "container_started",
+    "container_stopped",
+    "started",
+    "stopping",
+    "join",
+    "leave",
+    "chat",
+    "death",
+    "advancement",
+    "crash",
+];
+const DEFAULT_NOTIFICATION_EVENTS: [&str; 5] = [
+    "container_started",
+    "container_stopped",
+    "join",
+    "leave",
+    "crash",
+];
+
 macro_rules! config_defaults {
     ($($name:ident -> $type:ty: $value:expr;)*) => {
     $(
@@ -18,15 +67,28 @@ config_defaults! {
     default_port -> i32: 25565;
 
     default_world_name -> String: "world".to_string();
-    default_world_gamemode -> String: "survival".to_string();
+    default_world_gamemode -> Gamemode: Gamemode::Survival;
     default_world_allow_flight -> bool: false;
     default_world -> World: World {
         name: default_world_name(),
         seed: None,
         gamemode: default_world_gamemode(),
+        difficulty: None,
+        hardcore: None,
+        pvp: None,
+        max_players: None,
+        motd: None,
         allow_flight: default_world_allow_flight(),
     };
 
+    default_container_image -> String: "docker.io/itzg/minecraft-server".to_string();
+
+    default_backup_directory -> String: "backups".to_string();
+    default_backup -> Backup: Backup {
+        directory: default_backup_directory(),
+        retention: Retention::default(),
+    };
+
 }
 
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
@@ -45,6 +107,30 @@ pub struct Config {
     pub world: World,
 
     pub datapacks: Option<HashMap<String, String>>,
+
+    pub mods: Option<HashMap<String, String>>,
+
+    pub plugins: Option<HashMap<String, String>>,
+
+    pub properties: Option<HashMap<String, PropertyValue>>,
+
+    #[serde(default = "default_backup")]
+    pub backup: Backup,
+
+    #[serde(default)]
+    pub rcon: Rcon,
+
+    #[serde(default)]
+    pub query: Query,
+
+    #[serde(default)]
+    pub runtime: Runtime,
+
+    #[serde(default)]
+    pub container: Container,
+
+    #[serde(default)]
+    pub notifications: Notifications,
 }
 
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
@@ -53,6 +139,11 @@ pub struct Server {
 
     pub memory: Option<String>,
 
+    pub jvm_opts: Option<String>,
+
+    #[serde(default)]
+    pub use_aikar_flags: bool,
+
     #[serde(flatten)]
     pub server_type: ServerType,
 }
@@ -62,6 +153,33 @@ pub struct Server {
 pub enum ServerType {
     #[serde(alias = "vanilla")]
     Vanilla,
+
+    #[serde(alias = "paper")]
+    Paper { build: Option<String> },
+
+    #[serde(alias = "spigot")]
+    Spigot,
+
+    #[serde(alias = "purpur")]
+    Purpur { build: Option<String> },
+
+    #[serde(alias = "fabric")]
+    Fabric {
+        loader_version: String,
+        installer_version: Option<String>,
+    },
+
+    #[serde(alias = "quilt")]
+    Quilt {
+        loader_version: String,
+        installer_version: Option<String>,
+    },
+
+    #[serde(alias = "forge")]
+    Forge { forge_version: String },
+
+    #[serde(alias = "neoforge")]
+    NeoForge { neoforge_version: String },
 }
 
 impl Default for ServerType {
@@ -78,14 +196,1452 @@ pub struct World {
     pub seed: Option<String>,
 
     #[serde(default = "default_world_gamemode")]
-    pub gamemode: String,
+    pub gamemode: Gamemode,
+
+    pub difficulty: Option<Difficulty>,
+
+    pub hardcore: Option<bool>,
+
+    pub pvp: Option<bool>,
+
+    pub max_players: Option<u32>,
+
+    pub motd: Option<String>,
 
     #[serde(default = "default_world_allow_flight")]
     pub allow_flight: bool,
 }
 
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Gamemode {
+    #[default]
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl fmt::Display for Gamemode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gamemode::Survival => write!(f, "survival"),
+            Gamemode::Creative => write!(f, "creative"),
+            Gamemode::Adventure => write!(f, "adventure"),
+            Gamemode::Spectator => write!(f, "spectator"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Peaceful,
+    #[default]
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difficulty::Peaceful => write!(f, "peaceful"),
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Normal => write!(f, "normal"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Backup {
+    #[serde(default = "default_backup_directory")]
+    pub directory: String,
+
+    #[serde(default)]
+    pub retention: Retention,
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Retention {
+    pub keep_last: Option<usize>,
+
+    pub keep_daily: Option<usize>,
+
+    pub keep_weekly: Option<usize>,
+
+    pub keep_monthly: Option<usize>,
+}
+
+impl Retention {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none()
+            && self.keep_daily.is_none()
+            && self.keep_weekly.is_none()
+            && self.keep_monthly.is_none()
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Rcon {
+    pub password: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Query {
+    #[serde(default)]
+    pub enabled: bool,
+
+    pub port: Option<u16>,
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Notifications {
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub struct Webhook {
+    pub url: String,
+
+    #[serde(default)]
+    pub format: WebhookFormat,
+
+    pub events: Option<Vec<String>>,
+}
+
+impl Webhook {
+    pub fn is_subscribed(&self, event: &str) -> bool {
+        match &self.events {
+            Some(events) => events.iter().any(|name| name == event),
+            None => DEFAULT_NOTIFICATION_EVENTS.contains(&event),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Discord,
+    Slack,
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Runtime {
+    #[serde(rename = "type", default)]
+    pub runtime_type: RuntimeType,
+
+    pub host: Option<String>,
+
+    pub context: Option<String>,
+
+    pub cert_path: Option<String>,
+
+    pub data_path: Option<String>,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeType {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl std::str::FromStr for RuntimeType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "docker" => Ok(RuntimeType::Docker),
+            "podman" => Ok(RuntimeType::Podman),
+            _ => Err(format!("Unknown runtime \"{}\"", value)),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub struct Container {
+    #[serde(default = "default_container_image")]
+    pub image: String,
+
+    pub tag: Option<String>,
+
+    pub digest: Option<String>,
+
+    #[serde(default)]
+    pub resources: Resources,
+
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+
+    #[serde(default)]
+    pub ports: Vec<Port>,
+
+    #[serde(default)]
+    pub volumes: Vec<Volume>,
+
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl Default for Container {
+    fn default() -> Container {
+        Container {
+            image: default_container_image(),
+            tag: None,
+            digest: None,
+            resources: Resources::default(),
+            restart_policy: RestartPolicy::default(),
+            ports: Vec::new(),
+            volumes: Vec::new(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+pub struct Resources {
+    pub cpus: Option<f64>,
+
+    pub memory: Option<String>,
+
+    pub memory_reservation: Option<String>,
+
+    pub pids_limit: Option<i64>,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    No,
+    #[default]
+    Always,
+    UnlessStopped,
+    OnFailure,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub struct Port {
+    pub container: u16,
+
+    pub host: Option<u16>,
+
+    #[serde(default)]
+    pub protocol: Protocol,
+
+    pub host_ip: Option<String>,
+}
+
+impl Port {
+    pub fn host_port(&self) -> u16 {
+        self.host.unwrap_or(self.container)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Protocol::Tcp => write!(f, "tcp"),
+            Protocol::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub struct Volume {
+    pub source: String,
+
+    pub target: String,
+
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PropertyValue::Boolean(value) => write!(f, "{}", value),
+            PropertyValue::Integer(value) => write!(f, "{}", value),
+            PropertyValue::Float(value) => write!(f, "{}", value),
+            PropertyValue::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ValidationError(String);
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl error::Error for ValidationError {}
+
+fn is_valid_version(version: &str) -> bool {
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+')
+}
+
+fn validate_version(field: &str, version: &str) -> Result<(), ValidationError> {
+    if !is_valid_version(version) {
+        return Err(ValidationError(format!(
+            "The value \"{}\" for server.{} is not a valid version",
+            version, field
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parses a size such as 512m or 4G into bytes, the format used by both
+/// docker's memory limits and the JVM's heap flags.
+pub fn parse_memory_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let size = size
+        .strip_suffix(|c| c == 'b' || c == 'B')
+        .filter(|size| size.ends_with(|c: char| c.is_ascii_alphabetic()))
+        .unwrap_or(size);
+    let (number, multiplier) = match size.chars().last()?.to_ascii_lowercase() {
+        'k' => (&size[..size.len() - 1], 1 << 10),
+        'm' => (&size[..size.len() - 1], 1 << 20),
+        'g' => (&size[..size.len() - 1], 1 << 30),
+        't' => (&size[..size.len() - 1], 1 << 40),
+        _ => (size, 1),
+    };
+
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn validate_memory_size(field: &str, size: &str) -> Result<u64, ValidationError> {
+    parse_memory_size(size)
+        .filter(|bytes| *bytes > 0)
+        .ok_or_else(|| {
+            ValidationError(format!(
+                "The value \"{}\" for {} is not a valid memory size",
+                size, field
+            ))
+        })
+}
+
+impl Server {
+    /// The maximum heap size the JVM will use, from -Xmx in jvm_opts if set,
+    /// since the JVM uses the last one it is given, and otherwise from memory.
+    pub fn heap_size(&self) -> &str {
+        self.jvm_opts
+            .as_deref()
+            .and_then(|jvm_opts| {
+                jvm_opts
+                    .split_whitespace()
+                    .rev()
+                    .find_map(|opt| opt.strip_prefix("-Xmx"))
+            })
+            .or(self.memory.as_deref())
+            .unwrap_or(DEFAULT_SERVER_MEMORY)
+    }
+}
+
+impl ServerType {
+    pub fn loader_version(&self) -> Option<&str> {
+        match self {
+            ServerType::Fabric { loader_version, .. }
+            | ServerType::Quilt { loader_version, .. } => Some(loader_version),
+            ServerType::Forge { forge_version } => Some(forge_version),
+            ServerType::NeoForge { neoforge_version } => Some(neoforge_version),
+            _ => None,
+        }
+    }
+
+    pub fn supports_mods(&self) -> bool {
+        matches!(
+            self,
+            ServerType::Fabric { .. }
+                | ServerType::Quilt { .. }
+                | ServerType::Forge { .. }
+                | ServerType::NeoForge { .. }
+        )
+    }
+
+    pub fn supports_plugins(&self) -> bool {
+        matches!(
+            self,
+            ServerType::Paper { .. } | ServerType::Spigot | ServerType::Purpur { .. }
+        )
+    }
+
+    fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            ServerType::Fabric {
+                loader_version,
+                installer_version,
+            }
+            | ServerType::Quilt {
+                loader_version,
+                installer_version,
+            } => {
+                validate_version("loader_version", loader_version)?;
+                if let Some(installer_version) = installer_version {
+                    validate_version("installer_version", installer_version)?;
+                }
+                Ok(())
+            }
+            ServerType::Forge { forge_version } => validate_version("forge_version", forge_version),
+            ServerType::NeoForge { neoforge_version } => {
+                validate_version("neoforge_version", neoforge_version)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ServerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ServerType::Vanilla => "Vanilla",
+            ServerType::Paper { .. } => "Paper",
+            ServerType::Spigot => "Spigot",
+            ServerType::Purpur { .. } => "Purpur",
+            ServerType::Fabric { .. } => "Fabric",
+            ServerType::Quilt { .. } => "Quilt",
+            ServerType::Forge { .. } => "Forge",
+            ServerType::NeoForge { .. } => "NeoForge",
+        };
+
+        match self.loader_version() {
+            Some(loader_version) => write!(f, "{} {}", name, loader_version),
+            None => write!(f, "{}", name),
+        }
+    }
+}
+
+impl World {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if self.max_players == Some(0) {
+            return Err(ValidationError(
+                "The value for world.max_players must be at least 1".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Rcon {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(password) = &self.password {
+            if password.is_empty() || password.contains(char::is_whitespace) {
+                return Err(ValidationError(
+                    "The value for rcon.password must be non-empty and contain no whitespace"
+                        .to_owned(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Notifications {
+    fn validate(&self) -> Result<(), ValidationError> {
+        for webhook in &self.webhooks {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                return Err(ValidationError(
+                    "The url of each notifications.webhooks entry must be an http or https url"
+                        .to_owned(),
+                ));
+            }
+
+            for event in webhook.events.iter().flatten() {
+                if !NOTIFICATION_EVENTS.contains(&event.as_str()) {
+                    return Err(ValidationError(format!(
+                        "The event \"{}\" in notifications.webhooks is not one of {}",
+                        event,
+                        NOTIFICATION_EVENTS.join(", ")
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Runtime {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if self.host.is_some() && self.context.is_some() {
+            return Err(ValidationError(
+                "Only one of runtime.host and runtime.context may be set".to_owned(),
+            ));
+        }
+
+        if self.context.is_some() && self.runtime_type != RuntimeType::Docker {
+            return Err(ValidationError(
+                "The value for runtime.context is only supported by docker".to_owned(),
+            ));
+        }
+
+        if let Some(data_path) = &self.data_path {
+            if !data_path.starts_with('/') {
+                return Err(ValidationError(format!(
+                    "The value \"{}\" for runtime.data_path must be an absolute path",
+                    data_path
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Container {
+    fn validate(&self) -> Result<(), ValidationError> {
+        let name = self.image.rsplit('/').next().unwrap_or_default();
+        if self.image.is_empty() || self.image.contains('@') || name.contains(':') {
+            return Err(ValidationError(format!(
+                "The value \"{}\" for container.image must be an image name without a tag or digest",
+                self.image
+            )));
+        }
+
+        if self.tag.is_some() && self.digest.is_some() {
+            return Err(ValidationError(
+                "Only one of container.tag and container.digest may be set".to_owned(),
+            ));
+        }
+
+        if let Some(digest) = &self.digest {
+            let is_valid = match digest.strip_prefix("sha256:") {
+                Some(hash) => hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()),
+                None => false,
+            };
+            if !is_valid {
+                return Err(ValidationError(format!(
+                    "The value \"{}\" for container.digest is not a sha256 digest",
+                    digest
+                )));
+            }
+        }
+
+        self.resources.validate()?;
+
+        let mut targets = vec![DATA_TARGET];
+        for volume in &self.volumes {
+            volume.validate()?;
+            if targets.contains(&volume.target.as_str()) {
+                return Err(ValidationError(format!(
+                    "The target \"{}\" in container.volumes is already mounted",
+                    volume.target
+                )));
+            }
+            targets.push(&volume.target);
+        }
+
+        for name in self.env.keys() {
+            let is_valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_valid {
+                return Err(ValidationError(format!(
+                    "The name \"{}\" in container.env is not a valid environment variable",
+                    name
+                )));
+            }
+            if MANAGED_ENV_VARS.contains(&name.as_str()) {
+                return Err(ValidationError(format!(
+                    "The environment variable {} in container.env is set from other config and cannot be overridden",
+                    name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_volume_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+impl Volume {
+    fn validate(&self) -> Result<(), ValidationError> {
+        let is_path = self.source.starts_with('/') && !self.source.contains(':');
+        if !is_path && !is_volume_name(&self.source) {
+            return Err(ValidationError(format!(
+                "The source \"{}\" in container.volumes must be an absolute path or a volume name",
+                self.source
+            )));
+        }
+
+        if !self.target.starts_with('/') || self.target.contains(':') {
+            return Err(ValidationError(format!(
+                "The target \"{}\" in container.volumes must be an absolute path",
+                self.target
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Resources {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let Some(cpus) = self.cpus {
+            if cpus.is_nan() || cpus <= 0.0 {
+                return Err(ValidationError(
+                    "The value for container.resources.cpus must be greater than 0".to_owned(),
+                ));
+            }
+        }
+
+        if let Some(pids_limit) = self.pids_limit {
+            if pids_limit < 1 {
+                return Err(ValidationError(
+                    "The value for container.resources.pids_limit must be at least 1".to_owned(),
+                ));
+            }
+        }
+
+        let memory = match &self.memory {
+            Some(memory) => Some(validate_memory_size("container.resources.memory", memory)?),
+            None => None,
+        };
+        if let Some(memory_reservation) = &self.memory_reservation {
+            let memory_reservation =
+                validate_memory_size("container.resources.memory_reservation", memory_reservation)?;
+            if memory.is_some_and(|memory| memory_reservation > memory) {
+                return Err(ValidationError(
+                    "The value for container.resources.memory_reservation must not exceed container.resources.memory".to_owned(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.server.server_type.validate()?;
+        self.world.validate()?;
+        self.rcon.validate()?;
+        self.runtime.validate()?;
+        self.container.validate()?;
+        self.notifications.validate()?;
+        self.validate_heap_size()?;
+        self.validate_ports()
+    }
+
+    // Query is served over UDP on the game port, so it is published on the same port by default
+    pub fn query_port(&self) -> i32 {
+        self.query.port.map(i32::from).unwrap_or(self.port)
+    }
+
+    fn validate_ports(&self) -> Result<(), ValidationError> {
+        let mut container_ports = vec![
+            (GAME_CONTAINER_PORT, Protocol::Tcp),
+            (RCON_CONTAINER_PORT, Protocol::Tcp),
+        ];
+        let mut host_ports = vec![(self.port, Protocol::Tcp)];
+        if self.query.port == Some(0) {
+            return Err(ValidationError(
+                "The value for query.port must be between 1 and 65535".to_owned(),
+            ));
+        }
+        if self.query.enabled {
+            container_ports.push((GAME_CONTAINER_PORT, Protocol::Udp));
+            host_ports.push((self.query_port(), Protocol::Udp));
+        }
+        for port in &self.container.ports {
+            if port.container == 0 || port.host_port() == 0 {
+                return Err(ValidationError(
+                    "The ports in container.ports must be between 1 and 65535".to_owned(),
+                ));
+            }
+
+            let container_port = (port.container, port.protocol);
+            if container_ports.contains(&container_port) {
+                return Err(ValidationError(format!(
+                    "The container port {}/{} in container.ports is already published",
+                    port.container, port.protocol
+                )));
+            }
+            container_ports.push(container_port);
+
+            let host_port = (i32::from(port.host_port()), port.protocol);
+            if host_ports.contains(&host_port) {
+                return Err(ValidationError(format!(
+                    "The host port {}/{} in container.ports is already in use",
+                    port.host_port(),
+                    port.protocol
+                )));
+            }
+            host_ports.push(host_port);
+        }
+
+        Ok(())
+    }
+
+    fn validate_heap_size(&self) -> Result<(), ValidationError> {
+        let memory_limit = match &self.container.resources.memory {
+            Some(memory) => memory,
+            None => return Ok(()),
+        };
+
+        let heap_size = validate_memory_size("the server heap size", self.server.heap_size())?;
+        if parse_memory_size(memory_limit).is_some_and(|limit| heap_size > limit) {
+            return Err(ValidationError(format!(
+                "The server heap size {} exceeds the container memory limit {}",
+                self.server.heap_size(),
+                memory_limit
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 pub fn load_config(file_path: &str) -> Result<Config, Box<dyn error::Error>> {
     log::debug!("Loading config from {}", file_path);
     let file_contents = std::fs::read_to_string(file_path)?;
-    Ok(toml::from_str(&file_contents)?)
+    let mut config: Config = toml::from_str(&file_contents)?;
+    if let Ok(password) = std::env::var(RCON_PASSWORD_ENV_VAR) {
+        log::debug!("Using the rcon password from {}", RCON_PASSWORD_ENV_VAR);
+        config.rcon.password = Some(password);
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_config(server: &str) -> Result<Config, Box<dyn error::Error>> {
+        let config: Config = toml::from_str(&format!("name = \"name\"\n[server]\n{}", server))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_fabric() {
+        assert_eq!(
+            ServerType::Fabric {
+                loader_version: "0.11.6".to_owned(),
+                installer_version: Some("0.7.4".to_owned()),
+            },
+            parse_config(concat!(
+                "type = \"fabric\"\n",
+                "version = \"1.17.1\"\n",
+                "loader_version = \"0.11.6\"\n",
+                "installer_version = \"0.7.4\"\n",
+            ))
+            .unwrap()
+            .server
+            .server_type
+        );
+    }
+
+    #[test]
+    fn test_quilt() {
+        assert_eq!(
+            ServerType::Quilt {
+                loader_version: "0.16.0-beta.9".to_owned(),
+                installer_version: None,
+            },
+            parse_config(concat!(
+                "type = \"quilt\"\n",
+                "version = \"1.18.2\"\n",
+                "loader_version = \"0.16.0-beta.9\"\n",
+            ))
+            .unwrap()
+            .server
+            .server_type
+        );
+    }
+
+    #[test]
+    fn test_forge() {
+        assert_eq!(
+            ServerType::Forge {
+                forge_version: "37.0.103".to_owned(),
+            },
+            parse_config(concat!(
+                "type = \"forge\"\n",
+                "version = \"1.17.1\"\n",
+                "forge_version = \"37.0.103\"\n",
+            ))
+            .unwrap()
+            .server
+            .server_type
+        );
+    }
+
+    #[test]
+    fn test_neoforge() {
+        assert_eq!(
+            ServerType::NeoForge {
+                neoforge_version: "20.4.237".to_owned(),
+            },
+            parse_config(concat!(
+                "type = \"neoforge\"\n",
+                "version = \"1.20.4\"\n",
+                "neoforge_version = \"20.4.237\"\n",
+            ))
+            .unwrap()
+            .server
+            .server_type
+        );
+    }
+
+    #[test]
+    fn test_properties() {
+        let config: Config = toml::from_str(concat!(
+            "name = \"name\"\n",
+            "[server]\n",
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[properties]\n",
+            "view-distance = 12\n",
+            "pvp = false\n",
+            "motd = \"Hello world\"\n",
+        ))
+        .unwrap();
+
+        let mut expected = HashMap::new();
+        expected.insert("view-distance".to_owned(), PropertyValue::Integer(12));
+        expected.insert("pvp".to_owned(), PropertyValue::Boolean(false));
+        expected.insert(
+            "motd".to_owned(),
+            PropertyValue::String("Hello world".to_owned()),
+        );
+        assert_eq!(Some(expected), config.properties);
+    }
+
+    #[test]
+    fn test_properties_rejects_tables() {
+        assert!(toml::from_str::<Config>(concat!(
+            "name = \"name\"\n",
+            "[server]\n",
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[properties]\n",
+            "motd = [\"Hello\", \"world\"]\n",
+        ))
+        .is_err());
+    }
+
+    fn parse_world(world: &str) -> Result<Config, Box<dyn error::Error>> {
+        parse_config(&format!(
+            "type = \"vanilla\"\nversion = \"1.17.1\"\n[world]\n{}",
+            world
+        ))
+    }
+
+    #[test]
+    fn test_world_defaults() {
+        assert_eq!(default_world(), parse_world("").unwrap().world);
+    }
+
+    #[test]
+    fn test_world() {
+        assert_eq!(
+            World {
+                name: "survival".to_owned(),
+                seed: Some("seed".to_owned()),
+                gamemode: Gamemode::Adventure,
+                difficulty: Some(Difficulty::Hard),
+                hardcore: Some(true),
+                pvp: Some(false),
+                max_players: Some(8),
+                motd: Some("Welcome".to_owned()),
+                allow_flight: true,
+            },
+            parse_world(concat!(
+                "name = \"survival\"\n",
+                "seed = \"seed\"\n",
+                "gamemode = \"adventure\"\n",
+                "difficulty = \"hard\"\n",
+                "hardcore = true\n",
+                "pvp = false\n",
+                "max_players = 8\n",
+                "motd = \"Welcome\"\n",
+                "allow_flight = true\n",
+            ))
+            .unwrap()
+            .world
+        );
+    }
+
+    macro_rules! invalid_world_tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_world($value).is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_world_tests! {
+        test_world_invalid_gamemode: "gamemode = \"hardcore\"\n",
+        test_world_invalid_difficulty: "difficulty = \"extreme\"\n",
+        test_world_negative_max_players: "max_players = -1\n",
+        test_world_zero_max_players: "max_players = 0\n",
+        test_world_string_pvp: "pvp = \"yes\"\n",
+    }
+
+    #[test]
+    fn test_rcon_defaults() {
+        assert_eq!(Rcon::default(), parse_world("").unwrap().rcon);
+    }
+
+    #[test]
+    fn test_rcon() {
+        let config: Config = toml::from_str(concat!(
+            "name = \"name\"\n",
+            "[server]\n",
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[rcon]\n",
+            "password = \"secret\"\n",
+        ))
+        .unwrap();
+        assert_eq!(Some("secret".to_owned()), config.rcon.password);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_query_defaults() {
+        let config = parse_world("").unwrap();
+        assert_eq!(Query::default(), config.query);
+        assert!(!config.query.enabled);
+        assert_eq!(25565, config.query_port());
+    }
+
+    #[test]
+    fn test_query() {
+        let config = parse_config(concat!(
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[query]\n",
+            "enabled = true\n",
+            "port = 25570\n",
+        ))
+        .unwrap();
+        assert!(config.query.enabled);
+        assert_eq!(25570, config.query_port());
+    }
+
+    macro_rules! invalid_query_tests {
+        ($($name:ident: $query:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_config(&format!(
+                    "type = \"vanilla\"\nversion = \"1.17.1\"\n{}",
+                    $query
+                ))
+                .is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_query_tests! {
+        test_query_port_zero: "[query]\nenabled = true\nport = 0\n",
+        test_query_port_out_of_range: "[query]\nport = 70000\n",
+        test_query_extra_port_clash: "[query]\nenabled = true\n[[container.ports]]\ncontainer = 19132\nhost = 25565\nprotocol = \"udp\"\n",
+        test_query_container_port_clash: "[query]\nenabled = true\n[[container.ports]]\ncontainer = 25565\nhost = 25570\nprotocol = \"udp\"\n",
+    }
+
+    #[test]
+    fn test_notifications_defaults() {
+        let config = parse_world("").unwrap();
+        assert_eq!(Notifications::default(), config.notifications);
+    }
+
+    #[test]
+    fn test_notifications() {
+        let config = parse_config(concat!(
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[[notifications.webhooks]]\n",
+            "url = \"https://example.com/hook\"\n",
+            "[[notifications.webhooks]]\n",
+            "url = \"https://discord.com/api/webhooks/1/token\"\n",
+            "format = \"discord\"\n",
+            "events = [\"chat\", \"death\"]\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            vec![
+                Webhook {
+                    url: "https://example.com/hook".to_owned(),
+                    format: WebhookFormat::Json,
+                    events: None,
+                },
+                Webhook {
+                    url: "https://discord.com/api/webhooks/1/token".to_owned(),
+                    format: WebhookFormat::Discord,
+                    events: Some(vec!["chat".to_owned(), "death".to_owned()]),
+                },
+            ],
+            config.notifications.webhooks
+        );
+    }
+
+    macro_rules! webhook_subscribed_tests {
+        ($($name:ident: $events:expr, $event:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let webhook = Webhook {
+                    url: "https://example.com/hook".to_owned(),
+                    format: WebhookFormat::Json,
+                    events: $events.map(|events: Vec<&str>| events.into_iter().map(str::to_owned).collect()),
+                };
+                assert_eq!($expected, webhook.is_subscribed($event));
+            }
+        )*
+        }
+    }
+
+    webhook_subscribed_tests! {
+        test_webhook_default_events: None, "join", true,
+        test_webhook_default_events_skip_chat: None, "chat", false,
+        test_webhook_events: Some(vec!["chat"]), "chat", true,
+        test_webhook_events_replace_defaults: Some(vec!["chat"]), "join", false,
+        test_webhook_no_events: Some(vec![]), "crash", false,
+    }
+
+    macro_rules! invalid_notifications_tests {
+        ($($name:ident: $notifications:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_config(&format!(
+                    "type = \"vanilla\"\nversion = \"1.17.1\"\n[[notifications.webhooks]]\n{}",
+                    $notifications
+                ))
+                .is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_notifications_tests! {
+        test_webhook_missing_url: "format = \"slack\"\n",
+        test_webhook_invalid_url: "url = \"example.com/hook\"\n",
+        test_webhook_invalid_format: "url = \"https://example.com/hook\"\nformat = \"teams\"\n",
+        test_webhook_unknown_event: "url = \"https://example.com/hook\"\nevents = [\"joined\"]\n",
+    }
+
+    macro_rules! invalid_rcon_password_tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let rcon = Rcon {
+                    password: Some($value.to_owned()),
+                };
+                assert!(rcon.validate().is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_rcon_password_tests! {
+        test_rcon_empty_password: "",
+        test_rcon_password_with_space: "pass word",
+    }
+
+    #[test]
+    fn test_runtime_defaults() {
+        let runtime = parse_world("").unwrap().runtime;
+        assert_eq!(RuntimeType::Docker, runtime.runtime_type);
+        assert_eq!(Runtime::default(), runtime);
+    }
+
+    #[test]
+    fn test_runtime() {
+        let config: Config = toml::from_str(concat!(
+            "name = \"name\"\n",
+            "[server]\n",
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[runtime]\n",
+            "type = \"podman\"\n",
+            "host = \"ssh://steve@gamebox\"\n",
+            "data_path = \"/srv/minecraft\"\n",
+        ))
+        .unwrap();
+        assert_eq!(RuntimeType::Podman, config.runtime.runtime_type);
+        assert_eq!(Some("ssh://steve@gamebox".to_owned()), config.runtime.host);
+        assert_eq!(Some("/srv/minecraft".to_owned()), config.runtime.data_path);
+        assert!(config.validate().is_ok());
+    }
+
+    macro_rules! invalid_runtime_tests {
+        ($($name:ident: $runtime:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!($runtime.validate().is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_runtime_tests! {
+        test_runtime_host_and_context: Runtime {
+            host: Some("tcp://gamebox:2376".to_owned()),
+            context: Some("gamebox".to_owned()),
+            ..Runtime::default()
+        },
+        test_runtime_podman_context: Runtime {
+            runtime_type: RuntimeType::Podman,
+            context: Some("gamebox".to_owned()),
+            ..Runtime::default()
+        },
+        test_runtime_relative_data_path: Runtime {
+            data_path: Some("server".to_owned()),
+            ..Runtime::default()
+        },
+    }
+
+    #[test]
+    fn test_runtime_type_from_str() {
+        assert_eq!(Ok(RuntimeType::Docker), "docker".parse());
+        assert_eq!(Ok(RuntimeType::Podman), "podman".parse());
+        assert!("lxc".parse::<RuntimeType>().is_err());
+    }
+
+    #[test]
+    fn test_container_defaults() {
+        let container = parse_world("").unwrap().container;
+        assert_eq!("docker.io/itzg/minecraft-server", container.image);
+        assert_eq!(None, container.tag);
+        assert_eq!(None, container.digest);
+    }
+
+    #[test]
+    fn test_container() {
+        let config: Config = toml::from_str(concat!(
+            "name = \"name\"\n",
+            "[server]\n",
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[container]\n",
+            "image = \"registry.example.com:5000/minecraft-server\"\n",
+            "tag = \"2024.6.1-java21\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            "registry.example.com:5000/minecraft-server",
+            config.container.image
+        );
+        assert_eq!(Some("2024.6.1-java21".to_owned()), config.container.tag);
+        assert!(config.validate().is_ok());
+    }
+
+    macro_rules! invalid_container_tests {
+        ($($name:ident: $image:expr, $tag:expr, $digest:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let container = Container {
+                    image: $image.to_owned(),
+                    tag: $tag.map(|tag: &str| tag.to_owned()),
+                    digest: $digest.map(|digest: &str| digest.to_owned()),
+                    ..Container::default()
+                };
+                assert!(container.validate().is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_container_tests! {
+        test_container_empty_image: "", None, None,
+        test_container_image_with_tag: "itzg/minecraft-server:java17", None, None,
+        test_container_image_with_digest: "itzg/minecraft-server@sha256:abc", None, None,
+        test_container_tag_and_digest: "itzg/minecraft-server", Some("java17"), Some(concat!("sha256:", "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")),
+        test_container_short_digest: "itzg/minecraft-server", None, Some("sha256:abc"),
+        test_container_digest_without_algorithm: "itzg/minecraft-server", None, Some("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"),
+    }
+
+    #[test]
+    fn test_container_resources() {
+        let config = parse_config(concat!(
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "memory = \"2G\"\n",
+            "jvm_opts = \"-XX:+UseG1GC\"\n",
+            "use_aikar_flags = true\n",
+            "[container.resources]\n",
+            "cpus = 1.5\n",
+            "memory = \"3g\"\n",
+            "memory_reservation = \"2g\"\n",
+            "pids_limit = 512\n",
+        ))
+        .unwrap();
+        assert_eq!(Some("-XX:+UseG1GC".to_owned()), config.server.jvm_opts);
+        assert!(config.server.use_aikar_flags);
+        assert_eq!(
+            Resources {
+                cpus: Some(1.5),
+                memory: Some("3g".to_owned()),
+                memory_reservation: Some("2g".to_owned()),
+                pids_limit: Some(512),
+            },
+            config.container.resources
+        );
+    }
+
+    macro_rules! parse_memory_size_tests {
+        ($($name:ident: $size:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, parse_memory_size($size));
+            }
+        )*
+        }
+    }
+
+    parse_memory_size_tests! {
+        test_parse_memory_size_bytes: "1024", Some(1024),
+        test_parse_memory_size_kilobytes: "512k", Some(512 << 10),
+        test_parse_memory_size_megabytes: "512M", Some(512 << 20),
+        test_parse_memory_size_gigabytes: "4g", Some(4 << 30),
+        test_parse_memory_size_gigabytes_suffix: "4GB", Some(4 << 30),
+        test_parse_memory_size_terabytes: "1t", Some(1 << 40),
+        test_parse_memory_size_empty: "", None,
+        test_parse_memory_size_unit_only: "g", None,
+        test_parse_memory_size_fraction: "1.5g", None,
+        test_parse_memory_size_unknown_unit: "4x", None,
+        test_parse_memory_size_overflow: "99999999999t", None,
+    }
+
+    macro_rules! heap_size_tests {
+        ($($name:ident: $memory:expr, $jvm_opts:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let server = Server {
+                    memory: $memory.map(|memory: &str| memory.to_owned()),
+                    jvm_opts: $jvm_opts.map(|jvm_opts: &str| jvm_opts.to_owned()),
+                    ..Server::default()
+                };
+                assert_eq!($expected, server.heap_size());
+            }
+        )*
+        }
+    }
+
+    heap_size_tests! {
+        test_heap_size_default: None, None, "1G",
+        test_heap_size_memory: Some("4G"), None, "4G",
+        test_heap_size_jvm_opts: Some("4G"), Some("-Xms1G -Xmx6G"), "6G",
+        test_heap_size_last_jvm_opt: None, Some("-Xmx2G -Xmx3G"), "3G",
+        test_heap_size_jvm_opts_without_xmx: Some("4G"), Some("-XX:+UseG1GC"), "4G",
+    }
+
+    macro_rules! invalid_resources_tests {
+        ($($name:ident: $server:expr, $resources:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_config(&format!(
+                    "type = \"vanilla\"\nversion = \"1.17.1\"\n{}[container.resources]\n{}",
+                    $server, $resources
+                ))
+                .is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_resources_tests! {
+        test_resources_zero_cpus: "", "cpus = 0.0\n",
+        test_resources_zero_pids_limit: "", "pids_limit = 0\n",
+        test_resources_invalid_memory: "", "memory = \"lots\"\n",
+        test_resources_zero_memory: "", "memory = \"0\"\n",
+        test_resources_reservation_exceeds_memory: "", "memory = \"2G\"\nmemory_reservation = \"3G\"\n",
+        test_resources_default_heap_exceeds_memory: "", "memory = \"512M\"\n",
+        test_resources_heap_exceeds_memory: "memory = \"4G\"\n", "memory = \"3G\"\n",
+        test_resources_jvm_opts_heap_exceeds_memory: "memory = \"2G\"\njvm_opts = \"-Xmx4G\"\n", "memory = \"3G\"\n",
+        test_resources_invalid_heap: "memory = \"a lot\"\n", "memory = \"3G\"\n",
+    }
+
+    #[test]
+    fn test_container_extras() {
+        let config = parse_config(concat!(
+            "type = \"vanilla\"\n",
+            "version = \"1.17.1\"\n",
+            "[container]\n",
+            "restart_policy = \"unless-stopped\"\n",
+            "[[container.ports]]\n",
+            "container = 24454\n",
+            "protocol = \"udp\"\n",
+            "[[container.ports]]\n",
+            "container = 8123\n",
+            "host = 8080\n",
+            "host_ip = \"127.0.0.1\"\n",
+            "[[container.volumes]]\n",
+            "source = \"/srv/resources\"\n",
+            "target = \"/data/resources\"\n",
+            "read_only = true\n",
+            "[container.env]\n",
+            "TZ = \"Europe/London\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            RestartPolicy::UnlessStopped,
+            config.container.restart_policy
+        );
+        assert_eq!(
+            vec![
+                Port {
+                    container: 24454,
+                    host: None,
+                    protocol: Protocol::Udp,
+                    host_ip: None,
+                },
+                Port {
+                    container: 8123,
+                    host: Some(8080),
+                    protocol: Protocol::Tcp,
+                    host_ip: Some("127.0.0.1".to_owned()),
+                },
+            ],
+            config.container.ports
+        );
+        assert_eq!(24454, config.container.ports[0].host_port());
+        assert_eq!(
+            vec![Volume {
+                source: "/srv/resources".to_owned(),
+                target: "/data/resources".to_owned(),
+                read_only: true,
+            }],
+            config.container.volumes
+        );
+        assert_eq!(
+            Some(&"Europe/London".to_owned()),
+            config.container.env.get("TZ")
+        );
+    }
+
+    #[test]
+    fn test_container_extras_defaults() {
+        let container = parse_world("").unwrap().container;
+        assert_eq!(RestartPolicy::Always, container.restart_policy);
+        assert!(container.ports.is_empty());
+        assert!(container.volumes.is_empty());
+        assert!(container.env.is_empty());
+    }
+
+    macro_rules! invalid_container_extras_tests {
+        ($($name:ident: $container:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_config(&format!(
+                    "type = \"vanilla\"\nversion = \"1.17.1\"\n[container]\n{}",
+                    $container
+                ))
+                .is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_container_extras_tests! {
+        test_container_unknown_restart_policy: "restart_policy = \"sometimes\"\n",
+        test_container_port_zero: "[[container.ports]]\ncontainer = 0\n",
+        test_container_port_out_of_range: "[[container.ports]]\ncontainer = 70000\n",
+        test_container_port_unknown_protocol: "[[container.ports]]\ncontainer = 8123\nprotocol = \"sctp\"\n",
+        test_container_port_game_port: "[[container.ports]]\ncontainer = 25565\nhost = 25566\n",
+        test_container_port_rcon_port: "[[container.ports]]\ncontainer = 25575\n",
+        test_container_port_host_game_port: "[[container.ports]]\ncontainer = 8123\nhost = 25565\n",
+        test_container_port_duplicate_host: "[[container.ports]]\ncontainer = 8123\n[[container.ports]]\ncontainer = 8124\nhost = 8123\n",
+        test_container_volume_relative_source: "[[container.volumes]]\nsource = \"./resources\"\ntarget = \"/data/resources\"\n",
+        test_container_volume_relative_target: "[[container.volumes]]\nsource = \"/srv/resources\"\ntarget = \"resources\"\n",
+        test_container_volume_data_target: "[[container.volumes]]\nsource = \"/srv/data\"\ntarget = \"/data\"\n",
+        test_container_volume_duplicate_target: "[[container.volumes]]\nsource = \"a\"\ntarget = \"/a\"\n[[container.volumes]]\nsource = \"b\"\ntarget = \"/a\"\n",
+        test_container_env_invalid_name: "[container.env]\n\"MY-VAR\" = \"value\"\n",
+        test_container_env_managed_name: "[container.env]\nRCON_PASSWORD = \"password\"\n",
+    }
+
+    macro_rules! server_type_display_tests {
+        ($($name:ident: $server_type:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, $server_type.to_string());
+            }
+        )*
+        }
+    }
+
+    server_type_display_tests! {
+        test_display_vanilla: ServerType::Vanilla, "Vanilla",
+        test_display_paper: ServerType::Paper { build: Some("196".to_owned()) }, "Paper",
+        test_display_fabric: ServerType::Fabric { loader_version: "0.11.6".to_owned(), installer_version: None }, "Fabric 0.11.6",
+        test_display_forge: ServerType::Forge { forge_version: "37.0.103".to_owned() }, "Forge 37.0.103",
+        test_display_neoforge: ServerType::NeoForge { neoforge_version: "20.4.237".to_owned() }, "NeoForge 20.4.237",
+    }
+
+    macro_rules! invalid_server_type_tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_config($value).is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_server_type_tests! {
+        test_fabric_missing_loader_version: "type = \"fabric\"\nversion = \"1.17.1\"\n",
+        test_fabric_misspelled_loader_version: "type = \"fabric\"\nversion = \"1.17.1\"\nloader_verison = \"0.11.6\"\n",
+        test_fabric_invalid_loader_version: "type = \"fabric\"\nversion = \"1.17.1\"\nloader_version = \"0.11,6\"\n",
+        test_fabric_empty_loader_version: "type = \"fabric\"\nversion = \"1.17.1\"\nloader_version = \"\"\n",
+        test_quilt_missing_loader_version: "type = \"quilt\"\nversion = \"1.18.2\"\n",
+        test_forge_missing_forge_version: "type = \"forge\"\nversion = \"1.17.1\"\n",
+        test_neoforge_invalid_neoforge_version: "type = \"neoforge\"\nversion = \"1.20.4\"\nneoforge_version = \"latest\"\n",
+        test_quilt_invalid_installer_version: "type = \"quilt\"\nversion = \"1.18.2\"\nloader_version = \"0.16.0\"\ninstaller_version = \"latest version\"\n",
+    }
 }
@@ -0,0 +1,337 @@
+use chrono::{DateTime, Utc};
+
+const INFO_LEVEL: &str = "INFO";
+const LEVELS: [&str; 5] = [INFO_LEVEL, "WARN", "ERROR", "FATAL", "DEBUG"];
+const MAX_PLAYER_NAME_LENGTH: usize = 16;
+
+// The start of each vanilla death message after the player's name, from the death.* keys of
+// the language file. A phrase also matches the longer messages it starts, e.g. "was killed"
+// matches "was killed by magic".
+const DEATH_PHRASES: &[&str] = &[
+    "was shot by",
+    "was pummeled by",
+    "was pricked to death",
+    "walked into a cactus",
+    "drowned",
+    "experienced kinetic energy",
+    "blew up",
+    "was blown up by",
+    "was killed",
+    "hit the ground too hard",
+    "fell from a high place",
+    "fell off",
+    "fell while climbing",
+    "fell out of the world",
+    "fell too far and was finished by",
+    "was doomed to fall",
+    "was impaled",
+    "was squashed",
+    "was skewered by a falling stalactite",
+    "went up in flames",
+    "walked into fire",
+    "burned to death",
+    "was burnt to a crisp",
+    "went off with a bang",
+    "tried to swim in lava",
+    "was struck by lightning",
+    "discovered the floor was lava",
+    "walked into the danger zone",
+    "froze to death",
+    "was frozen to death",
+    "was slain by",
+    "was fireballed by",
+    "was stung to death",
+    "was obliterated by a sonically-charged shriek",
+    "was smashed by",
+    "starved to death",
+    "suffocated in a wall",
+    "was squished too much",
+    "was poked to death by a sweet berry bush",
+    "was roasted in dragon's breath",
+    "was sniped by",
+    "left the confines of this world",
+    "didn't want to live in the same world as",
+    "withered away",
+    "died",
+];
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    Started,
+    Stopping,
+    Join { player: String },
+    Leave { player: String },
+    Chat { player: String, message: String },
+    Death { player: String, message: String },
+    Advancement { player: String, advancement: String },
+    Crash { report: Option<String> },
+}
+
+impl GameEvent {
+    // The name of the event, as used for the type in JSON and the notification events
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameEvent::Started => "started",
+            GameEvent::Stopping => "stopping",
+            GameEvent::Join { .. } => "join",
+            GameEvent::Leave { .. } => "leave",
+            GameEvent::Chat { .. } => "chat",
+            GameEvent::Death { .. } => "death",
+            GameEvent::Advancement { .. } => "advancement",
+            GameEvent::Crash { .. } => "crash",
+        }
+    }
+}
+
+impl std::fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEvent::Started => write!(f, "The server started"),
+            GameEvent::Stopping => write!(f, "The server is stopping"),
+            GameEvent::Join { player } => write!(f, "{} joined the game", player),
+            GameEvent::Leave { player } => write!(f, "{} left the game", player),
+            GameEvent::Chat { player, message } => write!(f, "<{}> {}", player, message),
+            GameEvent::Death { message, .. } => write!(f, "{}", message),
+            GameEvent::Advancement {
+                player,
+                advancement,
+            } => write!(f, "{} made the advancement [{}]", player, advancement),
+            GameEvent::Crash {
+                report: Some(report),
+            } => {
+                write!(f, "The server crashed, the report is in {}", report)
+            }
+            GameEvent::Crash { report: None } => write!(f, "The server crashed"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct TimedEvent {
+    pub time: Option<String>,
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
+
+impl TimedEvent {
+    pub fn new(time: Option<DateTime<Utc>>, event: GameEvent) -> TimedEvent {
+        TimedEvent {
+            time: time.map(|time| time.to_rfc3339()),
+            event,
+        }
+    }
+}
+
+// Splits a server log line into its level and message. Vanilla and Fabric lines look like
+// "[12:00:00] [Server thread/INFO]: message", Paper lines like "[12:00:00 INFO]: message" and
+// Forge lines add the logger, "[01Jun2024 12:00:00.000] [Server thread/INFO] [logger/]: message".
+fn split_log_line(line: &str) -> Option<(&'static str, &str)> {
+    if !line.starts_with('[') {
+        return None;
+    }
+    let end = line.find("]: ")?;
+    let (header, message) = (&line[..=end], &line[end + 3..]);
+    let level = LEVELS.iter().copied().find(|level| {
+        header.contains(&format!("/{}]", level)) || header.contains(&format!(" {}]", level))
+    })?;
+    Some((level, message))
+}
+
+fn is_player_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PLAYER_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Splits off the player name a message starts with, skipping the former name of a player who
+// has changed it, e.g. "Steve (formerly known as Bob) joined the game"
+fn split_player(message: &str) -> Option<(&str, &str)> {
+    let (player, rest) = message.split_once(' ')?;
+    if !is_player_name(player) {
+        return None;
+    }
+    let rest = match rest.strip_prefix("(formerly known as ") {
+        Some(rest) => rest.split_once(") ")?.1,
+        None => rest,
+    };
+    Some((player, rest))
+}
+
+fn parse_chat(message: &str) -> Option<GameEvent> {
+    let message = message.strip_prefix("[Not Secure] ").unwrap_or(message);
+    let (player, text) = message.strip_prefix('<')?.split_once("> ")?;
+    if !is_player_name(player) {
+        return None;
+    }
+    Some(GameEvent::Chat {
+        player: player.to_owned(),
+        message: text.to_owned(),
+    })
+}
+
+fn parse_player_message(message: &str) -> Option<GameEvent> {
+    let (player, rest) = split_player(message)?;
+    let player = player.to_owned();
+    match rest {
+        "joined the game" => return Some(GameEvent::Join { player }),
+        "left the game" => return Some(GameEvent::Leave { player }),
+        _ => (),
+    }
+
+    for kind in [
+        "has made the advancement [",
+        "has completed the challenge [",
+        "has reached the goal [",
+    ] {
+        if let Some(advancement) = rest.strip_prefix(kind) {
+            return Some(GameEvent::Advancement {
+                player,
+                advancement: advancement.strip_suffix(']')?.to_owned(),
+            });
+        }
+    }
+
+    let is_death = DEATH_PHRASES.iter().any(|phrase| {
+        rest.strip_prefix(phrase)
+            .is_some_and(|end| end.is_empty() || end.starts_with(' '))
+    });
+    if is_death {
+        return Some(GameEvent::Death {
+            player,
+            message: message.to_owned(),
+        });
+    }
+
+    None
+}
+
+// Returns the game event a line of the server log records, if any
+pub fn parse_log_line(line: &str) -> Option<GameEvent> {
+    let (level, message) = split_log_line(line)?;
+
+    // Crashes are logged as errors, so they are checked before filtering to info messages
+    if let Some(report) = message.strip_prefix("This crash report has been saved to: ") {
+        return Some(GameEvent::Crash {
+            report: Some(report.trim().to_owned()),
+        });
+    }
+    if message.starts_with("We were unable to save this crash report to disk") {
+        return Some(GameEvent::Crash { report: None });
+    }
+
+    // Game events are only logged as info messages
+    if level != INFO_LEVEL {
+        return None;
+    }
+    if message.starts_with("Done (") && message.contains(")! For help, type") {
+        return Some(GameEvent::Started);
+    }
+    if message == "Stopping server" {
+        return Some(GameEvent::Stopping);
+    }
+
+    // Chat is checked first so players can't fake other events by sending their messages
+    parse_chat(message).or_else(|| parse_player_message(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each fixture is a log with the events it should produce, one JSON object per line
+    macro_rules! fixture_tests {
+        ($($name:ident: $fixture:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let log = include_str!(concat!("../fixtures/logs/", $fixture, ".log"));
+                let expected = include_str!(concat!("../fixtures/logs/", $fixture, ".jsonl"));
+
+                let events: Vec<String> = log
+                    .lines()
+                    .filter_map(parse_log_line)
+                    .map(|event| serde_json::to_string(&event).unwrap())
+                    .collect();
+                assert_eq!(expected.lines().collect::<Vec<&str>>(), events);
+            }
+        )*
+        }
+    }
+
+    fixture_tests! {
+        test_fixture_vanilla: "vanilla",
+        test_fixture_paper: "paper",
+        test_fixture_forge: "forge",
+        test_fixture_crash: "crash",
+    }
+
+    macro_rules! parse_log_line_tests {
+        ($($name:ident: $line:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, parse_log_line($line));
+            }
+        )*
+        }
+    }
+
+    parse_log_line_tests! {
+        test_parse_no_header: "Steve joined the game", None,
+        test_parse_init: "[init] Starting the Minecraft server...", None,
+        test_parse_warn_level: "[12:00:00] [Server thread/WARN]: Steve joined the game", None,
+        test_parse_invalid_player: "[12:00:00] [Server thread/INFO]: [Steve] joined the game", None,
+        test_parse_long_player: "[12:00:00] [Server thread/INFO]: ABCDEFGHIJKLMNOPQ joined the game", None,
+        test_parse_death_prefix_of_word: "[12:00:00] [Server thread/INFO]: Steve diedd", None,
+        test_parse_advancement_unterminated: "[12:00:00] [Server thread/INFO]: Steve has made the advancement [Stone Age", None,
+        test_parse_chat_invalid_player: "[12:00:00] [Server thread/INFO]: <Ste ve> hi", None,
+        test_parse_death_without_cause: "[12:00:00] [Server thread/INFO]: Steve died", Some(GameEvent::Death { player: "Steve".to_owned(), message: "Steve died".to_owned() }),
+    }
+
+    macro_rules! display_tests {
+        ($($name:ident: $event:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, $event.to_string());
+            }
+        )*
+        }
+    }
+
+    display_tests! {
+        test_display_join: GameEvent::Join { player: "Steve".to_owned() }, "Steve joined the game",
+        test_display_chat: GameEvent::Chat { player: "Steve".to_owned(), message: "hi".to_owned() }, "<Steve> hi",
+        test_display_advancement: GameEvent::Advancement { player: "Steve".to_owned(), advancement: "Stone Age".to_owned() }, "Steve made the advancement [Stone Age]",
+        test_display_crash: GameEvent::Crash { report: None }, "The server crashed",
+    }
+
+    #[test]
+    fn test_kind_matches_json_type() {
+        let event = GameEvent::Advancement {
+            player: "Steve".to_owned(),
+            advancement: "Stone Age".to_owned(),
+        };
+
+        assert_eq!(
+            serde_json::Value::from(event.kind()),
+            serde_json::to_value(&event).unwrap()["type"]
+        );
+    }
+
+    #[test]
+    fn test_timed_event_json() {
+        let event = TimedEvent::new(
+            Some("2024-06-01T12:00:00Z".parse().unwrap()),
+            GameEvent::Join {
+                player: "Steve".to_owned(),
+            },
+        );
+
+        assert_eq!(
+            r#"{"time":"2024-06-01T12:00:00+00:00","type":"join","player":"Steve"}"#,
+            serde_json::to_string(&event).unwrap()
+        );
+    }
+}
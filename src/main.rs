@@ -1,14 +1,18 @@
+use std::error::Error;
 use structopt::StructOpt;
 
 mod args;
 mod config;
+mod events;
 mod logging;
 mod providers;
 mod subcommands;
 
+const CONFIG_EXIT_CODE: i32 = 2;
+
 #[tokio::main]
 async fn main() {
-    let args = args::Args::from_args();
+    let mut args = args::Args::from_args();
 
     match logging::init_logging(args.debug, args.quiet, args.verbosity) {
         Err(err) => {
@@ -18,14 +22,42 @@ async fn main() {
         _ => (),
     }
 
-    let config = match config::load_config(&args.file) {
+    let mut config = match config::load_config(&args.file) {
         Ok(config) => config,
         Err(err) => {
-            log::error!("Unable to load config file: {}", err);
-            std::process::exit(1);
+            log::error!("Unable to load config file");
+            log_error_chain(err.as_ref());
+            std::process::exit(CONFIG_EXIT_CODE);
         }
     };
 
+    if let Some(runtime_type) = args.runtime {
+        config.runtime.runtime_type = runtime_type;
+    }
+    if let Some(host) = &args.host {
+        config.runtime.host = Some(host.to_owned());
+        config.runtime.context = None;
+    }
+    if let Some(context) = &args.context {
+        config.runtime.context = Some(context.to_owned());
+        config.runtime.host = None;
+    }
+    if let Err(err) = config.validate() {
+        log::error!("Invalid runtime options");
+        log_error_chain(&err);
+        std::process::exit(CONFIG_EXIT_CODE);
+    }
+
+    // Paths given to subcommands are relative to where the command was run, not the config file
+    if let args::SubCommand::Exec {
+        input: Some(input), ..
+    } = &mut args.subcommand
+    {
+        if let Ok(current_dir) = std::env::current_dir() {
+            *input = current_dir.join(&input).to_string_lossy().into_owned();
+        }
+    }
+
     if let Some(parent_dir) = std::path::Path::new(&args.file).parent() {
         log::trace!(
             "Changing to config file directory: {}",
@@ -45,26 +77,106 @@ async fn main() {
         std::env::current_dir().unwrap().display()
     );
 
-    let subcommands = match subcommands::new_from_defaults() {
-        Ok(subcommands) => subcommands,
-        Err(_) => {
-            log::error!("Encountered an unexpected error");
-            std::process::exit(1);
+    let result = match config.runtime.runtime_type {
+        config::RuntimeType::Docker => {
+            providers::backends::docker::new_from_defaults(&config.runtime)
+                .map_err(connection_error)
+                .and_then(|backend| {
+                    let subcommands = subcommands::new_from_defaults(backend);
+                    run_subcommand(&subcommands, args.subcommand, &config)
+                })
+        }
+        config::RuntimeType::Podman => {
+            providers::backends::podman::new_from_defaults(&config.runtime)
+                .map_err(connection_error)
+                .and_then(|backend| {
+                    let subcommands = subcommands::new_from_defaults(backend);
+                    run_subcommand(&subcommands, args.subcommand, &config)
+                })
         }
     };
 
-    let _ = match args.subcommand {
-        args::SubCommand::Up => subcommands.up(&config),
-        args::SubCommand::Down => subcommands.down(&config),
-        args::SubCommand::Create => subcommands.create(&config),
-        args::SubCommand::Destroy => subcommands.destroy(&config),
-        args::SubCommand::Start => subcommands.start(&config),
-        args::SubCommand::Stop => subcommands.stop(&config),
-        args::SubCommand::Status => subcommands.status(&config),
-        args::SubCommand::Console => subcommands.console(&config),
-        args::SubCommand::Logs => subcommands.logs(&config),
+    match result {
+        Ok(0) => (),
+        Ok(exit_code) => std::process::exit(exit_code),
+        Err(err) => {
+            log_error_chain(&err);
+            std::process::exit(err.exit_code());
+        }
+    }
+}
+
+fn connection_error(
+    err: providers::backends::connection::ConnectionError,
+) -> subcommands::CommandError {
+    subcommands::CommandError::Container {
+        message: "Failed to connect to the container runtime".to_owned(),
+        source: err.into(),
+    }
+}
+
+fn run_subcommand<
+    T1: providers::container::ContainerProvider,
+    T2: providers::file::FileProvider,
+    T3: providers::game::GameProvider,
+    T4: providers::notification::NotificationProvider,
+>(
+    subcommands: &subcommands::SubCommands<T1, T2, T3, T4>,
+    subcommand: args::SubCommand,
+    config: &config::Config,
+) -> Result<i32, subcommands::CommandError> {
+    let result = match subcommand {
+        args::SubCommand::Up(up_args) => subcommands.up(
+            config,
+            up_args.recreate,
+            up_args.stop.warn,
+            up_args.stop.timeout,
+            up_args.wait.timeout(),
+        ),
+        args::SubCommand::Down(stop_args) => {
+            subcommands.down(config, stop_args.warn, stop_args.timeout)
+        }
+        args::SubCommand::Create => subcommands.create(config),
+        args::SubCommand::Destroy => subcommands.destroy(config),
+        args::SubCommand::Start(wait_args) => subcommands.start(config, wait_args.timeout()),
+        args::SubCommand::Stop(stop_args) => {
+            subcommands.stop(config, stop_args.warn, stop_args.timeout)
+        }
+        args::SubCommand::Status(status_args) => {
+            return subcommands.status(config, status_args.format);
+        }
+        args::SubCommand::Ping(ping_args) => {
+            subcommands.ping(config, ping_args.address.as_ref(), ping_args.format)
+        }
+        args::SubCommand::Players { format } => subcommands.players(config, format),
+        args::SubCommand::Console => subcommands.console(config),
+        args::SubCommand::Exec { commands, input } => {
+            subcommands.exec(config, &commands, input.as_deref())
+        }
+        args::SubCommand::Logs(logs_args) => subcommands.logs(config, &logs_args),
+        args::SubCommand::Events(events_args) => subcommands.events(config, &events_args),
+        args::SubCommand::Watch => subcommands.watch(config),
         args::SubCommand::Datapacks(args::DatapackCommand::Sync) => {
-            subcommands.sync_datapacks(&config)
+            subcommands.sync_datapacks(config)
         }
+        args::SubCommand::Mods(args::ModCommand::Sync) => subcommands.sync_mods(config),
+        args::SubCommand::Plugins(args::PluginCommand::Sync) => subcommands.sync_plugins(config),
+        args::SubCommand::Backup { command } => match command {
+            None | Some(args::BackupCommand::Create) => subcommands.backup(config),
+            Some(args::BackupCommand::List) => subcommands.list_backups(config),
+            Some(args::BackupCommand::Prune) => subcommands.prune_backups(config),
+            Some(args::BackupCommand::Restore { id }) => subcommands.restore_backup(config, &id),
+        },
     };
+
+    result.map(|()| 0)
+}
+
+fn log_error_chain(err: &dyn Error) {
+    log::error!("{}", err);
+    let mut source = err.source();
+    while let Some(cause) = source {
+        log::error!("  Caused by: {}", cause);
+        source = cause.source();
+    }
 }
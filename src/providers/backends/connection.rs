@@ -0,0 +1,526 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::{Runtime, RuntimeType};
+
+const TIMEOUT: u64 = 120;
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+const PODMAN_SOCKET: &str = "/run/podman/podman.sock";
+const DEFAULT_CONTEXT: &str = "default";
+const TUNNEL_TIMEOUT: Duration = Duration::from_secs(10);
+const TUNNEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectionError {
+    #[error("The host \"{0}\" is not supported, use a unix://, tcp:// or ssh:// address")]
+    UnsupportedHost(String),
+    #[error("The docker context {0} could not be found")]
+    ContextNotFound(String),
+    #[error("Unable to read the docker context {name}")]
+    InvalidContext {
+        name: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Unable to read the docker config \"{}\"", .path.display())]
+    InvalidDockerConfig {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Unable to open an ssh tunnel to {destination}")]
+    Tunnel {
+        destination: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Unable to connect to {host}")]
+    Connect {
+        host: String,
+        #[source]
+        source: bollard::errors::Error,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Endpoint {
+    Unix(String),
+    Tcp {
+        address: String,
+        certs: Option<PathBuf>,
+    },
+    Ssh {
+        destination: String,
+        port: Option<String>,
+        socket: Option<String>,
+    },
+}
+
+impl Endpoint {
+    pub fn is_remote(&self) -> bool {
+        !matches!(self, Endpoint::Unix(_))
+    }
+}
+
+pub struct Connection {
+    pub client: bollard::Docker,
+    pub remote: bool,
+    pub tunnel: Option<SshTunnel>,
+}
+
+pub struct SshTunnel {
+    child: Child,
+    directory: PathBuf,
+    socket: PathBuf,
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_file(&self.socket);
+        let _ = fs::remove_dir(&self.directory);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerConfigFile {
+    current_context: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContextMeta {
+    name: String,
+    endpoints: HashMap<String, ContextEndpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContextEndpoint {
+    host: Option<String>,
+}
+
+pub fn connect(runtime: &Runtime) -> Result<Connection, ConnectionError> {
+    let endpoint = resolve_endpoint(runtime)?;
+    log::trace!("Connecting to {:?}", endpoint);
+
+    let remote = endpoint.is_remote();
+    let (client, tunnel) = match endpoint {
+        Endpoint::Unix(socket) => (connect_with_unix(&socket)?, None),
+        Endpoint::Tcp {
+            address,
+            certs: None,
+        } => (
+            bollard::Docker::connect_with_http(&address, TIMEOUT, bollard::API_DEFAULT_VERSION)
+                .map_err(connect_error(&address))?,
+            None,
+        ),
+        Endpoint::Tcp {
+            address,
+            certs: Some(certs),
+        } => (
+            bollard::Docker::connect_with_ssl(
+                &address,
+                &certs.join("key.pem"),
+                &certs.join("cert.pem"),
+                &certs.join("ca.pem"),
+                TIMEOUT,
+                bollard::API_DEFAULT_VERSION,
+            )
+            .map_err(connect_error(&address))?,
+            None,
+        ),
+        Endpoint::Ssh {
+            destination,
+            port,
+            socket,
+        } => {
+            let remote_socket = socket.unwrap_or_else(|| match runtime.runtime_type {
+                RuntimeType::Docker => DOCKER_SOCKET.to_owned(),
+                RuntimeType::Podman => PODMAN_SOCKET.to_owned(),
+            });
+            let tunnel = open_tunnel(&destination, port.as_deref(), &remote_socket)?;
+            (
+                connect_with_unix(&tunnel.socket.to_string_lossy())?,
+                Some(tunnel),
+            )
+        }
+    };
+
+    Ok(Connection {
+        client,
+        remote,
+        tunnel,
+    })
+}
+
+fn connect_error(host: &str) -> impl FnOnce(bollard::errors::Error) -> ConnectionError {
+    let host = host.to_owned();
+    move |source| ConnectionError::Connect { host, source }
+}
+
+fn connect_with_unix(socket: &str) -> Result<bollard::Docker, ConnectionError> {
+    bollard::Docker::connect_with_unix(socket, TIMEOUT, bollard::API_DEFAULT_VERSION)
+        .map_err(connect_error(socket))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+pub fn resolve_endpoint(runtime: &Runtime) -> Result<Endpoint, ConnectionError> {
+    if let Some(host) = &runtime.host {
+        return parse_host(host, runtime.cert_path.as_ref().map(PathBuf::from));
+    }
+
+    match runtime.runtime_type {
+        RuntimeType::Docker => {
+            if let Some(context) = &runtime.context {
+                return resolve_context(context);
+            }
+            if let Some(host) = env_var("DOCKER_HOST") {
+                return parse_host(&host, docker_env_certs());
+            }
+            let context = match env_var("DOCKER_CONTEXT") {
+                Some(context) => Some(context),
+                None => current_context()?,
+            };
+            match context {
+                Some(context) if context != DEFAULT_CONTEXT => resolve_context(&context),
+                _ => Ok(Endpoint::Unix(DOCKER_SOCKET.to_owned())),
+            }
+        }
+        RuntimeType::Podman => match env_var("CONTAINER_HOST") {
+            Some(host) => parse_host(&host, None),
+            None => Ok(Endpoint::Unix(default_podman_socket())),
+        },
+    }
+}
+
+fn parse_host(host: &str, certs: Option<PathBuf>) -> Result<Endpoint, ConnectionError> {
+    if let Some(socket) = host.strip_prefix("unix://") {
+        Ok(Endpoint::Unix(socket.to_owned()))
+    } else if host.starts_with('/') {
+        Ok(Endpoint::Unix(host.to_owned()))
+    } else if host.starts_with("tcp://") {
+        Ok(Endpoint::Tcp {
+            address: host.to_owned(),
+            certs,
+        })
+    } else if let Some(address) = host.strip_prefix("ssh://") {
+        let (authority, socket) = match address.find('/') {
+            Some(index) => (&address[..index], Some(address[index..].to_owned())),
+            None => (address, None),
+        };
+        let (user, host_port) = match authority.rfind('@') {
+            Some(index) => (Some(&authority[..index]), &authority[index + 1..]),
+            None => (None, authority),
+        };
+        let (hostname, port) = match host_port.split_once(':') {
+            Some((hostname, port)) => (hostname, Some(port.to_owned())),
+            None => (host_port, None),
+        };
+        if hostname.is_empty() {
+            return Err(ConnectionError::UnsupportedHost(host.to_owned()));
+        }
+
+        Ok(Endpoint::Ssh {
+            destination: match user {
+                Some(user) => format!("{}@{}", user, hostname),
+                None => hostname.to_owned(),
+            },
+            port,
+            socket: socket.filter(|socket| socket != "/"),
+        })
+    } else {
+        Err(ConnectionError::UnsupportedHost(host.to_owned()))
+    }
+}
+
+fn docker_config_dir() -> PathBuf {
+    match env_var("DOCKER_CONFIG") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env_var("HOME").unwrap_or_default()).join(".docker"),
+    }
+}
+
+fn docker_env_certs() -> Option<PathBuf> {
+    env_var("DOCKER_TLS_VERIFY")?;
+    Some(match env_var("DOCKER_CERT_PATH") {
+        Some(cert_path) => PathBuf::from(cert_path),
+        None => docker_config_dir(),
+    })
+}
+
+fn current_context() -> Result<Option<String>, ConnectionError> {
+    let path = docker_config_dir().join("config.json");
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(parse_current_context(&contents)
+            .map_err(|source| ConnectionError::InvalidDockerConfig { path, source })?),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_current_context(contents: &str) -> Result<Option<String>, serde_json::Error> {
+    let config: DockerConfigFile = serde_json::from_str(contents)?;
+    Ok(config.current_context)
+}
+
+fn parse_context_host(contents: &str) -> Result<(String, Option<String>), serde_json::Error> {
+    let meta: ContextMeta = serde_json::from_str(contents)?;
+    let host = meta
+        .endpoints
+        .get("docker")
+        .and_then(|endpoint| endpoint.host.clone());
+    Ok((meta.name, host))
+}
+
+// Context directories are named after a hash of the context name, so each one is read until
+// the name matches rather than computing the hash
+fn resolve_context(name: &str) -> Result<Endpoint, ConnectionError> {
+    let contexts_dir = docker_config_dir().join("contexts");
+    let entries = fs::read_dir(contexts_dir.join("meta"))
+        .map_err(|_| ConnectionError::ContextNotFound(name.to_owned()))?;
+
+    for entry in entries.flatten() {
+        let contents = match fs::read_to_string(entry.path().join("meta.json")) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        let (context_name, host) =
+            parse_context_host(&contents).map_err(|source| ConnectionError::InvalidContext {
+                name: name.to_owned(),
+                source,
+            })?;
+        if context_name != name {
+            continue;
+        }
+
+        let host = host.ok_or_else(|| ConnectionError::ContextNotFound(name.to_owned()))?;
+        let certs = contexts_dir
+            .join("tls")
+            .join(entry.file_name())
+            .join("docker");
+        return parse_host(&host, Some(certs).filter(|certs| certs.is_dir()));
+    }
+
+    Err(ConnectionError::ContextNotFound(name.to_owned()))
+}
+
+fn default_podman_socket() -> String {
+    // Rootless podman listens in the user's runtime directory rather than /run
+    if let Some(runtime_dir) = env_var("XDG_RUNTIME_DIR") {
+        let socket = Path::new(&runtime_dir).join("podman").join("podman.sock");
+        if socket.exists() {
+            return socket.to_string_lossy().to_string();
+        }
+    }
+
+    PODMAN_SOCKET.to_owned()
+}
+
+// The forwarded socket gives full access to the runtime, so it is created in a new directory
+// only the current user can open, preferring the user's runtime directory over the shared /tmp
+fn create_tunnel_directory() -> io::Result<PathBuf> {
+    let parent = env_var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir);
+    let directory = parent.join(format!(
+        "minecraft-compose-{}-{:016x}",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+
+    // create fails if the path already exists, so the directory can't have been made by anyone
+    // else
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&directory)?;
+    Ok(directory)
+}
+
+// bollard cannot talk ssh, so the remote socket is forwarded to a local one with OpenSSH
+fn open_tunnel(
+    destination: &str,
+    port: Option<&str>,
+    remote_socket: &str,
+) -> Result<SshTunnel, ConnectionError> {
+    let tunnel_error = |source| ConnectionError::Tunnel {
+        destination: destination.to_owned(),
+        source,
+    };
+    let directory = create_tunnel_directory().map_err(tunnel_error)?;
+    let socket = directory.join("docker.sock");
+
+    log::trace!(
+        "Forwarding {} on {} to {}",
+        remote_socket,
+        destination,
+        socket.display()
+    );
+    let mut command = Command::new("ssh");
+    command
+        .args([
+            "-N",
+            "-T",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ExitOnForwardFailure=yes",
+        ])
+        .arg("-L")
+        .arg(format!("{}:{}", socket.display(), remote_socket));
+    if let Some(port) = port {
+        command.args(["-p", port]);
+    }
+    let child = match command
+        .arg(destination)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            let _ = fs::remove_dir(&directory);
+            return Err(tunnel_error(err));
+        }
+    };
+    let mut tunnel = SshTunnel {
+        child,
+        directory,
+        socket,
+    };
+
+    // ssh is checked before the socket so a socket is never used once ssh has exited
+    let started = Instant::now();
+    loop {
+        if let Some(status) = tunnel.child.try_wait().map_err(tunnel_error)? {
+            return Err(tunnel_error(io::Error::other(format!(
+                "ssh exited with {}",
+                status
+            ))));
+        }
+        if tunnel.socket.exists() {
+            return Ok(tunnel);
+        }
+        if started.elapsed() > TUNNEL_TIMEOUT {
+            return Err(tunnel_error(io::Error::from(io::ErrorKind::TimedOut)));
+        }
+        std::thread::sleep(TUNNEL_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! parse_host_tests {
+        ($($name:ident: $host:expr, $expected:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, parse_host($host, None).unwrap());
+            }
+        )*
+        }
+    }
+
+    parse_host_tests! {
+        test_parse_host_unix: "unix:///var/run/docker.sock", Endpoint::Unix("/var/run/docker.sock".to_owned());
+        test_parse_host_path: "/run/podman/podman.sock", Endpoint::Unix("/run/podman/podman.sock".to_owned());
+        test_parse_host_tcp: "tcp://gamebox:2376", Endpoint::Tcp { address: "tcp://gamebox:2376".to_owned(), certs: None };
+        test_parse_host_ssh: "ssh://gamebox", Endpoint::Ssh { destination: "gamebox".to_owned(), port: None, socket: None };
+        test_parse_host_ssh_user_port: "ssh://steve@gamebox:2222", Endpoint::Ssh { destination: "steve@gamebox".to_owned(), port: Some("2222".to_owned()), socket: None };
+        test_parse_host_ssh_socket: "ssh://steve@gamebox/run/user/1000/podman/podman.sock", Endpoint::Ssh { destination: "steve@gamebox".to_owned(), port: None, socket: Some("/run/user/1000/podman/podman.sock".to_owned()) };
+    }
+
+    macro_rules! invalid_host_tests {
+        ($($name:ident: $host:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(matches!(
+                    parse_host($host, None),
+                    Err(ConnectionError::UnsupportedHost(_))
+                ));
+            }
+        )*
+        }
+    }
+
+    invalid_host_tests! {
+        test_parse_host_http: "http://gamebox:2375",
+        test_parse_host_npipe: "npipe:////./pipe/docker_engine",
+        test_parse_host_ssh_no_hostname: "ssh://steve@",
+        test_parse_host_relative: "docker.sock",
+    }
+
+    #[test]
+    fn test_parse_host_tcp_certs() {
+        assert_eq!(
+            Endpoint::Tcp {
+                address: "tcp://gamebox:2376".to_owned(),
+                certs: Some(PathBuf::from("certs")),
+            },
+            parse_host("tcp://gamebox:2376", Some(PathBuf::from("certs"))).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_create_tunnel_directory() {
+        let directory = create_tunnel_directory().unwrap();
+        assert!(directory.is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&directory).unwrap().permissions().mode();
+            assert_eq!(0o700, mode & 0o777);
+        }
+        let other = create_tunnel_directory().unwrap();
+        assert_ne!(directory, other);
+        fs::remove_dir(&directory).unwrap();
+        fs::remove_dir(&other).unwrap();
+    }
+
+    #[test]
+    fn test_is_remote() {
+        assert!(!Endpoint::Unix("/var/run/docker.sock".to_owned()).is_remote());
+        assert!(parse_host("tcp://gamebox:2376", None).unwrap().is_remote());
+        assert!(parse_host("ssh://gamebox", None).unwrap().is_remote());
+    }
+
+    #[test]
+    fn test_parse_current_context() {
+        assert_eq!(
+            Some("gamebox".to_owned()),
+            parse_current_context(r#"{"auths": {}, "currentContext": "gamebox"}"#).unwrap()
+        );
+        assert_eq!(None, parse_current_context(r#"{"auths": {}}"#).unwrap());
+        assert!(parse_current_context("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_context_host() {
+        assert_eq!(
+            ("gamebox".to_owned(), Some("ssh://steve@gamebox".to_owned())),
+            parse_context_host(concat!(
+                r#"{"Name": "gamebox", "Metadata": {}, "#,
+                r#""Endpoints": {"docker": {"Host": "ssh://steve@gamebox", "SkipTLSVerify": false}}}"#
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            ("empty".to_owned(), None),
+            parse_context_host(r#"{"Name": "empty", "Metadata": {}, "Endpoints": {}}"#).unwrap()
+        );
+    }
+}
@@ -1,40 +1,98 @@
-use bollard::container::{Config, CreateContainerOptions, LogsOptions};
+use bollard::container::{
+    Config, CreateContainerOptions, LogOutput, LogsOptions, StopContainerOptions,
+};
 use bollard::errors;
 use bollard::models::{ContainerInspectResponse, Image};
 
+use crate::config::Runtime;
+use crate::providers::backends::connection::{self, Connection, ConnectionError, SshTunnel};
+
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct DockerError {
+    pub message: String,
+    #[source]
+    pub source: errors::Error,
+}
+
+fn error(message: String) -> impl FnOnce(errors::Error) -> DockerError {
+    move |source| DockerError { message, source }
+}
+
 #[derive(Debug)]
 pub enum InspectResult {
     Ok(ContainerInspectResponse),
     NotFound,
 }
 
+#[derive(Debug, Default, PartialEq)]
+pub struct LogOptions {
+    pub follow: bool,
+    // None returns the whole log
+    pub tail: Option<u64>,
+    // Unix timestamps, 0 leaves that end of the log open
+    pub since: i64,
+    pub until: i64,
+    pub timestamps: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogEntry {
+    Stdout(String),
+    Stderr(String),
+}
+
+impl LogEntry {
+    pub fn text(&self) -> &str {
+        match self {
+            LogEntry::Stdout(text) | LogEntry::Stderr(text) => text,
+        }
+    }
+}
+
 #[cfg_attr(test, mockall::automock)]
 pub trait DockerBackend {
-    fn download_image(&self, image: &str, tag: &str) -> Result<(), ()>;
-    fn inspect_image(&self, image: &str) -> Result<Image, ()>;
-    fn create_container(&self, name: &str, container_config: Config<String>) -> Result<(), ()>;
-    fn delete_container(&self, name: &str) -> Result<(), ()>;
-    fn start_container(&self, name: &str) -> Result<(), ()>;
-    fn stop_container(&self, name: &str) -> Result<(), ()>;
-    fn inspect_container(&self, name: &str) -> Result<InspectResult, ()>;
-    fn get_container_logs(&self, name: &str) -> Box<dyn Iterator<Item = Result<String, ()>>>;
+    fn download_image(&self, image: &str, tag: &str) -> Result<(), DockerError>;
+    fn inspect_image(&self, image: &str) -> Result<Image, DockerError>;
+    fn create_container(
+        &self,
+        name: &str,
+        container_config: Config<String>,
+    ) -> Result<(), DockerError>;
+    fn delete_container(&self, name: &str) -> Result<(), DockerError>;
+    fn start_container(&self, name: &str) -> Result<(), DockerError>;
+    fn stop_container(&self, name: &str, timeout: u64) -> Result<(), DockerError>;
+    fn inspect_container(&self, name: &str) -> Result<InspectResult, DockerError>;
+    fn run_healthcheck(&self, name: &str) -> Result<(), DockerError>;
+    fn get_container_logs(
+        &self,
+        name: &str,
+        options: LogOptions,
+    ) -> Box<dyn Iterator<Item = Result<LogEntry, DockerError>>>;
+    fn is_rootless(&self) -> Result<bool, DockerError>;
+    fn is_remote(&self) -> bool;
 }
 
 pub struct DockerBackendImpl {
     docker: bollard::Docker,
+    remote: bool,
+    _tunnel: Option<SshTunnel>,
+}
+
+pub fn new_from_defaults(runtime: &Runtime) -> Result<DockerBackendImpl, ConnectionError> {
+    Ok(new_from_connection(connection::connect(runtime)?))
 }
 
-pub fn new_from_defaults() -> Result<DockerBackendImpl, ()> {
-    Ok(DockerBackendImpl {
-        docker: bollard::Docker::connect_with_local_defaults().or_else(|err| {
-            log::debug!("Failed to connect to docker: {}", err);
-            Err(())
-        })?,
-    })
+pub fn new_from_connection(connection: Connection) -> DockerBackendImpl {
+    DockerBackendImpl {
+        docker: connection.client,
+        remote: connection.remote,
+        _tunnel: connection.tunnel,
+    }
 }
 
 impl DockerBackend for DockerBackendImpl {
-    fn download_image(&self, image: &str, tag: &str) -> Result<(), ()> {
+    fn download_image(&self, image: &str, tag: &str) -> Result<(), DockerError> {
         log::trace!("Downloading image {}:{}", image, tag);
 
         for item in futures::executor::block_on_stream(self.docker.create_image(
@@ -46,26 +104,22 @@ impl DockerBackend for DockerBackendImpl {
             None,
             None,
         )) {
-            match item {
-                Ok(_) => Ok(()),
-                Err(err) => {
-                    log::trace!("Unable to download image {}:{}: {}", image, tag, err);
-                    Err(())
-                }
-            }?;
+            item.map_err(error(format!("Unable to download image {}:{}", image, tag)))?;
         }
 
         Ok(())
     }
 
-    fn inspect_image(&self, image: &str) -> Result<Image, ()> {
-        futures::executor::block_on(self.docker.inspect_image(&image)).or_else(|err| {
-            log::trace!("Unable to inspect image: {}", err);
-            Err(())
-        })
+    fn inspect_image(&self, image: &str) -> Result<Image, DockerError> {
+        futures::executor::block_on(self.docker.inspect_image(&image))
+            .map_err(error(format!("Unable to inspect image {}", image)))
     }
 
-    fn create_container(&self, name: &str, container_config: Config<String>) -> Result<(), ()> {
+    fn create_container(
+        &self,
+        name: &str,
+        container_config: Config<String>,
+    ) -> Result<(), DockerError> {
         log::trace!("Creating container {}", name);
         match futures::executor::block_on(self.docker.create_container(
             Some(CreateContainerOptions { name: name }),
@@ -77,74 +131,103 @@ impl DockerBackend for DockerBackendImpl {
                 });
                 Ok(())
             }
-            Err(err) => {
-                log::trace!("Unable to create container {}: {}", name, err);
-                Err(())
-            }
+            Err(err) => Err(error(format!("Unable to create container {}", name))(err)),
         }
     }
 
-    fn delete_container(&self, name: &str) -> Result<(), ()> {
+    fn delete_container(&self, name: &str) -> Result<(), DockerError> {
         log::trace!("Deleting container {}", name);
-        futures::executor::block_on(self.docker.remove_container(name, None)).or_else(|err| {
-            log::trace!("Unable to delete container {}: {}", name, err);
-            Err(())
-        })
+        futures::executor::block_on(self.docker.remove_container(name, None))
+            .map_err(error(format!("Unable to delete container {}", name)))
     }
 
-    fn start_container(&self, name: &str) -> Result<(), ()> {
+    fn start_container(&self, name: &str) -> Result<(), DockerError> {
         log::trace!("Starting container {}", name);
-        futures::executor::block_on(self.docker.start_container::<String>(name, None)).or_else(
-            |err| {
-                log::trace!("Unable to start container {}: {}", name, err);
-                Err(())
-            },
-        )
+        futures::executor::block_on(self.docker.start_container::<String>(name, None))
+            .map_err(error(format!("Unable to start container {}", name)))
     }
 
-    fn stop_container(&self, name: &str) -> Result<(), ()> {
-        log::trace!("Stopping container {}", name);
-        futures::executor::block_on(self.docker.stop_container(name, None)).or_else(|err| {
-            log::trace!("Unable to start container {}: {}", name, err);
-            Err(())
-        })
+    fn stop_container(&self, name: &str, timeout: u64) -> Result<(), DockerError> {
+        log::trace!("Stopping container {} with a timeout of {}s", name, timeout);
+        futures::executor::block_on(
+            self.docker
+                .stop_container(name, Some(StopContainerOptions { t: timeout as i64 })),
+        )
+        .map_err(error(format!("Unable to stop container {}", name)))
     }
 
-    fn inspect_container(&self, name: &str) -> Result<InspectResult, ()> {
+    fn inspect_container(&self, name: &str) -> Result<InspectResult, DockerError> {
         log::trace!("Inspecting container {}", name);
         match futures::executor::block_on(self.docker.inspect_container(name, None)) {
             Ok(result) => Ok(InspectResult::Ok(result)),
             Err(errors::Error::DockerResponseNotFoundError { message: _ }) => {
                 Ok(InspectResult::NotFound)
             }
-            Err(err) => {
-                log::trace!("Unable to inspect container {}: {}", name, err);
-                Err(())
-            }
+            Err(err) => Err(error(format!("Unable to inspect container {}", name))(err)),
         }
     }
 
-    fn get_container_logs(&self, name: &str) -> Box<dyn Iterator<Item = Result<String, ()>>> {
-        log::trace!("Getting logs for container {}", name);
+    // Docker runs the image's healthcheck itself
+    fn run_healthcheck(&self, _name: &str) -> Result<(), DockerError> {
+        Ok(())
+    }
+
+    fn get_container_logs(
+        &self,
+        name: &str,
+        options: LogOptions,
+    ) -> Box<dyn Iterator<Item = Result<LogEntry, DockerError>>> {
+        log::trace!("Getting logs for container {} with {:?}", name, options);
+        let container_name = name.to_owned();
 
         Box::new(
-            futures::executor::block_on_stream(self.docker.logs(
-                &name,
-                Some(LogsOptions {
-                    follow: true,
-                    stdout: true,
-                    stderr: true,
-                    tail: "10",
-                    ..std::default::Default::default()
-                }),
-            ))
-            .map(|line_result| match line_result {
-                Ok(output) => Ok(String::from_utf8_lossy(&output.into_bytes()).to_string()),
-                Err(err) => {
-                    log::trace!("Error reading logs: {}", err);
-                    Err(())
-                }
+            futures::executor::block_on_stream(
+                self.docker.logs(
+                    &name,
+                    Some(LogsOptions {
+                        follow: options.follow,
+                        stdout: true,
+                        stderr: true,
+                        since: options.since,
+                        until: options.until,
+                        timestamps: options.timestamps,
+                        tail: options
+                            .tail
+                            .map_or_else(|| "all".to_owned(), |tail| tail.to_string()),
+                    }),
+                ),
+            )
+            .map(move |line_result| {
+                line_result
+                    .map(|output| match output {
+                        LogOutput::StdErr { message } => {
+                            LogEntry::Stderr(String::from_utf8_lossy(&message).to_string())
+                        }
+                        // Containers with a tty only have console output
+                        output => LogEntry::Stdout(
+                            String::from_utf8_lossy(&output.into_bytes()).to_string(),
+                        ),
+                    })
+                    .map_err(error(format!(
+                        "Unable to read the logs of container {}",
+                        container_name
+                    )))
             }),
         )
     }
+
+    fn is_rootless(&self) -> Result<bool, DockerError> {
+        let info = futures::executor::block_on(self.docker.info())
+            .map_err(error("Unable to get the container runtime info".to_owned()))?;
+
+        Ok(info
+            .security_options
+            .unwrap_or_default()
+            .iter()
+            .any(|option| option == "name=rootless"))
+    }
+
+    fn is_remote(&self) -> bool {
+        self.remote
+    }
 }
@@ -1,118 +1,230 @@
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use std::fs;
+use std::io::{self, Write};
 use std::path;
 
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct FilesystemError {
+    pub message: String,
+    #[source]
+    pub source: io::Error,
+}
+
+fn error(message: String) -> impl FnOnce(io::Error) -> FilesystemError {
+    move |source| FilesystemError { message, source }
+}
+
 #[cfg_attr(test, mockall::automock)]
 pub trait FilesystemBackend {
-    fn canonicalize_path(&self, path: &path::PathBuf) -> Result<path::PathBuf, ()>;
+    fn canonicalize_path(&self, path: &path::PathBuf) -> Result<path::PathBuf, FilesystemError>;
     fn directory_exists(&self, directory_path: &path::PathBuf) -> bool;
-    fn create_directory(&self, directory_path: &path::PathBuf) -> Result<(), ()>;
-    fn read_directory(&self, directory_path: &path::PathBuf) -> Result<Vec<path::PathBuf>, ()>;
+    fn create_directory(&self, directory_path: &path::PathBuf) -> Result<(), FilesystemError>;
+    fn read_directory(
+        &self,
+        directory_path: &path::PathBuf,
+    ) -> Result<Vec<path::PathBuf>, FilesystemError>;
     fn file_exists(&self, file_path: &path::PathBuf) -> bool;
-    fn read_file(&self, file_path: &path::PathBuf) -> Result<String, ()>;
-    fn write_file(&self, file_path: &path::PathBuf, contents: &str) -> Result<(), ()>;
-    fn copy_file(&self, src: &path::PathBuf, dest: &path::PathBuf) -> Result<(), ()>;
-    fn delete_file(&self, file_path: &path::PathBuf) -> Result<(), ()>;
+    fn read_file(&self, file_path: &path::PathBuf) -> Result<String, FilesystemError>;
+    fn write_file(&self, file_path: &path::PathBuf, contents: &str) -> Result<(), FilesystemError>;
+    fn write_private_file(
+        &self,
+        file_path: &path::Path,
+        contents: &str,
+    ) -> Result<(), FilesystemError>;
+    fn copy_file(&self, src: &path::PathBuf, dest: &path::PathBuf) -> Result<(), FilesystemError>;
+    fn delete_file(&self, file_path: &path::PathBuf) -> Result<(), FilesystemError>;
+    fn delete_directory(&self, directory_path: &path::Path) -> Result<(), FilesystemError>;
+    fn create_archive(
+        &self,
+        archive_path: &path::Path,
+        base_path: &path::Path,
+        entries: &[path::PathBuf],
+    ) -> Result<(), FilesystemError>;
+    fn extract_archive(
+        &self,
+        archive_path: &path::Path,
+        dest_path: &path::Path,
+    ) -> Result<(), FilesystemError>;
+    fn rename_path(&self, src: &path::Path, dest: &path::Path) -> Result<(), FilesystemError>;
 }
 
 pub struct FilesystemBackendImpl {}
 
 impl FilesystemBackend for FilesystemBackendImpl {
-    fn canonicalize_path(&self, path: &path::PathBuf) -> Result<path::PathBuf, ()> {
-        std::fs::canonicalize(path).or_else(|err| {
-            log::trace!("Unable to canonicalize path {}: {}", path.display(), err);
-            Err(())
-        })
+    fn canonicalize_path(&self, path: &path::PathBuf) -> Result<path::PathBuf, FilesystemError> {
+        std::fs::canonicalize(path).map_err(error(format!(
+            "Unable to canonicalize path \"{}\"",
+            path.display()
+        )))
     }
 
     fn directory_exists(&self, directory_path: &path::PathBuf) -> bool {
         directory_path.is_dir()
     }
 
-    fn create_directory(&self, directory_path: &path::PathBuf) -> Result<(), ()> {
-        fs::create_dir_all(directory_path).or_else(|err| {
-            log::trace!(
-                "Unable to create folder {}: {}",
-                directory_path.display(),
-                err
-            );
-            Err(())
-        })
+    fn create_directory(&self, directory_path: &path::PathBuf) -> Result<(), FilesystemError> {
+        fs::create_dir_all(directory_path).map_err(error(format!(
+            "Unable to create folder \"{}\"",
+            directory_path.display()
+        )))
     }
 
-    fn read_directory(&self, directory_path: &path::PathBuf) -> Result<Vec<path::PathBuf>, ()> {
-        match fs::read_dir(directory_path) {
-            Ok(read_dir) => {
-                let mut entries = vec![];
-                for dir_entry_result in read_dir {
-                    match dir_entry_result {
-                        Ok(dir_entry) => {
-                            entries.push(dir_entry.path());
-                        }
-                        Err(err) => {
-                            log::trace!(
-                                "Unable to read directory {}: {}",
-                                directory_path.display(),
-                                err
-                            );
-                            return Err(());
-                        }
-                    }
-                }
-
-                Ok(entries)
-            }
-            Err(err) => {
-                log::trace!(
-                    "Unable to read directory {}: {}",
-                    directory_path.display(),
-                    err
-                );
-                Err(())
-            }
-        }
+    fn read_directory(
+        &self,
+        directory_path: &path::PathBuf,
+    ) -> Result<Vec<path::PathBuf>, FilesystemError> {
+        fs::read_dir(directory_path)
+            .and_then(|read_dir| {
+                read_dir
+                    .map(|dir_entry_result| dir_entry_result.map(|dir_entry| dir_entry.path()))
+                    .collect()
+            })
+            .map_err(error(format!(
+                "Unable to read directory \"{}\"",
+                directory_path.display()
+            )))
     }
 
     fn file_exists(&self, file_path: &path::PathBuf) -> bool {
         file_path.is_file()
     }
 
-    fn read_file(&self, file_path: &path::PathBuf) -> Result<String, ()> {
-        fs::read_to_string(file_path).or_else(|err| {
-            log::trace!("Unable to read file {}: {}", file_path.display(), err);
-            Err(())
-        })
+    fn read_file(&self, file_path: &path::PathBuf) -> Result<String, FilesystemError> {
+        fs::read_to_string(file_path).map_err(error(format!(
+            "Unable to read file \"{}\"",
+            file_path.display()
+        )))
+    }
+
+    fn write_file(&self, file_path: &path::PathBuf, contents: &str) -> Result<(), FilesystemError> {
+        fs::write(file_path, contents).map_err(error(format!(
+            "Unable to write file \"{}\"",
+            file_path.display()
+        )))
+    }
+
+    fn write_private_file(
+        &self,
+        file_path: &path::Path,
+        contents: &str,
+    ) -> Result<(), FilesystemError> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options
+            .open(file_path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .map_err(error(format!(
+                "Unable to write file \"{}\"",
+                file_path.display()
+            )))
+    }
+
+    fn copy_file(&self, src: &path::PathBuf, dest: &path::PathBuf) -> Result<(), FilesystemError> {
+        fs::copy(src, dest).map(|_| ()).map_err(error(format!(
+            "Unable to copy file \"{}\" to \"{}\"",
+            src.display(),
+            dest.display()
+        )))
     }
 
-    fn write_file(&self, file_path: &path::PathBuf, contents: &str) -> Result<(), ()> {
-        fs::write(file_path, contents).or_else(|err| {
-            log::trace!("Unable to write file {}: {}", file_path.display(), err);
-            Err(())
-        })
+    fn delete_file(&self, file_path: &path::PathBuf) -> Result<(), FilesystemError> {
+        fs::remove_file(file_path).map_err(error(format!(
+            "Unable to delete file \"{}\"",
+            file_path.display()
+        )))
     }
 
-    fn copy_file(&self, src: &path::PathBuf, dest: &path::PathBuf) -> Result<(), ()> {
-        match fs::copy(src, dest) {
-            Ok(_) => Ok(()),
-            Err(err) => {
-                log::trace!(
-                    "Unable to copy file \"{}\" to \"{}\": {}",
-                    src.display(),
-                    dest.display(),
-                    err
-                );
-                Err(())
-            }
+    fn delete_directory(&self, directory_path: &path::Path) -> Result<(), FilesystemError> {
+        fs::remove_dir_all(directory_path).map_err(error(format!(
+            "Unable to delete folder \"{}\"",
+            directory_path.display()
+        )))
+    }
+
+    fn create_archive(
+        &self,
+        archive_path: &path::Path,
+        base_path: &path::Path,
+        entries: &[path::PathBuf],
+    ) -> Result<(), FilesystemError> {
+        // create_new never replaces an existing archive, so only an archive created here is
+        // removed when writing it fails
+        let archive_file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(archive_path)
+            .map_err(error(format!(
+                "Unable to create archive \"{}\"",
+                archive_path.display()
+            )))?;
+
+        let result = write_archive(archive_file, archive_path, base_path, entries);
+        if result.is_err() {
+            let _ = fs::remove_file(archive_path);
         }
+        result
+    }
+
+    fn extract_archive(
+        &self,
+        archive_path: &path::Path,
+        dest_path: &path::Path,
+    ) -> Result<(), FilesystemError> {
+        let archive_file = fs::File::open(archive_path).map_err(error(format!(
+            "Unable to open archive \"{}\"",
+            archive_path.display()
+        )))?;
+
+        tar::Archive::new(GzDecoder::new(archive_file))
+            .unpack(dest_path)
+            .map_err(error(format!(
+                "Unable to extract archive \"{}\" to \"{}\"",
+                archive_path.display(),
+                dest_path.display()
+            )))
     }
 
-    fn delete_file(&self, file_path: &path::PathBuf) -> Result<(), ()> {
-        fs::remove_file(file_path).or_else(|err| {
-            log::trace!("Unable to delete file \"{}\": {}", file_path.display(), err);
-            Err(())
-        })
+    fn rename_path(&self, src: &path::Path, dest: &path::Path) -> Result<(), FilesystemError> {
+        fs::rename(src, dest).map_err(error(format!(
+            "Unable to rename \"{}\" to \"{}\"",
+            src.display(),
+            dest.display()
+        )))
     }
 }
 
+fn write_archive(
+    archive_file: fs::File,
+    archive_path: &path::Path,
+    base_path: &path::Path,
+    entries: &[path::PathBuf],
+) -> Result<(), FilesystemError> {
+    let mut builder = tar::Builder::new(GzEncoder::new(archive_file, Compression::default()));
+    for entry in entries {
+        builder
+            .append_dir_all(entry, base_path.join(entry))
+            .map_err(error(format!(
+                "Unable to add \"{}\" to archive \"{}\"",
+                entry.display(),
+                archive_path.display()
+            )))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(error(format!(
+            "Unable to write archive \"{}\"",
+            archive_path.display()
+        )))?;
+
+    Ok(())
+}
+
 pub fn new_from_defaults() -> FilesystemBackendImpl {
     FilesystemBackendImpl {}
 }
@@ -1,4 +1,9 @@
 use rustyline::{config::Config, error::ReadlineError, Editor};
+use std::io::{IsTerminal, Read};
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unable to read input")]
+pub struct InputError(#[source] pub ReadlineError);
 
 #[cfg_attr(test, mockall::automock(type Output = MockInputBackend;))]
 pub trait InputBackendFactory {
@@ -27,7 +32,9 @@ pub enum InputResponse {
 
 #[cfg_attr(test, mockall::automock)]
 pub trait InputBackend {
-    fn get_line(&mut self, prompt: &str) -> Result<InputResponse, ()>;
+    fn get_line(&mut self, prompt: &str) -> Result<InputResponse, InputError>;
+    fn is_terminal(&self) -> bool;
+    fn read_to_end(&mut self) -> Result<String, InputError>;
 }
 
 pub struct InputBackendImpl {
@@ -35,16 +42,25 @@ pub struct InputBackendImpl {
 }
 
 impl InputBackend for InputBackendImpl {
-    fn get_line(&mut self, prompt: &str) -> Result<InputResponse, ()> {
+    fn get_line(&mut self, prompt: &str) -> Result<InputResponse, InputError> {
         match self.editor.readline(prompt) {
             Ok(line) => Ok(InputResponse::Input(line)),
             Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
                 Ok(InputResponse::EndOfInput)
             }
-            Err(err) => {
-                log::trace!("Encountered a readline error: {}", err);
-                Err(())
-            }
+            Err(err) => Err(InputError(err)),
         }
     }
+
+    fn is_terminal(&self) -> bool {
+        std::io::stdin().is_terminal()
+    }
+
+    fn read_to_end(&mut self) -> Result<String, InputError> {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|err| InputError(ReadlineError::Io(err)))?;
+        Ok(contents)
+    }
 }
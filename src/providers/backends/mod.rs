@@ -1,4 +1,9 @@
+pub mod connection;
 pub mod docker;
 pub mod filesystem;
 pub mod input;
+pub mod ping;
+pub mod podman;
+pub mod query;
 pub mod rcon;
+pub mod webhook;
@@ -0,0 +1,418 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+// Status responses are small JSON documents, the favicon being the largest part
+const MAX_PACKET_LENGTH: i32 = 1 << 21;
+// -1 asks the server to report its own version rather than check ours
+const HANDSHAKE_PROTOCOL_VERSION: i32 = -1;
+const STATUS_NEXT_STATE: i32 = 1;
+const STATUS_PACKET_ID: i32 = 0x00;
+const PING_PACKET_ID: i32 = 0x01;
+
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct PingError {
+    pub message: String,
+    #[source]
+    pub source: io::Error,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct ServerStatus {
+    pub version: String,
+    pub protocol: i32,
+    pub motd: String,
+    pub online: u32,
+    pub max: u32,
+    pub latency_ms: u64,
+}
+
+#[cfg_attr(test, mockall::automock)]
+pub trait PingBackend {
+    fn ping(&self, host: &str, port: u16) -> Result<ServerStatus, PingError>;
+}
+
+pub struct PingBackendImpl {
+    timeout: Duration,
+}
+
+pub fn new_from_defaults() -> PingBackendImpl {
+    PingBackendImpl {
+        timeout: Duration::from_secs(5),
+    }
+}
+
+impl PingBackend for PingBackendImpl {
+    fn ping(&self, host: &str, port: u16) -> Result<ServerStatus, PingError> {
+        log::trace!("Pinging {}:{}", host, port);
+        let error = |message: &str| {
+            let message = format!("{} {}:{}", message, host, port);
+            move |source| PingError { message, source }
+        };
+
+        let mut stream = self
+            .connect(host, port)
+            .map_err(error("Unable to connect to"))?;
+        let start = Instant::now();
+        let response = request_status(&mut stream, host, port).map_err(error("Failed to ping"))?;
+        let status_latency = start.elapsed();
+
+        // Some servers close the connection instead of answering the ping, so fall back to the
+        // time taken by the status request
+        let latency = match send_ping(&mut stream) {
+            Ok(latency) => latency,
+            Err(err) => {
+                log::debug!("No ping response from {}:{}: {}", host, port, err);
+                status_latency
+            }
+        };
+
+        parse_status(&response, latency).map_err(error("Invalid status response from"))
+    }
+}
+
+impl PingBackendImpl {
+    fn connect(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut last_error = None;
+        for address in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    return Ok(stream);
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the host has no addresses")
+        }))
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+fn write_var_int(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            buffer.push(value as u8);
+            return;
+        }
+        buffer.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_var_int(reader: &mut impl Read) -> io::Result<i32> {
+    let mut value = 0u32;
+    for position in 0..5 {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+        value |= u32::from(byte[0] & 0x7f) << (7 * position);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+
+    Err(invalid_data("VarInt is too long"))
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    write_var_int(buffer, value.len() as i32);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn write_packet(writer: &mut impl Write, packet_id: i32, data: &[u8]) -> io::Result<()> {
+    let mut body = vec![];
+    write_var_int(&mut body, packet_id);
+    body.extend_from_slice(data);
+
+    let mut packet = vec![];
+    write_var_int(&mut packet, body.len() as i32);
+    packet.extend(body);
+    writer.write_all(&packet)?;
+    writer.flush()
+}
+
+fn read_packet(reader: &mut impl Read) -> io::Result<(i32, Vec<u8>)> {
+    let length = read_var_int(reader)?;
+    if !(1..=MAX_PACKET_LENGTH).contains(&length) {
+        return Err(invalid_data("packet length is out of range"));
+    }
+
+    let mut body = vec![0u8; length as usize];
+    reader.read_exact(&mut body)?;
+    let mut body = io::Cursor::new(body);
+    let packet_id = read_var_int(&mut body)?;
+    let position = body.position() as usize;
+    Ok((packet_id, body.into_inner().split_off(position)))
+}
+
+fn request_status(stream: &mut TcpStream, host: &str, port: u16) -> io::Result<String> {
+    let mut handshake = vec![];
+    write_var_int(&mut handshake, HANDSHAKE_PROTOCOL_VERSION);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_var_int(&mut handshake, STATUS_NEXT_STATE);
+    write_packet(stream, STATUS_PACKET_ID, &handshake)?;
+    write_packet(stream, STATUS_PACKET_ID, &[])?;
+
+    let (packet_id, data) = read_packet(stream)?;
+    if packet_id != STATUS_PACKET_ID {
+        return Err(invalid_data("expected a status response"));
+    }
+
+    let mut data = io::Cursor::new(data);
+    let length = read_var_int(&mut data)?;
+    let mut response = vec![];
+    data.read_to_end(&mut response)?;
+    if length < 0 || length as usize != response.len() {
+        return Err(invalid_data("status response length does not match"));
+    }
+
+    String::from_utf8(response).map_err(|_| invalid_data("status response is not UTF-8"))
+}
+
+fn send_ping(stream: &mut TcpStream) -> io::Result<Duration> {
+    let payload = rand::random::<i64>().to_be_bytes();
+    let start = Instant::now();
+    write_packet(stream, PING_PACKET_ID, &payload)?;
+    let (packet_id, data) = read_packet(stream)?;
+    if packet_id != PING_PACKET_ID || data != payload {
+        return Err(invalid_data("expected a matching pong"));
+    }
+
+    Ok(start.elapsed())
+}
+
+// The description is either a plain string or a chat component whose text is split across
+// nested extra components, and either may contain legacy formatting codes
+fn description_text(description: &serde_json::Value) -> String {
+    let mut text = String::new();
+    match description {
+        serde_json::Value::String(value) => text.push_str(value),
+        serde_json::Value::Array(components) => {
+            components
+                .iter()
+                .for_each(|component| text.push_str(&description_text(component)));
+        }
+        serde_json::Value::Object(component) => {
+            if let Some(value) = component.get("text") {
+                text.push_str(&description_text(value));
+            }
+            if let Some(extra) = component.get("extra") {
+                text.push_str(&description_text(extra));
+            }
+        }
+        _ => (),
+    }
+
+    strip_formatting_codes(&text)
+}
+
+// Removes legacy formatting codes, which are a section sign followed by a code character
+pub fn strip_formatting_codes(text: &str) -> String {
+    let mut chars = text.chars();
+    let mut stripped = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{a7}' => {
+                chars.next();
+            }
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+fn parse_status(response: &str, latency: Duration) -> io::Result<ServerStatus> {
+    let status: serde_json::Value =
+        serde_json::from_str(response).map_err(|err| invalid_data(&err.to_string()))?;
+    let count = |field: &str| {
+        status["players"][field]
+            .as_u64()
+            .and_then(|count| u32::try_from(count).ok())
+            .ok_or_else(|| invalid_data(&format!("missing players.{}", field)))
+    };
+
+    Ok(ServerStatus {
+        version: status["version"]["name"]
+            .as_str()
+            .ok_or_else(|| invalid_data("missing version.name"))?
+            .to_owned(),
+        protocol: status["version"]["protocol"]
+            .as_i64()
+            .and_then(|protocol| i32::try_from(protocol).ok())
+            .ok_or_else(|| invalid_data("missing version.protocol"))?,
+        motd: description_text(&status["description"]),
+        online: count("online")?,
+        max: count("max")?,
+        latency_ms: latency.as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    macro_rules! var_int_tests {
+        ($($name:ident: $value:expr, $bytes:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut buffer = vec![];
+                write_var_int(&mut buffer, $value);
+                assert_eq!($bytes.to_vec(), buffer);
+                assert_eq!($value, read_var_int(&mut io::Cursor::new(buffer)).unwrap());
+            }
+        )*
+        }
+    }
+
+    var_int_tests! {
+        test_var_int_zero: 0, [0x00],
+        test_var_int_one_byte: 127, [0x7f],
+        test_var_int_two_bytes: 128, [0x80, 0x01],
+        test_var_int_port: 25565, [0xdd, 0xc7, 0x01],
+        test_var_int_max: i32::MAX, [0xff, 0xff, 0xff, 0xff, 0x07],
+        test_var_int_negative: -1, [0xff, 0xff, 0xff, 0xff, 0x0f],
+    }
+
+    #[test]
+    fn test_read_var_int_too_long() {
+        let mut reader = io::Cursor::new(vec![0xff; 6]);
+        assert!(read_var_int(&mut reader).is_err());
+    }
+
+    macro_rules! description_text_tests {
+        ($($name:ident: $description:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let description: serde_json::Value = serde_json::from_str($description).unwrap();
+                assert_eq!($expected, description_text(&description));
+            }
+        )*
+        }
+    }
+
+    description_text_tests! {
+        test_description_string: r#""A Minecraft Server""#, "A Minecraft Server",
+        test_description_formatting_codes: r#""§aGreen §lbold""#, "Green bold",
+        test_description_component: r#"{"text": "Hello"}"#, "Hello",
+        test_description_extra: r#"{"text": "", "extra": [{"text": "Hello ", "color": "gold"}, "world", {"text": "!", "extra": ["!"]}]}"#, "Hello world!!",
+        test_description_missing: "null", "",
+    }
+
+    macro_rules! invalid_status_tests {
+        ($($name:ident: $response:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_status($response, Duration::from_millis(1)).is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_status_tests! {
+        test_status_not_json: "not json",
+        test_status_missing_version: r#"{"players": {"online": 0, "max": 20}, "description": ""}"#,
+        test_status_missing_players: r#"{"version": {"name": "1.20.4", "protocol": 765}, "description": ""}"#,
+        test_status_negative_players: r#"{"version": {"name": "1.20.4", "protocol": 765}, "players": {"online": -1, "max": 20}}"#,
+    }
+
+    const STATUS_RESPONSE: &str = r#"{"version": {"name": "1.20.4", "protocol": 765}, "players": {"online": 2, "max": 20, "sample": []}, "description": {"text": "A Minecraft Server"}}"#;
+
+    // Accepts one connection, checks the handshake and answers the status request and, if
+    // answer_ping is set, the ping
+    fn start_fake_server(response: &'static str, answer_ping: bool) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let (packet_id, handshake) = read_packet(&mut stream).unwrap();
+            assert_eq!(STATUS_PACKET_ID, packet_id);
+            let mut expected_handshake = vec![];
+            write_var_int(&mut expected_handshake, HANDSHAKE_PROTOCOL_VERSION);
+            write_string(&mut expected_handshake, "127.0.0.1");
+            expected_handshake.extend_from_slice(&port.to_be_bytes());
+            write_var_int(&mut expected_handshake, STATUS_NEXT_STATE);
+            assert_eq!(expected_handshake, handshake);
+
+            assert_eq!(
+                (STATUS_PACKET_ID, vec![]),
+                read_packet(&mut stream).unwrap()
+            );
+            let mut data = vec![];
+            write_string(&mut data, response);
+            write_packet(&mut stream, STATUS_PACKET_ID, &data).unwrap();
+
+            if answer_ping {
+                let (packet_id, payload) = read_packet(&mut stream).unwrap();
+                assert_eq!(PING_PACKET_ID, packet_id);
+                write_packet(&mut stream, PING_PACKET_ID, &payload).unwrap();
+            }
+        });
+        port
+    }
+
+    fn get_ping_backend() -> PingBackendImpl {
+        PingBackendImpl {
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_ping() {
+        let port = start_fake_server(STATUS_RESPONSE, true);
+
+        let status = get_ping_backend().ping("127.0.0.1", port).unwrap();
+        assert_eq!(
+            ServerStatus {
+                version: "1.20.4".to_owned(),
+                protocol: 765,
+                motd: "A Minecraft Server".to_owned(),
+                online: 2,
+                max: 20,
+                latency_ms: status.latency_ms,
+            },
+            status
+        );
+    }
+
+    #[test]
+    fn test_ping_without_pong() {
+        let port = start_fake_server(STATUS_RESPONSE, false);
+
+        let status = get_ping_backend().ping("127.0.0.1", port).unwrap();
+        assert_eq!("1.20.4", status.version);
+        assert_eq!(2, status.online);
+    }
+
+    #[test]
+    fn test_ping_invalid_response() {
+        let port = start_fake_server(r#"{"description": "no version"}"#, true);
+
+        assert!(get_ping_backend().ping("127.0.0.1", port).is_err());
+    }
+
+    #[test]
+    fn test_ping_connection_refused() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        assert!(get_ping_backend().ping("127.0.0.1", port).is_err());
+    }
+}
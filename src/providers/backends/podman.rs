@@ -0,0 +1,338 @@
+use bollard::container::Config;
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::models::{HealthConfig, HealthStatusEnum, Image};
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use crate::config::Runtime;
+use crate::providers::backends::connection::{self, ConnectionError};
+use crate::providers::backends::docker::{
+    self, DockerBackend, DockerBackendImpl, DockerError, InspectResult, LogEntry, LogOptions,
+};
+
+// The defaults Docker uses when the image's healthcheck leaves them unset
+const DEFAULT_HEALTHCHECK_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_HEALTHCHECK_RETRIES: u32 = 3;
+
+pub struct PodmanBackendImpl {
+    podman: bollard::Docker,
+    docker: DockerBackendImpl,
+    health: RefCell<ManualHealth>,
+}
+
+// The results of the healthchecks run by run_healthcheck, following Docker's rules for when a
+// container becomes healthy or unhealthy
+#[derive(Debug, Default, PartialEq)]
+struct ManualHealth {
+    status: Option<HealthStatusEnum>,
+    failures: u32,
+    last_run: Option<Instant>,
+}
+
+impl ManualHealth {
+    // Failures during the start period don't count towards the retries
+    fn record(&mut self, passed: bool, in_start_period: bool, retries: u32) {
+        if passed {
+            self.status = Some(HealthStatusEnum::HEALTHY);
+            self.failures = 0;
+        } else if !in_start_period {
+            self.failures += 1;
+            if self.failures >= retries {
+                self.status = Some(HealthStatusEnum::UNHEALTHY);
+            }
+        }
+    }
+}
+
+pub fn new_from_defaults(runtime: &Runtime) -> Result<PodmanBackendImpl, ConnectionError> {
+    let connection = connection::connect(runtime)?;
+
+    Ok(PodmanBackendImpl {
+        podman: connection.client.clone(),
+        docker: docker::new_from_connection(connection),
+        health: RefCell::new(ManualHealth::default()),
+    })
+}
+
+fn healthcheck_command(test: &[String]) -> Option<Vec<String>> {
+    match test.split_first() {
+        Some((kind, args)) if kind == "CMD" && !args.is_empty() => Some(args.to_vec()),
+        Some((kind, args)) if kind == "CMD-SHELL" && !args.is_empty() => {
+            Some(vec!["/bin/sh".to_owned(), "-c".to_owned(), args.join(" ")])
+        }
+        _ => None,
+    }
+}
+
+// Zero means the value is inherited, which for these is Docker's default
+fn healthcheck_duration(nanoseconds: Option<i64>, default: Duration) -> Duration {
+    match nanoseconds {
+        Some(nanoseconds) if nanoseconds > 0 => Duration::from_nanos(nanoseconds as u64),
+        _ => default,
+    }
+}
+
+fn healthcheck_retries(healthcheck: &HealthConfig) -> u32 {
+    match healthcheck.retries {
+        Some(retries) if retries > 0 => retries as u32,
+        _ => DEFAULT_HEALTHCHECK_RETRIES,
+    }
+}
+
+fn in_start_period(started_at: Option<&str>, start_period: Duration) -> bool {
+    let running_for = started_at
+        .and_then(|started_at| chrono::DateTime::parse_from_rfc3339(started_at).ok())
+        .and_then(|started_at| {
+            (chrono::Utc::now() - started_at.with_timezone(&chrono::Utc))
+                .to_std()
+                .ok()
+        });
+    matches!(running_for, Some(running_for) if running_for < start_period)
+}
+
+impl PodmanBackendImpl {
+    fn exec_healthcheck(&self, name: &str, command: Vec<String>) -> Result<bool, DockerError> {
+        log::trace!("Running healthcheck {:?} in container {}", command, name);
+        let error = |source| DockerError {
+            message: format!("Unable to run the healthcheck in container {}", name),
+            source,
+        };
+
+        let exec = futures::executor::block_on(self.podman.create_exec(
+            name,
+            CreateExecOptions {
+                cmd: Some(command),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                ..std::default::Default::default()
+            },
+        ))
+        .map_err(error)?;
+
+        if let StartExecResults::Attached { output, .. } =
+            futures::executor::block_on(self.podman.start_exec(&exec.id, None)).map_err(error)?
+        {
+            for item in futures::executor::block_on_stream(output) {
+                item.map_err(error)?;
+            }
+        }
+
+        let result =
+            futures::executor::block_on(self.podman.inspect_exec(&exec.id)).map_err(error)?;
+        Ok(result.exit_code == Some(0))
+    }
+}
+
+impl DockerBackend for PodmanBackendImpl {
+    fn download_image(&self, image: &str, tag: &str) -> Result<(), DockerError> {
+        self.docker.download_image(image, tag)
+    }
+
+    fn inspect_image(&self, image: &str) -> Result<Image, DockerError> {
+        self.docker.inspect_image(image)
+    }
+
+    fn create_container(
+        &self,
+        name: &str,
+        container_config: Config<String>,
+    ) -> Result<(), DockerError> {
+        self.docker.create_container(name, container_config)
+    }
+
+    fn delete_container(&self, name: &str) -> Result<(), DockerError> {
+        self.docker.delete_container(name)
+    }
+
+    fn start_container(&self, name: &str) -> Result<(), DockerError> {
+        self.docker.start_container(name)
+    }
+
+    fn stop_container(&self, name: &str, timeout: u64) -> Result<(), DockerError> {
+        self.docker.stop_container(name, timeout)
+    }
+
+    fn inspect_container(&self, name: &str) -> Result<InspectResult, DockerError> {
+        let mut response = match self.docker.inspect_container(name)? {
+            InspectResult::Ok(response) => response,
+            InspectResult::NotFound => return Ok(InspectResult::NotFound),
+        };
+
+        // Podman only runs healthchecks from a systemd timer, which is often missing for
+        // rootless users, so the results of run_healthcheck are reported instead
+        if let (Some(status), Some(health)) = (
+            self.health.borrow().status,
+            response
+                .state
+                .as_mut()
+                .filter(|state| state.running == Some(true))
+                .and_then(|state| state.health.as_mut())
+                .filter(|health| health.status == Some(HealthStatusEnum::STARTING)),
+        ) {
+            health.status = Some(status);
+        }
+
+        Ok(InspectResult::Ok(response))
+    }
+
+    fn run_healthcheck(&self, name: &str) -> Result<(), DockerError> {
+        let response = match self.docker.inspect_container(name)? {
+            InspectResult::Ok(response) => response,
+            InspectResult::NotFound => return Ok(()),
+        };
+
+        let healthcheck = match response
+            .config
+            .and_then(|config| config.healthcheck)
+            .filter(|healthcheck| healthcheck.test.is_some())
+        {
+            Some(healthcheck) => healthcheck,
+            None => return Ok(()),
+        };
+        let command = match healthcheck.test.as_deref().and_then(healthcheck_command) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        let state = match response.state.filter(|state| {
+            state.running == Some(true)
+                && state.health.as_ref().and_then(|health| health.status)
+                    == Some(HealthStatusEnum::STARTING)
+        }) {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+
+        let interval = healthcheck_duration(healthcheck.interval, DEFAULT_HEALTHCHECK_INTERVAL);
+        if let Some(last_run) = self.health.borrow().last_run {
+            if last_run.elapsed() < interval {
+                return Ok(());
+            }
+        }
+
+        let passed = match self.exec_healthcheck(name, command) {
+            Ok(passed) => passed,
+            Err(err) => {
+                log::debug!("{}", err);
+                false
+            }
+        };
+        let mut health = self.health.borrow_mut();
+        health.last_run = Some(Instant::now());
+        health.record(
+            passed,
+            in_start_period(
+                state.started_at.as_deref(),
+                healthcheck_duration(healthcheck.start_period, Duration::ZERO),
+            ),
+            healthcheck_retries(&healthcheck),
+        );
+        log::debug!(
+            "The healthcheck passed: {}, health is now {:?}",
+            passed,
+            health
+        );
+
+        Ok(())
+    }
+
+    fn get_container_logs(
+        &self,
+        name: &str,
+        options: LogOptions,
+    ) -> Box<dyn Iterator<Item = Result<LogEntry, DockerError>>> {
+        self.docker.get_container_logs(name, options)
+    }
+
+    fn is_rootless(&self) -> Result<bool, DockerError> {
+        self.docker.is_rootless()
+    }
+
+    fn is_remote(&self) -> bool {
+        self.docker.is_remote()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! healthcheck_command_tests {
+        ($($name:ident: $test:expr, $expected:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let test: Vec<String> = $test.iter().map(|s: &&str| s.to_string()).collect();
+                let expected: Option<Vec<String>> =
+                    $expected.map(|e: Vec<&str>| e.iter().map(|s| s.to_string()).collect());
+                assert_eq!(expected, healthcheck_command(&test));
+            }
+        )*
+        }
+    }
+
+    #[test]
+    fn test_manual_health_healthy() {
+        let mut health = ManualHealth::default();
+        health.record(false, false, 3);
+        health.record(true, false, 3);
+        assert_eq!(Some(HealthStatusEnum::HEALTHY), health.status);
+        assert_eq!(0, health.failures);
+    }
+
+    #[test]
+    fn test_manual_health_unhealthy_after_retries() {
+        let mut health = ManualHealth::default();
+        health.record(false, false, 3);
+        health.record(false, false, 3);
+        assert_eq!(None, health.status);
+        health.record(false, false, 3);
+        assert_eq!(Some(HealthStatusEnum::UNHEALTHY), health.status);
+    }
+
+    #[test]
+    fn test_manual_health_ignores_start_period_failures() {
+        let mut health = ManualHealth::default();
+        for _ in 0..5 {
+            health.record(false, true, 3);
+        }
+        assert_eq!(None, health.status);
+        assert_eq!(0, health.failures);
+    }
+
+    #[test]
+    fn test_healthcheck_settings() {
+        let healthcheck = HealthConfig {
+            retries: Some(24),
+            ..std::default::Default::default()
+        };
+        assert_eq!(24, healthcheck_retries(&healthcheck));
+        assert_eq!(
+            DEFAULT_HEALTHCHECK_RETRIES,
+            healthcheck_retries(&HealthConfig::default())
+        );
+        assert_eq!(
+            Duration::from_secs(5),
+            healthcheck_duration(Some(5_000_000_000), DEFAULT_HEALTHCHECK_INTERVAL)
+        );
+        assert_eq!(
+            DEFAULT_HEALTHCHECK_INTERVAL,
+            healthcheck_duration(Some(0), DEFAULT_HEALTHCHECK_INTERVAL)
+        );
+    }
+
+    #[test]
+    fn test_in_start_period() {
+        let started_at = (chrono::Utc::now() - chrono::Duration::seconds(10)).to_rfc3339();
+        assert!(in_start_period(Some(&started_at), Duration::from_secs(30)));
+        assert!(!in_start_period(Some(&started_at), Duration::from_secs(5)));
+        assert!(!in_start_period(None, Duration::from_secs(30)));
+    }
+
+    healthcheck_command_tests! {
+        test_healthcheck_command_cmd: ["CMD", "mc-health", "--quiet"], Some(vec!["mc-health", "--quiet"]);
+        test_healthcheck_command_shell: ["CMD-SHELL", "mc-health"], Some(vec!["/bin/sh", "-c", "mc-health"]);
+        test_healthcheck_command_none: ["NONE"], None;
+        test_healthcheck_command_inherit: [] as [&str; 0], None;
+        test_healthcheck_command_missing_args: ["CMD"], None;
+    }
+}
@@ -0,0 +1,383 @@
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::providers::backends::ping::strip_formatting_codes;
+
+const MAGIC: [u8; 2] = [0xfe, 0xfd];
+const HANDSHAKE_TYPE: u8 = 9;
+const STAT_TYPE: u8 = 0;
+// Servers only echo the low four bits of each byte of the session id
+const SESSION_ID_MASK: i32 = 0x0f0f_0f0f;
+const MAX_RESPONSE_LENGTH: usize = 1 << 16;
+const STAT_PADDING: &[u8] = b"splitnum\x00\x80\x00";
+const PLAYERS_PADDING: &[u8] = b"\x01player_\x00\x00";
+
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct QueryError {
+    pub message: String,
+    #[source]
+    pub source: io::Error,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct QueryStatus {
+    pub motd: String,
+    pub version: String,
+    pub software: Option<String>,
+    pub plugins: Vec<String>,
+    pub map: String,
+    pub online: u32,
+    pub max: u32,
+    pub players: Vec<String>,
+}
+
+#[cfg_attr(test, mockall::automock)]
+pub trait QueryBackend {
+    fn query(&self, host: &str, port: u16) -> Result<QueryStatus, QueryError>;
+}
+
+pub struct QueryBackendImpl {
+    timeout: Duration,
+}
+
+pub fn new_from_defaults() -> QueryBackendImpl {
+    QueryBackendImpl {
+        timeout: Duration::from_secs(5),
+    }
+}
+
+impl QueryBackend for QueryBackendImpl {
+    fn query(&self, host: &str, port: u16) -> Result<QueryStatus, QueryError> {
+        log::trace!("Querying {}:{}", host, port);
+        let error = |message: &str| {
+            let message = format!("{} {}:{}", message, host, port);
+            move |source| QueryError { message, source }
+        };
+
+        let socket = self
+            .connect(host, port)
+            .map_err(error("Unable to connect to"))?;
+        let session_id = rand::random::<i32>() & SESSION_ID_MASK;
+
+        let token = request(&socket, HANDSHAKE_TYPE, session_id, &[])
+            .and_then(|response| parse_challenge_token(&response))
+            .map_err(error("Failed to start a query session with"))?;
+
+        let mut payload = token.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[0; 4]);
+        request(&socket, STAT_TYPE, session_id, &payload)
+            .and_then(|response| parse_full_stat(&response))
+            .map_err(error("Invalid query response from"))
+    }
+}
+
+impl QueryBackendImpl {
+    fn connect(&self, host: &str, port: u16) -> io::Result<UdpSocket> {
+        let address = (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the host has no addresses"))?;
+        let local_address = if address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+
+        let socket = UdpSocket::bind(local_address)?;
+        socket.set_read_timeout(Some(self.timeout))?;
+        socket.set_write_timeout(Some(self.timeout))?;
+        socket.connect(address)?;
+        Ok(socket)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+// Sends a request and returns the body of the response, after its type and session id
+fn request(
+    socket: &UdpSocket,
+    request_type: u8,
+    session_id: i32,
+    payload: &[u8],
+) -> io::Result<Vec<u8>> {
+    let mut packet = MAGIC.to_vec();
+    packet.push(request_type);
+    packet.extend_from_slice(&session_id.to_be_bytes());
+    packet.extend_from_slice(payload);
+    socket.send(&packet)?;
+
+    let mut response = vec![0; MAX_RESPONSE_LENGTH];
+    let length = socket.recv(&mut response)?;
+    response.truncate(length);
+
+    let mut header = [request_type, 0, 0, 0, 0];
+    header[1..].copy_from_slice(&session_id.to_be_bytes());
+    match response.strip_prefix(&header[..]) {
+        Some(body) => Ok(body.to_vec()),
+        None => Err(invalid_data("the response does not match the request")),
+    }
+}
+
+// Splits off the next null terminated string
+fn read_string<'a>(data: &mut &'a [u8]) -> io::Result<&'a [u8]> {
+    let end = data
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or_else(|| invalid_data("unterminated string"))?;
+    let value = &data[..end];
+    *data = &data[end + 1..];
+    Ok(value)
+}
+
+fn parse_challenge_token(response: &[u8]) -> io::Result<i32> {
+    let mut data = response;
+    let token = read_string(&mut data)?;
+    std::str::from_utf8(token)
+        .ok()
+        .and_then(|token| token.trim().parse().ok())
+        .ok_or_else(|| invalid_data("invalid challenge token"))
+}
+
+// The plugins value is "<software>: <plugin>; <plugin>" on modded servers and empty on vanilla
+fn parse_plugins(plugins: &str) -> (Option<String>, Vec<String>) {
+    let (software, plugins) = match plugins.split_once(':') {
+        Some((software, plugins)) => (software.trim(), plugins),
+        None => (plugins.trim(), ""),
+    };
+
+    (
+        Some(software.to_owned()).filter(|software| !software.is_empty()),
+        plugins
+            .split(';')
+            .map(str::trim)
+            .filter(|plugin| !plugin.is_empty())
+            .map(str::to_owned)
+            .collect(),
+    )
+}
+
+fn parse_full_stat(response: &[u8]) -> io::Result<QueryStatus> {
+    let mut data = response
+        .strip_prefix(STAT_PADDING)
+        .ok_or_else(|| invalid_data("missing the stat padding"))?;
+
+    let mut status = QueryStatus::default();
+    let mut online = None;
+    let mut max = None;
+    loop {
+        let key = read_string(&mut data)?;
+        if key.is_empty() {
+            break;
+        }
+        let value = String::from_utf8_lossy(read_string(&mut data)?).into_owned();
+        match key {
+            b"hostname" => status.motd = strip_formatting_codes(&value),
+            b"version" => status.version = value,
+            b"plugins" => {
+                let (software, plugins) = parse_plugins(&value);
+                status.software = software;
+                status.plugins = plugins;
+            }
+            b"map" => status.map = value,
+            b"numplayers" => online = value.parse().ok(),
+            b"maxplayers" => max = value.parse().ok(),
+            _ => (),
+        }
+    }
+    status.online = online.ok_or_else(|| invalid_data("missing numplayers"))?;
+    status.max = max.ok_or_else(|| invalid_data("missing maxplayers"))?;
+
+    data = data
+        .strip_prefix(PLAYERS_PADDING)
+        .ok_or_else(|| invalid_data("missing the player padding"))?;
+    loop {
+        let player = read_string(&mut data)?;
+        if player.is_empty() {
+            break;
+        }
+        status
+            .players
+            .push(String::from_utf8_lossy(player).into_owned());
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_full_stat(pairs: &[(&str, &str)], players: &[&str]) -> Vec<u8> {
+        let mut response = STAT_PADDING.to_vec();
+        for (key, value) in pairs {
+            response.extend_from_slice(key.as_bytes());
+            response.push(0);
+            response.extend_from_slice(value.as_bytes());
+            response.push(0);
+        }
+        response.push(0);
+        response.extend_from_slice(PLAYERS_PADDING);
+        for player in players {
+            response.extend_from_slice(player.as_bytes());
+            response.push(0);
+        }
+        response.push(0);
+        response
+    }
+
+    fn get_pairs<'a>(plugins: &'a str, numplayers: &'a str) -> Vec<(&'a str, &'a str)> {
+        vec![
+            ("hostname", "\u{a7}aA Minecraft Server"),
+            ("gametype", "SMP"),
+            ("game_id", "MINECRAFT"),
+            ("version", "1.20.4"),
+            ("plugins", plugins),
+            ("map", "world"),
+            ("numplayers", numplayers),
+            ("maxplayers", "20"),
+            ("hostport", "25565"),
+            ("hostip", "172.17.0.2"),
+        ]
+    }
+
+    #[test]
+    fn test_parse_full_stat() {
+        let response = get_full_stat(
+            &get_pairs("Paper on 1.20.4: Dynmap 3.7; LuckPerms 5.4", "2"),
+            &["alice", "bob"],
+        );
+
+        assert_eq!(
+            QueryStatus {
+                motd: "A Minecraft Server".to_owned(),
+                version: "1.20.4".to_owned(),
+                software: Some("Paper on 1.20.4".to_owned()),
+                plugins: vec!["Dynmap 3.7".to_owned(), "LuckPerms 5.4".to_owned()],
+                map: "world".to_owned(),
+                online: 2,
+                max: 20,
+                players: vec!["alice".to_owned(), "bob".to_owned()],
+            },
+            parse_full_stat(&response).unwrap()
+        );
+    }
+
+    macro_rules! invalid_full_stat_tests {
+        ($($name:ident: $response:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert!(parse_full_stat($response).is_err());
+            }
+        )*
+        }
+    }
+
+    invalid_full_stat_tests! {
+        test_full_stat_empty: &[],
+        test_full_stat_missing_padding: &get_full_stat(&get_pairs("", "0"), &[])[STAT_PADDING.len()..],
+        test_full_stat_missing_numplayers: &get_full_stat(&[("maxplayers", "20")], &[]),
+        test_full_stat_invalid_numplayers: &get_full_stat(&get_pairs("", "many"), &[]),
+        test_full_stat_truncated: &get_full_stat(&get_pairs("", "1"), &["alice"])[..40],
+    }
+
+    macro_rules! parse_plugins_tests {
+        ($($name:ident: $plugins:expr, $software:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let expected: Vec<String> = $expected.iter().map(|plugin: &&str| plugin.to_string()).collect();
+                assert_eq!(
+                    ($software.map(|software: &str| software.to_owned()), expected),
+                    parse_plugins($plugins)
+                );
+            }
+        )*
+        }
+    }
+
+    parse_plugins_tests! {
+        test_parse_plugins_vanilla: "", None, [] as [&str; 0],
+        test_parse_plugins_software_only: "Paper on 1.20.4", Some("Paper on 1.20.4"), [] as [&str; 0],
+        test_parse_plugins_list: "CraftBukkit on Bukkit 1.20.4: WorldEdit 7.2; Essentials", Some("CraftBukkit on Bukkit 1.20.4"), ["WorldEdit 7.2", "Essentials"],
+    }
+
+    macro_rules! challenge_token_tests {
+        ($($name:ident: $response:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, parse_challenge_token($response).ok());
+            }
+        )*
+        }
+    }
+
+    challenge_token_tests! {
+        test_challenge_token: b"9513307\x00", Some(9513307),
+        test_challenge_token_negative: b"-1431655766\x00", Some(-1431655766),
+        test_challenge_token_unterminated: b"9513307", None,
+        test_challenge_token_invalid: b"token\x00", None,
+    }
+
+    // Answers a handshake and a full stat request, checking the challenge token is sent back
+    fn start_fake_server(full_stat: Vec<u8>) -> u16 {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let mut buffer = [0; 64];
+
+            let (length, client) = socket.recv_from(&mut buffer).unwrap();
+            assert_eq!(7, length);
+            assert_eq!(&MAGIC, &buffer[..2]);
+            assert_eq!(HANDSHAKE_TYPE, buffer[2]);
+            let session_id = buffer[3..7].to_vec();
+            let mut response = vec![HANDSHAKE_TYPE];
+            response.extend_from_slice(&session_id);
+            response.extend_from_slice(b"9513307\x00");
+            socket.send_to(&response, client).unwrap();
+
+            let (length, client) = socket.recv_from(&mut buffer).unwrap();
+            assert_eq!(15, length);
+            assert_eq!(STAT_TYPE, buffer[2]);
+            assert_eq!(session_id, buffer[3..7].to_vec());
+            assert_eq!(9513307i32.to_be_bytes(), buffer[7..11]);
+            let mut response = vec![STAT_TYPE];
+            response.extend_from_slice(&session_id);
+            response.extend(full_stat);
+            socket.send_to(&response, client).unwrap();
+        });
+        port
+    }
+
+    fn get_query_backend() -> QueryBackendImpl {
+        QueryBackendImpl {
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_query() {
+        let port = start_fake_server(get_full_stat(&get_pairs("", "1"), &["alice"]));
+
+        let status = get_query_backend().query("127.0.0.1", port).unwrap();
+        assert_eq!("1.20.4", status.version);
+        assert_eq!(None, status.software);
+        assert_eq!(vec!["alice".to_owned()], status.players);
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let query_backend = QueryBackendImpl {
+            timeout: Duration::from_millis(50),
+        };
+
+        assert!(query_backend.query("127.0.0.1", port).is_err());
+    }
+}
@@ -1,10 +1,18 @@
 use rcon::{Builder, Connection};
 
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct RconError {
+    pub message: String,
+    #[source]
+    pub source: rcon::Error,
+}
+
 #[cfg_attr(test, mockall::automock(type Output = MockRconBackend;))]
 pub trait RconBackendFactory {
     type Output: RconBackend;
 
-    fn create(&self, host: &str, port: &str) -> Result<Self::Output, ()>;
+    fn create(&self, host: &str, port: &str, password: &str) -> Result<Self::Output, RconError>;
 }
 
 pub struct RconBackendFactoryImpl {}
@@ -12,16 +20,16 @@ pub struct RconBackendFactoryImpl {}
 impl RconBackendFactory for RconBackendFactoryImpl {
     type Output = RconBackendImpl;
 
-    fn create(&self, host: &str, port: &str) -> Result<RconBackendImpl, ()> {
+    fn create(&self, host: &str, port: &str, password: &str) -> Result<RconBackendImpl, RconError> {
         Ok(RconBackendImpl {
             connection: futures::executor::block_on(
                 Builder::new()
                     .enable_minecraft_quirks(true)
-                    .connect(format!("{}:{}", host, port), "minecraft"),
+                    .connect(format!("{}:{}", host, port), password),
             )
-            .or_else(|err| {
-                log::trace!("Unable to connect to {}:{}: {}", host, port, err);
-                Err(())
+            .map_err(|source| RconError {
+                message: format!("Unable to connect to {}:{}", host, port),
+                source,
             })?,
         })
     }
@@ -29,7 +37,7 @@ impl RconBackendFactory for RconBackendFactoryImpl {
 
 #[cfg_attr(test, mockall::automock)]
 pub trait RconBackend: std::marker::Sized {
-    fn cmd(&mut self, cmd: &str) -> Result<String, ()>;
+    fn cmd(&mut self, cmd: &str) -> Result<String, RconError>;
 }
 
 pub struct RconBackendImpl {
@@ -37,12 +45,10 @@ pub struct RconBackendImpl {
 }
 
 impl RconBackend for RconBackendImpl {
-    fn cmd(&mut self, cmd: &str) -> Result<String, ()> {
-        Ok(
-            futures::executor::block_on(self.connection.cmd(cmd)).or_else(|err| {
-                log::trace!("Failed to execute rcon command: {}", err);
-                Err(())
-            })?,
-        )
+    fn cmd(&mut self, cmd: &str) -> Result<String, RconError> {
+        futures::executor::block_on(self.connection.cmd(cmd)).map_err(|source| RconError {
+            message: format!("Failed to execute the rcon command \"{}\"", cmd),
+            source,
+        })
     }
 }
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("The webhook at {host} responded with status {status}")]
+    Status { host: String, status: u16 },
+    #[error("Unable to send the webhook to {host}: {reason}")]
+    Transport { host: String, reason: String },
+}
+
+impl WebhookError {
+    // Connection failures, rate limits and server errors may succeed on a later attempt,
+    // while other statuses mean the request itself was rejected
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            WebhookError::Status { status, .. } => *status == 429 || *status >= 500,
+            WebhookError::Transport { .. } => true,
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+pub trait WebhookBackend {
+    fn post(&self, url: &str, payload: &serde_json::Value) -> Result<(), WebhookError>;
+}
+
+pub struct WebhookBackendImpl {
+    agent: ureq::Agent,
+}
+
+pub fn new_from_defaults() -> WebhookBackendImpl {
+    WebhookBackendImpl {
+        agent: ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(10))
+            .build(),
+    }
+}
+
+impl WebhookBackend for WebhookBackendImpl {
+    fn post(&self, url: &str, payload: &serde_json::Value) -> Result<(), WebhookError> {
+        let host = redact_url(url).to_owned();
+        log::trace!("Posting a webhook to {}", host);
+        match self
+            .agent
+            .post(url)
+            .set("Content-Type", "application/json")
+            .send_string(&payload.to_string())
+        {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, _)) => Err(WebhookError::Status { host, status }),
+            Err(ureq::Error::Transport(transport)) => Err(WebhookError::Transport {
+                reason: transport_reason(url, &host, &transport),
+                host,
+            }),
+        }
+    }
+}
+
+// Webhook urls carry their credentials in the path, so only the scheme and host are logged
+fn redact_url(url: &str) -> &str {
+    let start = url.find("://").map_or(0, |index| index + 3);
+    match url[start..].find('/') {
+        Some(end) => &url[..start + end],
+        None => url,
+    }
+}
+
+// The transport error's own message starts with the full url, so the reason is built from its
+// parts instead, with the url replaced wherever the parts repeat it
+fn transport_reason(url: &str, host: &str, transport: &ureq::Transport) -> String {
+    let mut reason = transport.kind().to_string();
+    if let Some(message) = transport.message() {
+        reason.push_str(&format!(": {}", message));
+    }
+    if let Some(source) = std::error::Error::source(transport) {
+        reason.push_str(&format!(": {}", source));
+    }
+
+    let mut reason = reason.replace(url, host);
+    if let Some(parsed_url) = transport.url() {
+        reason = reason.replace(parsed_url.as_str(), host);
+    }
+    reason
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    // Answers a single request with the given status, returning the request line and body
+    fn start_fake_server(status: &'static str) -> (u16, std::thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                let (name, value) = header.split_once(':').unwrap();
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            (
+                request_line.trim().to_owned(),
+                String::from_utf8(body).unwrap(),
+            )
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_post() {
+        let (port, handle) = start_fake_server("204 No Content");
+
+        new_from_defaults()
+            .post(
+                &format!("http://127.0.0.1:{}/hooks/token", port),
+                &serde_json::json!({"text": "hello"}),
+            )
+            .unwrap();
+
+        let (request_line, body) = handle.join().unwrap();
+        assert_eq!("POST /hooks/token HTTP/1.1", request_line);
+        assert_eq!(r#"{"text":"hello"}"#, body);
+    }
+
+    macro_rules! post_status_tests {
+        ($($name:ident: $status:expr, $code:expr, $retryable:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (port, handle) = start_fake_server($status);
+
+                let err = new_from_defaults()
+                    .post(&format!("http://127.0.0.1:{}/hooks/token", port), &serde_json::json!({}))
+                    .unwrap_err();
+                handle.join().unwrap();
+
+                assert!(matches!(err, WebhookError::Status { status: $code, .. }));
+                assert_eq!($retryable, err.is_retryable());
+                assert!(!err.to_string().contains("token"));
+            }
+        )*
+        }
+    }
+
+    post_status_tests! {
+        test_post_not_found: "404 Not Found", 404, false,
+        test_post_rate_limited: "429 Too Many Requests", 429, true,
+        test_post_server_error: "500 Internal Server Error", 500, true,
+    }
+
+    #[test]
+    fn test_post_connection_refused() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let err = new_from_defaults()
+            .post(
+                &format!("http://127.0.0.1:{}/hooks/token", port),
+                &serde_json::json!({}),
+            )
+            .unwrap_err();
+
+        assert!(matches!(err, WebhookError::Transport { .. }));
+        assert!(err.is_retryable());
+        assert!(err.to_string().contains("Connection Failed"));
+
+        let mut source: Option<&dyn std::error::Error> = Some(&err);
+        while let Some(err) = source {
+            assert!(!err.to_string().contains("token"));
+            source = err.source();
+        }
+    }
+
+    #[test]
+    fn test_post_dns_failure() {
+        let err = new_from_defaults()
+            .post(
+                "http://minecraft-compose.invalid/hooks/token",
+                &serde_json::json!({}),
+            )
+            .unwrap_err();
+
+        assert!(matches!(err, WebhookError::Transport { .. }));
+        assert!(err.to_string().contains("Dns Failed"));
+        assert!(!err.to_string().contains("token"));
+        assert!(std::error::Error::source(&err).is_none());
+    }
+
+    macro_rules! redact_url_tests {
+        ($($name:ident: $url:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, redact_url($url));
+            }
+        )*
+        }
+    }
+
+    redact_url_tests! {
+        test_redact_url_path: "https://discord.com/api/webhooks/1/token", "https://discord.com",
+        test_redact_url_no_path: "http://localhost:8080", "http://localhost:8080",
+    }
+}
@@ -4,13 +4,40 @@ use bollard::models::{
     RestartPolicy, RestartPolicyNameEnum,
 };
 use bollard::service;
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
 
 use crate::config::{self, Config};
 use crate::providers::backends;
 
-const IMAGE_NAME: &str = "itzg/minecraft-server";
-const IMAGE_TAG: &str = "latest";
+const IMAGE_LABEL: &str = "minecraft-compose.image";
+const CONFIG_HASH_LABEL: &str = "minecraft-compose.config-hash";
+const DEFAULT_UNPRIVILEGED_PORT_START: i32 = 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContainerError {
+    #[error(transparent)]
+    Docker(#[from] backends::docker::DockerError),
+    #[error(transparent)]
+    Connection(#[from] backends::connection::ConnectionError),
+    #[error("The image {image} could not be found")]
+    ImageNotFound {
+        image: String,
+        #[source]
+        source: Box<backends::docker::DockerError>,
+    },
+    #[error("The data path \"{}\" is not valid unicode", .0.display())]
+    InvalidDataPath(PathBuf),
+    #[error("The container runtime is remote, so runtime.data_path must be set to the location of the data folder on its host")]
+    RemoteDataPathRequired,
+    #[error("The rcon port of the container {0} is not published")]
+    RconAddressNotFound(String),
+    #[error("Port {port} cannot be published by a rootless container runtime, use a port of at least {start} or lower net.ipv4.ip_unprivileged_port_start")]
+    PrivilegedPort { port: i32, start: i32 },
+}
 
 #[derive(Debug, PartialEq)]
 pub enum ContainerState {
@@ -25,39 +52,211 @@ pub enum GameState {
     Unknown,
     Starting,
     Running,
+    Unhealthy,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct PublishedPort {
+    pub container: String,
+    pub host_ip: Option<String>,
+    pub host_port: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogLine {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub text: String,
 }
 
 #[cfg_attr(test, mockall::automock)]
 pub trait ContainerProvider {
-    fn create_container(&self, config: &Config, data_path: &PathBuf) -> Result<(), ()>;
-    fn delete_container(&self, config: &Config) -> Result<(), ()>;
-    fn start_container(&self, config: &Config) -> Result<(), ()>;
-    fn stop_container(&self, config: &Config) -> Result<(), ()>;
-    fn get_container_status(&self, config: &Config) -> Result<ContainerState, ()>;
-    fn get_container_rcon_address(&self, config: &Config) -> Result<(String, String), ()>;
-    fn display_container_logs(&self, config: &Config) -> Result<(), ()>;
+    fn create_container(&self, config: &Config, data_path: &PathBuf) -> Result<(), ContainerError>;
+    fn delete_container(&self, config: &Config) -> Result<(), ContainerError>;
+    fn start_container(&self, config: &Config) -> Result<(), ContainerError>;
+    fn stop_container(&self, config: &Config, timeout: u64) -> Result<(), ContainerError>;
+    fn get_container_status(&self, config: &Config) -> Result<ContainerState, ContainerError>;
+    fn run_container_healthcheck(&self, config: &Config) -> Result<(), ContainerError>;
+    fn get_container_rcon_address(
+        &self,
+        config: &Config,
+    ) -> Result<(String, String), ContainerError>;
+    fn display_container_logs(
+        &self,
+        config: &Config,
+        options: backends::docker::LogOptions,
+    ) -> Result<(), ContainerError>;
+    fn get_container_logs(
+        &self,
+        config: &Config,
+        since: Option<DateTime<Utc>>,
+        tail: Option<u64>,
+    ) -> Result<Vec<LogLine>, ContainerError>;
+    fn stream_container_logs(
+        &self,
+        config: &Config,
+        options: backends::docker::LogOptions,
+    ) -> Box<dyn Iterator<Item = Result<LogLine, ContainerError>>>;
+    fn get_container_image(&self, config: &Config) -> Result<Option<String>, ContainerError>;
+    fn get_container_server_type(&self, config: &Config) -> Result<Option<String>, ContainerError>;
+    fn get_container_started_at(
+        &self,
+        config: &Config,
+    ) -> Result<Option<DateTime<Utc>>, ContainerError>;
+    fn get_container_ports(&self, config: &Config) -> Result<Vec<PublishedPort>, ContainerError>;
+    fn has_config_drift(
+        &self,
+        config: &Config,
+        data_path: &Path,
+    ) -> Result<Option<bool>, ContainerError>;
 }
 
 pub struct ContainerProviderImpl<T: backends::docker::DockerBackend> {
     docker: T,
+    unprivileged_port_start: fn() -> i32,
 }
 
-pub fn new_from_defaults() -> Result<ContainerProviderImpl<backends::docker::DockerBackendImpl>, ()>
-{
-    Ok(ContainerProviderImpl {
-        docker: backends::docker::new_from_defaults()?,
-    })
+pub fn new_from_backend<T: backends::docker::DockerBackend>(docker: T) -> ContainerProviderImpl<T> {
+    ContainerProviderImpl {
+        docker,
+        unprivileged_port_start,
+    }
 }
 
-impl<T: backends::docker::DockerBackend> ContainerProvider for ContainerProviderImpl<T> {
-    fn create_container(&self, config: &Config, data_path: &PathBuf) -> Result<(), ()> {
-        let data_path = match data_path.to_str() {
-            Some(s) => Ok(s),
-            None => {
-                log::error!("Unable to convert data path to string");
-                Err(())
+fn unprivileged_port_start() -> i32 {
+    std::fs::read_to_string("/proc/sys/net/ipv4/ip_unprivileged_port_start")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_UNPRIVILEGED_PORT_START)
+}
+
+// Docker drops the docker.io prefix from the names of images on Docker Hub
+fn is_same_image(name: &str, image: &str) -> bool {
+    name == image || format!("docker.io/{}", name) == image
+}
+
+// Picks the itzg/minecraft-server Java variant that supports the version, falling back to
+// latest for snapshots and anything newer than the known variants
+fn default_tag(version: &str) -> &'static str {
+    let release = version.split('-').next().unwrap_or_default();
+    let parts: Option<Vec<u32>> = release.split('.').map(|part| part.parse().ok()).collect();
+    match parts.as_deref() {
+        Some([1, minor, ..]) if *minor < 17 => "java8",
+        Some([1, 17..=19, ..]) | Some([1, 20]) => "java17",
+        Some([1, 20, patch, ..]) if *patch < 5 => "java17",
+        Some([1, 20..=21, ..]) => "java21",
+        _ => "latest",
+    }
+}
+
+// Returns the tag or digest to pull along with the full image reference
+fn image_source(config: &Config) -> (String, String) {
+    let image = &config.container.image;
+    match (&config.container.digest, &config.container.tag) {
+        (Some(digest), _) => (digest.to_owned(), format!("{}@{}", image, digest)),
+        (None, Some(tag)) => (tag.to_owned(), format!("{}:{}", image, tag)),
+        (None, None) => {
+            let tag = default_tag(&config.server.version);
+            (tag.to_owned(), format!("{}:{}", image, tag))
+        }
+    }
+}
+
+// serde_json sorts object keys, so the hash does not depend on the order of the port map
+fn config_hash(container_config: &ContainerConfig<String>) -> String {
+    let mut hasher = fnv::FnvHasher::default();
+    hasher.write(
+        serde_json::to_value(container_config)
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    format!("{:016x}", hasher.finish())
+}
+
+fn resolve_digest(image: &str, image_details: &service::Image) -> Option<String> {
+    image_details
+        .repo_digests
+        .as_ref()?
+        .iter()
+        .filter_map(|repo_digest| repo_digest.split_once('@'))
+        .find(|(name, _)| is_same_image(name, image))
+        .map(|(_, digest)| format!("{}@{}", image, digest))
+}
+
+fn restart_policy_name(restart_policy: config::RestartPolicy) -> RestartPolicyNameEnum {
+    match restart_policy {
+        config::RestartPolicy::No => RestartPolicyNameEnum::NO,
+        config::RestartPolicy::Always => RestartPolicyNameEnum::ALWAYS,
+        config::RestartPolicy::UnlessStopped => RestartPolicyNameEnum::UNLESS_STOPPED,
+        config::RestartPolicy::OnFailure => RestartPolicyNameEnum::ON_FAILURE,
+    }
+}
+
+// Log lines requested with timestamps start with an RFC 3339 timestamp and a space
+fn parse_log_line(line: &str) -> LogLine {
+    line.split_once(' ')
+        .and_then(|(timestamp, text)| {
+            DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|timestamp| LogLine {
+                    timestamp: Some(timestamp.with_timezone(&Utc)),
+                    text: text.to_owned(),
+                })
+        })
+        .unwrap_or_else(|| LogLine {
+            timestamp: None,
+            text: line.to_owned(),
+        })
+}
+
+// Describes the server type the container was created with the same way the config displays it,
+// the image runs vanilla when TYPE is unset
+fn server_type_from_env(env: &[String]) -> String {
+    let variables: HashMap<&str, &str> = env
+        .iter()
+        .filter_map(|variable| variable.split_once('='))
+        .collect();
+
+    let (name, version_variable) = match variables.get("TYPE").copied().unwrap_or("VANILLA") {
+        "VANILLA" => ("Vanilla", None),
+        "PAPER" => ("Paper", None),
+        "SPIGOT" => ("Spigot", None),
+        "PURPUR" => ("Purpur", None),
+        "FABRIC" => ("Fabric", Some("FABRIC_LOADER_VERSION")),
+        "QUILT" => ("Quilt", Some("QUILT_LOADER_VERSION")),
+        "FORGE" => ("Forge", Some("FORGE_VERSION")),
+        "NEOFORGE" => ("NeoForge", Some("NEOFORGE_VERSION")),
+        server_type => return server_type.to_owned(),
+    };
+
+    match version_variable.and_then(|variable| variables.get(variable)) {
+        Some(version) => format!("{} {}", name, version),
+        None => name.to_owned(),
+    }
+}
+
+fn memory_bytes(size: &Option<String>) -> Option<i64> {
+    size.as_deref()
+        .and_then(config::parse_memory_size)
+        .and_then(|bytes| i64::try_from(bytes).ok())
+}
+
+impl<T: backends::docker::DockerBackend> ContainerProviderImpl<T> {
+    fn build_container_config(
+        &self,
+        config: &Config,
+        data_path: &Path,
+    ) -> Result<ContainerConfig<String>, ContainerError> {
+        // The bind mount is resolved by the runtime, so a remote runtime needs the path on its host
+        let data_path = match &config.runtime.data_path {
+            Some(data_path) => data_path.as_str(),
+            None if self.docker.is_remote() => {
+                return Err(ContainerError::RemoteDataPathRequired);
             }
-        }?;
+            None => data_path
+                .to_str()
+                .ok_or_else(|| ContainerError::InvalidDataPath(data_path.to_path_buf()))?,
+        };
 
         let mut port_map = PortMap::new();
         port_map.insert(
@@ -74,78 +273,224 @@ impl<T: backends::docker::DockerBackend> ContainerProvider for ContainerProvider
                 host_port: None,
             }]),
         );
+        let mut exposed_ports = HashMap::new();
+        if config.query.enabled {
+            port_map.insert(
+                "25565/udp".to_owned(),
+                Some(vec![PortBinding {
+                    host_ip: Some(config.host.to_owned()),
+                    host_port: Some(config.query_port().to_string()),
+                }]),
+            );
+            exposed_ports.insert("25565/udp".to_owned(), HashMap::new());
+        }
+        for port in &config.container.ports {
+            let container_port = format!("{}/{}", port.container, port.protocol);
+            port_map.insert(
+                container_port.clone(),
+                Some(vec![PortBinding {
+                    host_ip: Some(port.host_ip.as_ref().unwrap_or(&config.host).to_owned()),
+                    host_port: Some(port.host_port().to_string()),
+                }]),
+            );
+            exposed_ports.insert(container_port, HashMap::new());
+        }
+
+        let mut binds = vec![format!("{}:/data", data_path)];
+        for volume in &config.container.volumes {
+            let mode = if volume.read_only { ":ro" } else { "" };
+            binds.push(format!("{}:{}{}", volume.source, volume.target, mode));
+        }
 
         let mut env = vec![
             String::from("EULA=true"),
             format!("VERSION={}", config.server.version),
+            // The rcon password is only written to server.properties when starting, so it is
+            // kept out of the container's env and the image is stopped from overwriting it
+            String::from("ENABLE_RCON=true"),
+            String::from("OVERRIDE_SERVER_PROPERTIES=false"),
         ];
         if let Some(memory) = &config.server.memory {
             env.append(&mut vec![format!("MEMORY={}", memory)]);
         }
-        match config.server.server_type {
+        if let Some(jvm_opts) = &config.server.jvm_opts {
+            env.append(&mut vec![format!("JVM_OPTS={}", jvm_opts)]);
+        }
+        if config.server.use_aikar_flags {
+            env.append(&mut vec![String::from("USE_AIKAR_FLAGS=true")]);
+        }
+        // Sorted so the config hash does not depend on the map's iteration order
+        let mut extra_env: Vec<_> = config.container.env.iter().collect();
+        extra_env.sort();
+        for (name, value) in extra_env {
+            env.push(format!("{}={}", name, value));
+        }
+        match &config.server.server_type {
             config::ServerType::Vanilla => {
                 env.append(&mut vec![String::from("TYPE=VANILLA")]);
             }
+            config::ServerType::Paper { build } => {
+                env.append(&mut vec![String::from("TYPE=PAPER")]);
+                if let Some(build) = build {
+                    env.append(&mut vec![format!("PAPER_BUILD={}", build)]);
+                }
+            }
+            config::ServerType::Spigot => {
+                env.append(&mut vec![String::from("TYPE=SPIGOT")]);
+            }
+            config::ServerType::Purpur { build } => {
+                env.append(&mut vec![String::from("TYPE=PURPUR")]);
+                if let Some(build) = build {
+                    env.append(&mut vec![format!("PURPUR_BUILD={}", build)]);
+                }
+            }
+            config::ServerType::Fabric {
+                loader_version,
+                installer_version,
+            } => {
+                env.append(&mut vec![
+                    String::from("TYPE=FABRIC"),
+                    format!("FABRIC_LOADER_VERSION={}", loader_version),
+                ]);
+                if let Some(installer_version) = installer_version {
+                    env.append(&mut vec![format!(
+                        "FABRIC_INSTALLER_VERSION={}",
+                        installer_version
+                    )]);
+                }
+            }
+            config::ServerType::Quilt {
+                loader_version,
+                installer_version,
+            } => {
+                env.append(&mut vec![
+                    String::from("TYPE=QUILT"),
+                    format!("QUILT_LOADER_VERSION={}", loader_version),
+                ]);
+                if let Some(installer_version) = installer_version {
+                    env.append(&mut vec![format!(
+                        "QUILT_INSTALLER_VERSION={}",
+                        installer_version
+                    )]);
+                }
+            }
+            config::ServerType::Forge { forge_version } => {
+                env.append(&mut vec![
+                    String::from("TYPE=FORGE"),
+                    format!("FORGE_VERSION={}", forge_version),
+                ]);
+            }
+            config::ServerType::NeoForge { neoforge_version } => {
+                env.append(&mut vec![
+                    String::from("TYPE=NEOFORGE"),
+                    format!("NEOFORGE_VERSION={}", neoforge_version),
+                ]);
+            }
         }
 
-        let full_image_name = format!("{}:{}", IMAGE_NAME, IMAGE_TAG);
+        let resources = &config.container.resources;
+        Ok(ContainerConfig {
+            image: Some(image_source(config).1),
+            env: Some(env),
+            exposed_ports: Some(exposed_ports).filter(|ports| !ports.is_empty()),
+            host_config: Some(HostConfig {
+                binds: Some(binds),
+                port_bindings: Some(port_map),
+                restart_policy: Some(RestartPolicy {
+                    name: Some(restart_policy_name(config.container.restart_policy)),
+                    maximum_retry_count: None,
+                }),
+                nano_cpus: resources.cpus.map(|cpus| (cpus * 1e9) as i64),
+                memory: memory_bytes(&resources.memory),
+                memory_reservation: memory_bytes(&resources.memory_reservation),
+                pids_limit: resources.pids_limit,
+                ..std::default::Default::default()
+            }),
+            ..std::default::Default::default()
+        })
+    }
+}
 
-        if let Err(()) = self.docker.download_image(IMAGE_NAME, IMAGE_TAG) {
-            match self.docker.inspect_image(IMAGE_NAME) {
-                Err(()) => {
-                    log::error!("The image {} could not be found", full_image_name);
-                    Err(())
-                }
-                Ok(image) => {
-                    if !match image.repo_tags {
-                        None => false,
-                        Some(tags) => tags.contains(&full_image_name),
-                    } {
-                        log::error!("The image {} could not be found", full_image_name);
-                        return Err(());
-                    }
+impl<T: backends::docker::DockerBackend> ContainerProvider for ContainerProviderImpl<T> {
+    fn create_container(&self, config: &Config, data_path: &PathBuf) -> Result<(), ContainerError> {
+        let mut container_config = self.build_container_config(config, data_path)?;
+        let config_hash = config_hash(&container_config);
+
+        let start = if self.docker.is_remote() {
+            DEFAULT_UNPRIVILEGED_PORT_START
+        } else {
+            (self.unprivileged_port_start)()
+        };
+        let lowest_port = config
+            .container
+            .ports
+            .iter()
+            .map(|port| i32::from(port.host_port()))
+            .chain(std::iter::once(config.port))
+            .chain(Some(config.query_port()).filter(|_| config.query.enabled))
+            .min()
+            .unwrap_or(config.port);
+        if lowest_port < start && self.docker.is_rootless()? {
+            return Err(ContainerError::PrivilegedPort {
+                port: lowest_port,
+                start,
+            });
+        }
 
-                    Ok(())
+        let image = &config.container.image;
+        let (tag, image_reference) = image_source(config);
+
+        let download_result = self.docker.download_image(image, &tag);
+        let resolved_image = match (self.docker.inspect_image(&image_reference), download_result) {
+            (Ok(image_details), download_result) => {
+                if let Err(download_error) = download_result {
+                    log::warn!(
+                        "Unable to download {}, continuing with the local image: {}",
+                        image_reference,
+                        download_error
+                    );
                 }
-            }?;
+                resolve_digest(image, &image_details).unwrap_or_else(|| image_reference.clone())
+            }
+            (Err(_), Err(download_error)) => {
+                return Err(ContainerError::ImageNotFound {
+                    image: image_reference,
+                    source: Box::new(download_error),
+                });
+            }
+            (Err(inspect_error), Ok(())) => return Err(inspect_error.into()),
+        };
+        log::debug!("Using the image {}", resolved_image);
 
-            log::warn!("Unable to download latest image, continuing with local image");
-        }
+        let mut labels = HashMap::new();
+        labels.insert(IMAGE_LABEL.to_owned(), resolved_image);
+        labels.insert(CONFIG_HASH_LABEL.to_owned(), config_hash);
+        container_config.labels = Some(labels);
 
-        self.docker.create_container(
-            &config.name,
-            ContainerConfig {
-                image: Some(full_image_name),
-                env: Some(env),
-                host_config: Some(HostConfig {
-                    binds: Some(vec![format!("{}:/data", data_path)]),
-                    port_bindings: Some(port_map),
-                    restart_policy: Some(RestartPolicy {
-                        name: Some(RestartPolicyNameEnum::ALWAYS),
-                        maximum_retry_count: None,
-                    }),
-                    ..std::default::Default::default()
-                }),
-                ..std::default::Default::default()
-            },
-        )
+        Ok(self
+            .docker
+            .create_container(&config.name, container_config)?)
+    }
+
+    fn delete_container(&self, config: &Config) -> Result<(), ContainerError> {
+        Ok(self.docker.delete_container(&config.name)?)
     }
 
-    fn delete_container(&self, config: &Config) -> Result<(), ()> {
-        self.docker.delete_container(&config.name)
+    fn start_container(&self, config: &Config) -> Result<(), ContainerError> {
+        Ok(self.docker.start_container(&config.name)?)
     }
 
-    fn start_container(&self, config: &Config) -> Result<(), ()> {
-        self.docker.start_container(&config.name)
+    fn stop_container(&self, config: &Config, timeout: u64) -> Result<(), ContainerError> {
+        Ok(self.docker.stop_container(&config.name, timeout)?)
     }
 
-    fn stop_container(&self, config: &Config) -> Result<(), ()> {
-        self.docker.stop_container(&config.name)
+    fn run_container_healthcheck(&self, config: &Config) -> Result<(), ContainerError> {
+        Ok(self.docker.run_healthcheck(&config.name)?)
     }
 
-    fn get_container_status(&self, config: &Config) -> Result<ContainerState, ()> {
-        match self.docker.inspect_container(&config.name) {
-            Ok(backends::docker::InspectResult::Ok(result)) => match result.state {
+    fn get_container_status(&self, config: &Config) -> Result<ContainerState, ContainerError> {
+        match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(result) => match result.state {
                 None => Ok(ContainerState::Unknown),
                 Some(state) => match state.status {
                     Some(ContainerStateStatusEnum::CREATED)
@@ -155,9 +500,13 @@ impl<T: backends::docker::DockerBackend> ContainerProvider for ContainerProvider
                     | Some(ContainerStateStatusEnum::PAUSED) => Ok(ContainerState::Stopped),
                     Some(ContainerStateStatusEnum::RUNNING) => match state.health {
                         Some(Health {
-                            status: Some(HealthStatusEnum::NONE | HealthStatusEnum::UNHEALTHY),
+                            status: Some(HealthStatusEnum::NONE),
                             ..
                         }) => Ok(ContainerState::Running(GameState::Unknown)),
+                        Some(Health {
+                            status: Some(HealthStatusEnum::UNHEALTHY),
+                            ..
+                        }) => Ok(ContainerState::Running(GameState::Unhealthy)),
                         Some(Health {
                             status: Some(HealthStatusEnum::STARTING),
                             ..
@@ -175,42 +524,198 @@ impl<T: backends::docker::DockerBackend> ContainerProvider for ContainerProvider
                     None => Ok(ContainerState::Unknown),
                 },
             },
-            Ok(backends::docker::InspectResult::NotFound) => Ok(ContainerState::NotFound),
-            Err(()) => Err(()),
+            backends::docker::InspectResult::NotFound => Ok(ContainerState::NotFound),
         }
     }
 
-    fn get_container_rcon_address(&self, config: &Config) -> Result<(String, String), ()> {
-        match self.docker.inspect_container(&config.name) {
-            Ok(backends::docker::InspectResult::Ok(service::ContainerInspectResponse {
+    fn get_container_rcon_address(
+        &self,
+        config: &Config,
+    ) -> Result<(String, String), ContainerError> {
+        match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(service::ContainerInspectResponse {
                 network_settings:
                     Some(service::NetworkSettings {
                         ports: Some(ports), ..
                     }),
                 ..
-            })) => match ports.get("25575/tcp") {
-                Some(Some(bindings)) => {
-                    if bindings.len() != 1 {
-                        return Err(());
-                    }
-                    Ok((
-                        bindings[0].host_ip.as_ref().unwrap().to_owned(),
-                        bindings[0].host_port.as_ref().unwrap().to_owned(),
-                    ))
-                }
-                _ => Err(()),
+            }) => match ports.get("25575/tcp") {
+                Some(Some(bindings)) if bindings.len() == 1 => Ok((
+                    bindings[0].host_ip.as_ref().unwrap().to_owned(),
+                    bindings[0].host_port.as_ref().unwrap().to_owned(),
+                )),
+                _ => Err(ContainerError::RconAddressNotFound(config.name.clone())),
             },
-            _ => Err(()),
+            _ => Err(ContainerError::RconAddressNotFound(config.name.clone())),
         }
     }
 
-    fn display_container_logs(&self, config: &Config) -> Result<(), ()> {
-        for log_entry in self.docker.get_container_logs(&config.name) {
-            print!("{}", log_entry?);
+    fn display_container_logs(
+        &self,
+        config: &Config,
+        options: backends::docker::LogOptions,
+    ) -> Result<(), ContainerError> {
+        for log_entry in self.docker.get_container_logs(&config.name, options) {
+            match log_entry? {
+                backends::docker::LogEntry::Stdout(text) => print!("{}", text),
+                backends::docker::LogEntry::Stderr(text) => eprint!("{}", text),
+            }
         }
 
         Ok(())
     }
+
+    fn get_container_logs(
+        &self,
+        config: &Config,
+        since: Option<DateTime<Utc>>,
+        tail: Option<u64>,
+    ) -> Result<Vec<LogLine>, ContainerError> {
+        let mut lines = vec![];
+        for line in self.stream_container_logs(
+            config,
+            backends::docker::LogOptions {
+                follow: false,
+                tail,
+                since: since.map_or(0, |since| since.timestamp()),
+                ..std::default::Default::default()
+            },
+        ) {
+            let line = line?;
+            // The runtime only filters to the second, so drop the lines already seen
+            let is_new = match (since, line.timestamp) {
+                (Some(since), Some(timestamp)) => timestamp > since,
+                _ => true,
+            };
+            if is_new {
+                lines.push(line);
+            }
+        }
+
+        Ok(lines)
+    }
+
+    // Splits the log into lines along with the time each was written
+    fn stream_container_logs(
+        &self,
+        config: &Config,
+        options: backends::docker::LogOptions,
+    ) -> Box<dyn Iterator<Item = Result<LogLine, ContainerError>>> {
+        let log_entries = self.docker.get_container_logs(
+            &config.name,
+            backends::docker::LogOptions {
+                timestamps: true,
+                ..options
+            },
+        );
+
+        Box::new(log_entries.flat_map(|log_entry| {
+            match log_entry {
+                Ok(log_entry) => log_entry
+                    .text()
+                    .lines()
+                    .map(|line| Ok(parse_log_line(line)))
+                    .collect::<Vec<_>>(),
+                Err(err) => vec![Err(err.into())],
+            }
+        }))
+    }
+
+    fn get_container_image(&self, config: &Config) -> Result<Option<String>, ContainerError> {
+        match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(service::ContainerInspectResponse {
+                config: Some(container_config),
+                ..
+            }) => Ok(container_config
+                .labels
+                .and_then(|mut labels| labels.remove(IMAGE_LABEL))
+                .or(container_config.image)),
+            _ => Ok(None),
+        }
+    }
+
+    fn get_container_server_type(&self, config: &Config) -> Result<Option<String>, ContainerError> {
+        match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(response) => Ok(Some(server_type_from_env(
+                &response
+                    .config
+                    .and_then(|container_config| container_config.env)
+                    .unwrap_or_default(),
+            ))),
+            backends::docker::InspectResult::NotFound => Ok(None),
+        }
+    }
+
+    fn get_container_started_at(
+        &self,
+        config: &Config,
+    ) -> Result<Option<DateTime<Utc>>, ContainerError> {
+        match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(service::ContainerInspectResponse {
+                state:
+                    Some(service::ContainerState {
+                        running: Some(true),
+                        started_at: Some(started_at),
+                        ..
+                    }),
+                ..
+            }) => Ok(DateTime::parse_from_rfc3339(&started_at)
+                .ok()
+                .map(|started_at| started_at.with_timezone(&Utc))),
+            _ => Ok(None),
+        }
+    }
+
+    fn get_container_ports(&self, config: &Config) -> Result<Vec<PublishedPort>, ContainerError> {
+        let response = match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(response) => response,
+            backends::docker::InspectResult::NotFound => return Ok(vec![]),
+        };
+
+        // The network settings hold the ports assigned by the runtime while the container is
+        // running, otherwise fall back to the requested bindings
+        let host_config = response.host_config;
+        let port_map = response
+            .network_settings
+            .and_then(|network_settings| network_settings.ports)
+            .filter(|ports| ports.values().any(Option::is_some))
+            .or_else(|| host_config.and_then(|host_config| host_config.port_bindings))
+            .unwrap_or_default();
+
+        let mut ports: Vec<PublishedPort> = port_map
+            .into_iter()
+            .flat_map(|(container, bindings)| {
+                bindings
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(move |binding| PublishedPort {
+                        container: container.clone(),
+                        host_ip: binding.host_ip,
+                        host_port: binding.host_port.filter(|port| !port.is_empty()),
+                    })
+            })
+            .collect();
+        ports.sort_by(|a, b| (&a.container, &a.host_ip).cmp(&(&b.container, &b.host_ip)));
+        Ok(ports)
+    }
+
+    fn has_config_drift(
+        &self,
+        config: &Config,
+        data_path: &Path,
+    ) -> Result<Option<bool>, ContainerError> {
+        let expected_hash = config_hash(&self.build_container_config(config, data_path)?);
+
+        // Containers created before the hash was recorded can't be compared
+        match self.docker.inspect_container(&config.name)? {
+            backends::docker::InspectResult::Ok(response) => Ok(response
+                .config
+                .and_then(|container_config| container_config.labels)
+                .and_then(|mut labels| labels.remove(CONFIG_HASH_LABEL))
+                .map(|hash| hash != expected_hash)),
+            backends::docker::InspectResult::NotFound => Ok(Some(false)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -219,14 +724,43 @@ mod tests {
 
     use super::*;
     use crate::config;
-    use crate::providers::backends::docker::MockDockerBackend;
+    use crate::providers::backends::docker::{DockerError, LogEntry, MockDockerBackend};
 
     fn get_container_provider() -> ContainerProviderImpl<MockDockerBackend> {
+        let mut docker = MockDockerBackend::new();
+        docker.expect_is_remote().return_const(false);
+
         ContainerProviderImpl {
-            docker: MockDockerBackend::new(),
+            docker,
+            unprivileged_port_start: || 1024,
+        }
+    }
+
+    fn get_docker_error() -> DockerError {
+        DockerError {
+            message: "error".to_owned(),
+            source: bollard::errors::Error::DockerResponseServerError {
+                status_code: 500,
+                message: "error".to_owned(),
+            },
+        }
+    }
+
+    fn get_image() -> bollard::service::Image {
+        bollard::service::Image {
+            repo_digests: Some(vec!["itzg/minecraft-server@sha256:abc".to_owned()]),
+            ..std::default::Default::default()
         }
     }
 
+    fn expect_inspect_image(container_provider: &mut ContainerProviderImpl<MockDockerBackend>) {
+        container_provider
+            .docker
+            .expect_inspect_image()
+            .with(eq("docker.io/itzg/minecraft-server:java17"))
+            .returning(|_| Ok(get_image()));
+    }
+
     fn get_config() -> config::Config {
         config::Config {
             name: "name".to_owned(),
@@ -250,20 +784,25 @@ mod tests {
         container_provider
             .docker
             .expect_download_image()
-            .with(eq("itzg/minecraft-server"), eq("latest"))
+            .with(eq("docker.io/itzg/minecraft-server"), eq("java17"))
             .times(1)
             .returning(|_, _| Ok(()));
 
+        expect_inspect_image(&mut container_provider);
+
         container_provider
             .docker
             .expect_create_container()
             .withf(|name, container_config| {
                 name == "name"
-                    && container_config.image == Some("itzg/minecraft-server:latest".to_owned())
+                    && container_config.image
+                        == Some("docker.io/itzg/minecraft-server:java17".to_owned())
                     && container_config.env
                         == Some(vec![
                             String::from("EULA=true"),
                             String::from("VERSION=1.17.1"),
+                            String::from("ENABLE_RCON=true"),
+                            String::from("OVERRIDE_SERVER_PROPERTIES=false"),
                             String::from("TYPE=VANILLA"),
                         ])
                     && match &container_config.host_config {
@@ -305,10 +844,9 @@ mod tests {
             .times(1)
             .returning(|_, _| Ok(()));
 
-        assert_eq!(
-            Ok(()),
-            container_provider.create_container(&config, &data_path)
-        );
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
     }
 
     #[test]
@@ -321,20 +859,25 @@ mod tests {
         container_provider
             .docker
             .expect_download_image()
-            .with(eq("itzg/minecraft-server"), eq("latest"))
+            .with(eq("docker.io/itzg/minecraft-server"), eq("java17"))
             .times(1)
             .returning(|_, _| Ok(()));
 
+        expect_inspect_image(&mut container_provider);
+
         container_provider
             .docker
             .expect_create_container()
             .withf(|name, container_config| {
                 name == "name"
-                    && container_config.image == Some("itzg/minecraft-server:latest".to_owned())
+                    && container_config.image
+                        == Some("docker.io/itzg/minecraft-server:java17".to_owned())
                     && container_config.env
                         == Some(vec![
                             String::from("EULA=true"),
                             String::from("VERSION=1.17.1"),
+                            String::from("ENABLE_RCON=true"),
+                            String::from("OVERRIDE_SERVER_PROPERTIES=false"),
                             String::from("MEMORY=5G"),
                             String::from("TYPE=VANILLA"),
                         ])
@@ -342,146 +885,654 @@ mod tests {
             .times(1)
             .returning(|_, _| Ok(()));
 
-        assert_eq!(
-            Ok(()),
-            container_provider.create_container(&config, &data_path)
-        );
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
     }
 
     #[test]
-    fn test_create_container_cannot_download() {
+    fn test_create_container_with_resources() {
         let mut container_provider = get_container_provider();
-        let config = get_config();
+        let mut config = get_config();
+        config.server.jvm_opts = Some(String::from("-XX:+UseG1GC"));
+        config.server.use_aikar_flags = true;
+        config.container.resources = config::Resources {
+            cpus: Some(1.5),
+            memory: Some(String::from("2G")),
+            memory_reservation: Some(String::from("512m")),
+            pids_limit: Some(512),
+        };
         let data_path = PathBuf::from("path");
 
         container_provider
             .docker
             .expect_download_image()
-            .with(eq("itzg/minecraft-server"), eq("latest"))
+            .with(eq("docker.io/itzg/minecraft-server"), eq("java17"))
             .times(1)
-            .returning(|_, _| Err(()));
+            .returning(|_, _| Ok(()));
 
-        container_provider
-            .docker
-            .expect_inspect_image()
-            .with(eq("itzg/minecraft-server"))
-            .times(1)
-            .returning(|_| {
-                Ok(bollard::service::Image {
-                    repo_tags: Some(vec!["itzg/minecraft-server:latest".to_owned()]),
-                    ..std::default::Default::default()
-                })
-            });
+        expect_inspect_image(&mut container_provider);
 
         container_provider
             .docker
             .expect_create_container()
-            .withf(|name, container_config| {
-                name == "name"
-                    && container_config.image == Some("itzg/minecraft-server:latest".to_owned())
+            .withf(|_, container_config| {
+                container_config.env
+                    == Some(vec![
+                        String::from("EULA=true"),
+                        String::from("VERSION=1.17.1"),
+                        String::from("ENABLE_RCON=true"),
+                        String::from("OVERRIDE_SERVER_PROPERTIES=false"),
+                        String::from("JVM_OPTS=-XX:+UseG1GC"),
+                        String::from("USE_AIKAR_FLAGS=true"),
+                        String::from("TYPE=VANILLA"),
+                    ])
+                    && match &container_config.host_config {
+                        None => false,
+                        Some(host_config) => {
+                            host_config.nano_cpus == Some(1_500_000_000)
+                                && host_config.memory == Some(2 << 30)
+                                && host_config.memory_reservation == Some(512 << 20)
+                                && host_config.pids_limit == Some(512)
+                        }
+                    }
             })
             .times(1)
             .returning(|_, _| Ok(()));
 
-        assert_eq!(
-            Ok(()),
-            container_provider.create_container(&config, &data_path)
-        );
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
+    }
+
+    macro_rules! create_container_server_type_tests {
+        ($($name:ident: $server_type:expr, $expected_env:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut container_provider = get_container_provider();
+                let mut config = get_config();
+                config.server.server_type = $server_type;
+                let data_path = PathBuf::from("path");
+
+                container_provider
+                    .docker
+                    .expect_download_image()
+                    .with(eq("docker.io/itzg/minecraft-server"), eq("java17"))
+                    .times(1)
+                    .returning(|_, _| Ok(()));
+
+                expect_inspect_image(&mut container_provider);
+
+                container_provider
+                    .docker
+                    .expect_create_container()
+                    .withf(|name, container_config| {
+                        let mut expected_env = vec![
+                            String::from("EULA=true"),
+                            String::from("VERSION=1.17.1"),
+                            String::from("ENABLE_RCON=true"),
+                            String::from("OVERRIDE_SERVER_PROPERTIES=false"),
+                        ];
+                        expected_env.extend($expected_env.iter().map(|e: &&str| e.to_string()));
+
+                        name == "name" && container_config.env == Some(expected_env)
+                    })
+                    .times(1)
+                    .returning(|_, _| Ok(()));
+
+                assert!(container_provider.create_container(&config, &data_path).is_ok());
+            }
+        )*
+        }
+    }
+
+    create_container_server_type_tests! {
+        test_create_container_paper: config::ServerType::Paper { build: None }, ["TYPE=PAPER"];
+        test_create_container_paper_with_build: config::ServerType::Paper { build: Some("196".to_owned()) }, ["TYPE=PAPER", "PAPER_BUILD=196"];
+        test_create_container_spigot: config::ServerType::Spigot, ["TYPE=SPIGOT"];
+        test_create_container_purpur: config::ServerType::Purpur { build: None }, ["TYPE=PURPUR"];
+        test_create_container_purpur_with_build: config::ServerType::Purpur { build: Some("1428".to_owned()) }, ["TYPE=PURPUR", "PURPUR_BUILD=1428"];
+        test_create_container_fabric: config::ServerType::Fabric { loader_version: "0.11.6".to_owned(), installer_version: None }, ["TYPE=FABRIC", "FABRIC_LOADER_VERSION=0.11.6"];
+        test_create_container_fabric_with_installer: config::ServerType::Fabric { loader_version: "0.11.6".to_owned(), installer_version: Some("0.7.4".to_owned()) }, ["TYPE=FABRIC", "FABRIC_LOADER_VERSION=0.11.6", "FABRIC_INSTALLER_VERSION=0.7.4"];
+        test_create_container_quilt: config::ServerType::Quilt { loader_version: "0.16.0".to_owned(), installer_version: None }, ["TYPE=QUILT", "QUILT_LOADER_VERSION=0.16.0"];
+        test_create_container_forge: config::ServerType::Forge { forge_version: "37.0.103".to_owned() }, ["TYPE=FORGE", "FORGE_VERSION=37.0.103"];
+        test_create_container_neoforge: config::ServerType::NeoForge { neoforge_version: "20.4.237".to_owned() }, ["TYPE=NEOFORGE", "NEOFORGE_VERSION=20.4.237"];
+        test_create_container_quilt_with_installer: config::ServerType::Quilt { loader_version: "0.16.0".to_owned(), installer_version: Some("0.4.1".to_owned()) }, ["TYPE=QUILT", "QUILT_LOADER_VERSION=0.16.0", "QUILT_INSTALLER_VERSION=0.4.1"];
     }
 
     #[test]
-    fn test_delete_container() {
+    fn test_create_container_cannot_download() {
         let mut container_provider = get_container_provider();
         let config = get_config();
+        let data_path = PathBuf::from("path");
 
         container_provider
             .docker
-            .expect_delete_container()
-            .with(eq("name"))
+            .expect_download_image()
+            .with(eq("docker.io/itzg/minecraft-server"), eq("java17"))
             .times(1)
-            .returning(|_| Ok(()));
+            .returning(|_, _| Err(get_docker_error()));
+
+        expect_inspect_image(&mut container_provider);
+
+        container_provider
+            .docker
+            .expect_create_container()
+            .withf(|name, container_config| {
+                name == "name"
+                    && container_config.image
+                        == Some("docker.io/itzg/minecraft-server:java17".to_owned())
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
 
-        assert_eq!(Ok(()), container_provider.delete_container(&config));
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
     }
 
     #[test]
-    fn test_start_container() {
+    fn test_create_container_image_not_found() {
         let mut container_provider = get_container_provider();
         let config = get_config();
+        let data_path = PathBuf::from("path");
 
         container_provider
             .docker
-            .expect_start_container()
-            .with(eq("name"))
+            .expect_download_image()
             .times(1)
-            .returning(|_| Ok(()));
+            .returning(|_, _| Err(get_docker_error()));
+
+        container_provider
+            .docker
+            .expect_inspect_image()
+            .times(1)
+            .returning(|_| Err(get_docker_error()));
+
+        container_provider.docker.expect_create_container().times(0);
 
-        assert_eq!(Ok(()), container_provider.start_container(&config));
+        assert!(matches!(
+            container_provider.create_container(&config, &data_path),
+            Err(ContainerError::ImageNotFound { .. })
+        ));
     }
 
     #[test]
-    fn test_stop_container() {
+    fn test_create_container_inspect_fails() {
         let mut container_provider = get_container_provider();
         let config = get_config();
+        let data_path = PathBuf::from("path");
 
         container_provider
             .docker
-            .expect_stop_container()
-            .with(eq("name"))
+            .expect_download_image()
             .times(1)
-            .returning(|_| Ok(()));
+            .returning(|_, _| Ok(()));
 
-        assert_eq!(Ok(()), container_provider.stop_container(&config));
-    }
+        container_provider
+            .docker
+            .expect_inspect_image()
+            .times(1)
+            .returning(|_| Err(get_docker_error()));
 
-    mod test_get_container_status {
-        use bollard::models::{ContainerInspectResponse, ContainerState as DockerContainerState};
+        container_provider.docker.expect_create_container().times(0);
 
-        use super::*;
+        assert!(matches!(
+            container_provider.create_container(&config, &data_path),
+            Err(ContainerError::Docker(_))
+        ));
+    }
 
-        #[test]
-        fn inspect_result_not_found() {
-            let mut container_provider = get_container_provider();
-            let config = get_config();
+    macro_rules! create_container_image_tests {
+        ($($name:ident: $container:expr, $expected_tag:expr, $expected_image:expr, $repo_digests:expr, $expected_label:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut container_provider = get_container_provider();
+                let mut config = get_config();
+                config.container = $container;
+                let data_path = PathBuf::from("path");
+
+                container_provider
+                    .docker
+                    .expect_download_image()
+                    .with(eq("docker.io/itzg/minecraft-server"), eq($expected_tag))
+                    .times(1)
+                    .returning(|_, _| Ok(()));
+
+                container_provider
+                    .docker
+                    .expect_inspect_image()
+                    .with(eq($expected_image))
+                    .times(1)
+                    .returning(|_| {
+                        Ok(bollard::service::Image {
+                            repo_digests: $repo_digests.map(|digests: Vec<&str>| {
+                                digests.iter().map(|digest| digest.to_string()).collect()
+                            }),
+                            ..std::default::Default::default()
+                        })
+                    });
+
+                container_provider
+                    .docker
+                    .expect_create_container()
+                    .withf(|_, container_config| {
+                        container_config.image == Some($expected_image.to_owned())
+                            && container_config.labels.as_ref().and_then(|labels| labels.get(IMAGE_LABEL))
+                                == Some(&$expected_label.to_owned())
+                    })
+                    .times(1)
+                    .returning(|_, _| Ok(()));
+
+                assert!(container_provider.create_container(&config, &data_path).is_ok());
+            }
+        )*
+        }
+    }
 
-            container_provider
-                .docker
-                .expect_inspect_container()
-                .with(eq("name"))
-                .times(1)
-                .returning(|_| Ok(backends::docker::InspectResult::NotFound));
+    create_container_image_tests! {
+        test_create_container_default_image: config::Container::default(), "java17", "docker.io/itzg/minecraft-server:java17", Some(vec!["itzg/minecraft-server@sha256:abc"]), "docker.io/itzg/minecraft-server@sha256:abc";
+        test_create_container_podman_repo_digest: config::Container::default(), "java17", "docker.io/itzg/minecraft-server:java17", Some(vec!["docker.io/itzg/minecraft-server@sha256:abc"]), "docker.io/itzg/minecraft-server@sha256:abc";
+        test_create_container_other_repo_digest: config::Container::default(), "java17", "docker.io/itzg/minecraft-server:java17", Some(vec!["example.com/minecraft-server@sha256:abc"]), "docker.io/itzg/minecraft-server:java17";
+        test_create_container_no_repo_digests: config::Container::default(), "java17", "docker.io/itzg/minecraft-server:java17", None, "docker.io/itzg/minecraft-server:java17";
+        test_create_container_tag: config::Container { tag: Some("2024.6.1-java21".to_owned()), ..config::Container::default() }, "2024.6.1-java21", "docker.io/itzg/minecraft-server:2024.6.1-java21", Some(vec!["itzg/minecraft-server@sha256:abc"]), "docker.io/itzg/minecraft-server@sha256:abc";
+        test_create_container_digest: config::Container { digest: Some("sha256:abc".to_owned()), ..config::Container::default() }, "sha256:abc", "docker.io/itzg/minecraft-server@sha256:abc", Some(vec!["itzg/minecraft-server@sha256:abc"]), "docker.io/itzg/minecraft-server@sha256:abc";
+    }
 
-            assert_eq!(
-                Ok(ContainerState::NotFound),
-                container_provider.get_container_status(&config)
-            );
+    macro_rules! default_tag_tests {
+        ($($name:ident: $version:expr, $expected:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, default_tag($version));
+            }
+        )*
         }
+    }
 
-        #[test]
-        fn none_state() {
-            let mut container_provider = get_container_provider();
-            let config = get_config();
-
-            container_provider
-                .docker
-                .expect_inspect_container()
-                .with(eq("name"))
-                .times(1)
-                .returning(|_| {
-                    Ok(backends::docker::InspectResult::Ok(
-                        std::default::Default::default(),
-                    ))
-                });
+    default_tag_tests! {
+        test_default_tag_1_12: "1.12.2", "java8";
+        test_default_tag_1_16: "1.16.5", "java8";
+        test_default_tag_1_17: "1.17", "java17";
+        test_default_tag_1_20: "1.20", "java17";
+        test_default_tag_1_20_4: "1.20.4", "java17";
+        test_default_tag_1_20_5: "1.20.5", "java21";
+        test_default_tag_1_21: "1.21.1", "java21";
+        test_default_tag_pre_release: "1.21-pre1", "java21";
+        test_default_tag_snapshot: "24w14a", "latest";
+        test_default_tag_new_versioning: "26.1", "latest";
+    }
 
-            assert_eq!(
-                Ok(ContainerState::Unknown),
-                container_provider.get_container_status(&config)
-            );
+    macro_rules! create_container_privileged_port_tests {
+        ($($name:ident: $port:expr, $rootless:expr, $expected_rootless_checks:expr, $expected_ok:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut container_provider = get_container_provider();
+                let mut config = get_config();
+                config.port = $port;
+                let data_path = PathBuf::from("path");
+
+                container_provider
+                    .docker
+                    .expect_is_rootless()
+                    .times($expected_rootless_checks)
+                    .returning(|| Ok($rootless));
+
+                container_provider
+                    .docker
+                    .expect_download_image()
+                    .returning(|_, _| Ok(()));
+
+                expect_inspect_image(&mut container_provider);
+
+                container_provider
+                    .docker
+                    .expect_create_container()
+                    .times(if $expected_ok { 1 } else { 0 })
+                    .returning(|_, _| Ok(()));
+
+                let result = container_provider.create_container(&config, &data_path);
+                if $expected_ok {
+                    assert!(result.is_ok());
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(ContainerError::PrivilegedPort { port: $port, start: 1024 })
+                    ));
+                }
+            }
+        )*
         }
+    }
 
-        macro_rules! get_container_status_tests {
-            ($($name:ident: $docker_status:expr, $docker_health_status:expr, $result_state:expr;)*) => {
-            $(
+    create_container_privileged_port_tests! {
+        test_create_container_rootless_privileged_port: 80, true, 1, false;
+        test_create_container_rootful_privileged_port: 80, false, 1, true;
+        test_create_container_rootless_unprivileged_port: 25565, true, 0, true;
+    }
+
+    #[test]
+    fn test_create_container_rootless_privileged_extra_port() {
+        let mut container_provider = get_container_provider();
+        let mut config = get_config();
+        config.container.ports = vec![config::Port {
+            container: 8123,
+            host: Some(80),
+            protocol: config::Protocol::Tcp,
+            host_ip: None,
+        }];
+        let data_path = PathBuf::from("path");
+
+        container_provider
+            .docker
+            .expect_is_rootless()
+            .times(1)
+            .returning(|| Ok(true));
+
+        container_provider.docker.expect_create_container().times(0);
+
+        assert!(matches!(
+            container_provider.create_container(&config, &data_path),
+            Err(ContainerError::PrivilegedPort {
+                port: 80,
+                start: 1024
+            })
+        ));
+    }
+
+    #[test]
+    fn test_create_container_with_extras() {
+        let mut container_provider = get_container_provider();
+        let mut config = get_config();
+        config.container.restart_policy = config::RestartPolicy::UnlessStopped;
+        config.container.ports = vec![
+            config::Port {
+                container: 24454,
+                host: None,
+                protocol: config::Protocol::Udp,
+                host_ip: None,
+            },
+            config::Port {
+                container: 8123,
+                host: Some(8080),
+                protocol: config::Protocol::Tcp,
+                host_ip: Some("127.0.0.1".to_owned()),
+            },
+        ];
+        config.container.volumes = vec![
+            config::Volume {
+                source: "/srv/resources".to_owned(),
+                target: "/data/resources".to_owned(),
+                read_only: true,
+            },
+            config::Volume {
+                source: "dynmap".to_owned(),
+                target: "/data/dynmap".to_owned(),
+                read_only: false,
+            },
+        ];
+        config.container.env = vec![
+            ("TZ".to_owned(), "Europe/London".to_owned()),
+            ("ENABLE_WHITELIST".to_owned(), "true".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+        let data_path = PathBuf::from("path");
+
+        container_provider
+            .docker
+            .expect_download_image()
+            .returning(|_, _| Ok(()));
+
+        expect_inspect_image(&mut container_provider);
+
+        container_provider
+            .docker
+            .expect_create_container()
+            .withf(|_, container_config| {
+                let host_config = container_config.host_config.as_ref().unwrap();
+                let port_bindings = host_config.port_bindings.as_ref().unwrap();
+                let exposed_ports = container_config.exposed_ports.as_ref().unwrap();
+
+                container_config.env
+                    == Some(vec![
+                        String::from("EULA=true"),
+                        String::from("VERSION=1.17.1"),
+                        String::from("ENABLE_RCON=true"),
+                        String::from("OVERRIDE_SERVER_PROPERTIES=false"),
+                        String::from("ENABLE_WHITELIST=true"),
+                        String::from("TZ=Europe/London"),
+                        String::from("TYPE=VANILLA"),
+                    ])
+                    && host_config.binds
+                        == Some(vec![
+                            "path:/data".to_owned(),
+                            "/srv/resources:/data/resources:ro".to_owned(),
+                            "dynmap:/data/dynmap".to_owned(),
+                        ])
+                    && host_config.restart_policy
+                        == Some(RestartPolicy {
+                            name: Some(RestartPolicyNameEnum::UNLESS_STOPPED),
+                            maximum_retry_count: None,
+                        })
+                    && port_bindings.len() == 4
+                    && port_bindings.get("24454/udp")
+                        == Some(&Some(vec![PortBinding {
+                            host_ip: Some("0.0.0.0".to_owned()),
+                            host_port: Some("24454".to_owned()),
+                        }]))
+                    && port_bindings.get("8123/tcp")
+                        == Some(&Some(vec![PortBinding {
+                            host_ip: Some("127.0.0.1".to_owned()),
+                            host_port: Some("8080".to_owned()),
+                        }]))
+                    && exposed_ports.len() == 2
+                    && exposed_ports.contains_key("24454/udp")
+                    && exposed_ports.contains_key("8123/tcp")
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_create_container_with_query() {
+        let mut container_provider = get_container_provider();
+        let mut config = get_config();
+        config.query.enabled = true;
+        config.query.port = Some(25570);
+        let data_path = PathBuf::from("path");
+
+        container_provider
+            .docker
+            .expect_download_image()
+            .returning(|_, _| Ok(()));
+
+        expect_inspect_image(&mut container_provider);
+
+        container_provider
+            .docker
+            .expect_create_container()
+            .withf(|_, container_config| {
+                let host_config = container_config.host_config.as_ref().unwrap();
+                let port_bindings = host_config.port_bindings.as_ref().unwrap();
+
+                port_bindings.len() == 3
+                    && port_bindings.get("25565/udp")
+                        == Some(&Some(vec![PortBinding {
+                            host_ip: Some("0.0.0.0".to_owned()),
+                            host_port: Some("25570".to_owned()),
+                        }]))
+                    && container_config
+                        .exposed_ports
+                        .as_ref()
+                        .unwrap()
+                        .contains_key("25565/udp")
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
+    }
+
+    fn get_remote_container_provider() -> ContainerProviderImpl<MockDockerBackend> {
+        let mut docker = MockDockerBackend::new();
+        docker.expect_is_remote().return_const(true);
+
+        ContainerProviderImpl {
+            docker,
+            unprivileged_port_start: || panic!("The local sysctl must not be read"),
+        }
+    }
+
+    #[test]
+    fn test_create_container_remote_data_path() {
+        let mut container_provider = get_remote_container_provider();
+        let mut config = get_config();
+        config.runtime.data_path = Some("/srv/minecraft".to_owned());
+        let data_path = PathBuf::from("path");
+
+        container_provider
+            .docker
+            .expect_download_image()
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        expect_inspect_image(&mut container_provider);
+
+        container_provider
+            .docker
+            .expect_create_container()
+            .withf(|_, container_config| match &container_config.host_config {
+                None => false,
+                Some(host_config) => {
+                    host_config.binds == Some(vec!["/srv/minecraft:/data".to_owned()])
+                }
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(container_provider
+            .create_container(&config, &data_path)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_create_container_remote_without_data_path() {
+        let mut container_provider = get_remote_container_provider();
+        let config = get_config();
+        let data_path = PathBuf::from("path");
+
+        container_provider.docker.expect_download_image().times(0);
+        container_provider.docker.expect_create_container().times(0);
+
+        assert!(matches!(
+            container_provider.create_container(&config, &data_path),
+            Err(ContainerError::RemoteDataPathRequired)
+        ));
+    }
+
+    #[test]
+    fn test_delete_container() {
+        let mut container_provider = get_container_provider();
+        let config = get_config();
+
+        container_provider
+            .docker
+            .expect_delete_container()
+            .with(eq("name"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(container_provider.delete_container(&config).is_ok());
+    }
+
+    #[test]
+    fn test_start_container() {
+        let mut container_provider = get_container_provider();
+        let config = get_config();
+
+        container_provider
+            .docker
+            .expect_start_container()
+            .with(eq("name"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(container_provider.start_container(&config).is_ok());
+    }
+
+    #[test]
+    fn test_stop_container() {
+        let mut container_provider = get_container_provider();
+        let config = get_config();
+
+        container_provider
+            .docker
+            .expect_stop_container()
+            .with(eq("name"), eq(30))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(container_provider.stop_container(&config, 30).is_ok());
+    }
+
+    mod test_get_container_status {
+        use bollard::models::{ContainerInspectResponse, ContainerState as DockerContainerState};
+
+        use super::*;
+
+        #[test]
+        fn inspect_result_not_found() {
+            let mut container_provider = get_container_provider();
+            let config = get_config();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .returning(|_| Ok(backends::docker::InspectResult::NotFound));
+
+            assert_eq!(
+                ContainerState::NotFound,
+                container_provider.get_container_status(&config).unwrap()
+            );
+        }
+
+        #[test]
+        fn none_state() {
+            let mut container_provider = get_container_provider();
+            let config = get_config();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .returning(|_| {
+                    Ok(backends::docker::InspectResult::Ok(
+                        std::default::Default::default(),
+                    ))
+                });
+
+            assert_eq!(
+                ContainerState::Unknown,
+                container_provider.get_container_status(&config).unwrap()
+            );
+        }
+
+        macro_rules! get_container_status_tests {
+            ($($name:ident: $docker_status:expr, $docker_health_status:expr, $result_state:expr;)*) => {
+            $(
                 #[test]
                 fn $name() {
                     let mut container_provider = get_container_provider();
@@ -508,8 +1559,8 @@ mod tests {
                         })));
 
                     assert_eq!(
-                        Result::<ContainerState, ()>::Ok($result_state),
-                        container_provider.get_container_status(&config),
+                        $result_state,
+                        container_provider.get_container_status(&config).unwrap(),
                     );
                 }
             )*
@@ -526,7 +1577,7 @@ mod tests {
             status_running_none: Some(ContainerStateStatusEnum::RUNNING), Some(HealthStatusEnum::NONE), ContainerState::Running(GameState::Unknown);
             status_running_starting: Some(ContainerStateStatusEnum::RUNNING), Some(HealthStatusEnum::STARTING), ContainerState::Running(GameState::Starting);
             status_running_healthy: Some(ContainerStateStatusEnum::RUNNING), Some(HealthStatusEnum::HEALTHY), ContainerState::Running(GameState::Running);
-            status_running_unhealthy: Some(ContainerStateStatusEnum::RUNNING), Some(HealthStatusEnum::UNHEALTHY), ContainerState::Running(GameState::Unknown);
+            status_running_unhealthy: Some(ContainerStateStatusEnum::RUNNING), Some(HealthStatusEnum::UNHEALTHY), ContainerState::Running(GameState::Unhealthy);
             status_restarting: Some(ContainerStateStatusEnum::RESTARTING), None::<HealthStatusEnum>, ContainerState::Running(GameState::Unknown);
             status_removing: Some(ContainerStateStatusEnum::REMOVING), None::<HealthStatusEnum>, ContainerState::NotFound;
         }
@@ -580,8 +1631,10 @@ mod tests {
                 });
 
             assert_eq!(
-                Ok(("host".to_owned(), "port".to_owned())),
-                container_provider.get_container_rcon_address(&config)
+                ("host".to_owned(), "port".to_owned()),
+                container_provider
+                    .get_container_rcon_address(&config)
+                    .unwrap()
             );
         }
 
@@ -607,33 +1660,583 @@ mod tests {
                     ))
                 });
 
+            assert!(matches!(
+                container_provider.get_container_rcon_address(&config),
+                Err(ContainerError::RconAddressNotFound(_))
+            ));
+        }
+    }
+
+    mod test_get_container_image {
+        use super::*;
+
+        fn get_inspect_result(
+            labels: Option<HashMap<String, String>>,
+        ) -> backends::docker::InspectResult {
+            backends::docker::InspectResult::Ok(service::ContainerInspectResponse {
+                config: Some(service::ContainerConfig {
+                    image: Some("docker.io/itzg/minecraft-server:java17".to_owned()),
+                    labels,
+                    ..std::default::Default::default()
+                }),
+                ..std::default::Default::default()
+            })
+        }
+
+        #[test]
+        fn label() {
+            let mut container_provider = get_container_provider();
+            let mut labels = HashMap::new();
+            labels.insert(
+                IMAGE_LABEL.to_owned(),
+                "docker.io/itzg/minecraft-server@sha256:abc".to_owned(),
+            );
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .return_once(move |_| Ok(get_inspect_result(Some(labels))));
+
+            assert_eq!(
+                Some("docker.io/itzg/minecraft-server@sha256:abc".to_owned()),
+                container_provider
+                    .get_container_image(&get_config())
+                    .unwrap()
+            );
+        }
+
+        #[test]
+        fn no_label() {
+            let mut container_provider = get_container_provider();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .return_once(|_| Ok(get_inspect_result(None)));
+
             assert_eq!(
-                Err(()),
-                container_provider.get_container_rcon_address(&config)
+                Some("docker.io/itzg/minecraft-server:java17".to_owned()),
+                container_provider
+                    .get_container_image(&get_config())
+                    .unwrap()
+            );
+        }
+
+        #[test]
+        fn not_found() {
+            let mut container_provider = get_container_provider();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .returning(|_| Ok(backends::docker::InspectResult::NotFound));
+
+            assert_eq!(
+                None,
+                container_provider
+                    .get_container_image(&get_config())
+                    .unwrap()
             );
         }
     }
 
+    mod test_get_container_server_type {
+        use super::*;
+
+        fn get_server_type(env: Vec<&str>) -> Option<String> {
+            let mut container_provider = get_container_provider();
+            let env = env.into_iter().map(str::to_owned).collect();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .return_once(move |_| {
+                    Ok(backends::docker::InspectResult::Ok(
+                        service::ContainerInspectResponse {
+                            config: Some(service::ContainerConfig {
+                                env: Some(env),
+                                ..std::default::Default::default()
+                            }),
+                            ..std::default::Default::default()
+                        },
+                    ))
+                });
+
+            container_provider
+                .get_container_server_type(&get_config())
+                .unwrap()
+        }
+
+        macro_rules! get_container_server_type_tests {
+            ($($name:ident: $env:expr, $expected:expr;)*) => {
+                $(
+                    #[test]
+                    fn $name() {
+                        assert_eq!(Some($expected.to_owned()), get_server_type($env));
+                    }
+                )*
+            }
+        }
+
+        get_container_server_type_tests! {
+            unset: vec!["EULA=TRUE"], "Vanilla";
+            paper: vec!["TYPE=PAPER", "PAPER_BUILD=196"], "Paper";
+            fabric: vec!["TYPE=FABRIC", "FABRIC_LOADER_VERSION=0.11.6"], "Fabric 0.11.6";
+            quilt: vec!["TYPE=QUILT", "QUILT_LOADER_VERSION=0.16.0"], "Quilt 0.16.0";
+            forge: vec!["TYPE=FORGE", "FORGE_VERSION=37.0.103"], "Forge 37.0.103";
+            neoforge: vec!["TYPE=NEOFORGE", "NEOFORGE_VERSION=20.4.237"], "NeoForge 20.4.237";
+            forge_without_version: vec!["TYPE=FORGE"], "Forge";
+            unknown: vec!["TYPE=MOHIST"], "MOHIST";
+        }
+
+        #[test]
+        fn not_found() {
+            let mut container_provider = get_container_provider();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .returning(|_| Ok(backends::docker::InspectResult::NotFound));
+
+            assert_eq!(
+                None,
+                container_provider
+                    .get_container_server_type(&get_config())
+                    .unwrap()
+            );
+        }
+    }
+
+    mod test_get_container_started_at {
+        use super::*;
+
+        fn get_started_at(running: bool, started_at: &str) -> Option<DateTime<Utc>> {
+            let mut container_provider = get_container_provider();
+            let started_at = started_at.to_owned();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .return_once(move |_| {
+                    Ok(backends::docker::InspectResult::Ok(
+                        service::ContainerInspectResponse {
+                            state: Some(service::ContainerState {
+                                running: Some(running),
+                                started_at: Some(started_at),
+                                ..std::default::Default::default()
+                            }),
+                            ..std::default::Default::default()
+                        },
+                    ))
+                });
+
+            container_provider
+                .get_container_started_at(&get_config())
+                .unwrap()
+        }
+
+        #[test]
+        fn running() {
+            assert_eq!(
+                Some(
+                    DateTime::parse_from_rfc3339("2024-06-01T12:00:00.5+00:00")
+                        .unwrap()
+                        .with_timezone(&Utc)
+                ),
+                get_started_at(true, "2024-06-01T12:00:00.5+00:00")
+            );
+        }
+
+        #[test]
+        fn stopped() {
+            assert_eq!(None, get_started_at(false, "2024-06-01T12:00:00Z"));
+        }
+
+        #[test]
+        fn invalid() {
+            assert_eq!(None, get_started_at(true, ""));
+        }
+    }
+
+    mod test_get_container_ports {
+        use super::*;
+
+        fn get_port_map(ports: &[(&str, &str, &str)]) -> PortMap {
+            ports
+                .iter()
+                .map(|(container, host_ip, host_port)| {
+                    (
+                        container.to_string(),
+                        Some(vec![PortBinding {
+                            host_ip: Some(host_ip.to_string()),
+                            host_port: Some(host_port.to_string()),
+                        }]),
+                    )
+                })
+                .collect()
+        }
+
+        fn get_ports(
+            network_ports: Option<PortMap>,
+            port_bindings: Option<PortMap>,
+        ) -> Vec<PublishedPort> {
+            let mut container_provider = get_container_provider();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .return_once(move |_| {
+                    Ok(backends::docker::InspectResult::Ok(
+                        service::ContainerInspectResponse {
+                            network_settings: Some(service::NetworkSettings {
+                                ports: network_ports,
+                                ..std::default::Default::default()
+                            }),
+                            host_config: Some(HostConfig {
+                                port_bindings,
+                                ..std::default::Default::default()
+                            }),
+                            ..std::default::Default::default()
+                        },
+                    ))
+                });
+
+            container_provider
+                .get_container_ports(&get_config())
+                .unwrap()
+        }
+
+        #[test]
+        fn running() {
+            assert_eq!(
+                vec![
+                    PublishedPort {
+                        container: "25565/tcp".to_owned(),
+                        host_ip: Some("0.0.0.0".to_owned()),
+                        host_port: Some("25565".to_owned()),
+                    },
+                    PublishedPort {
+                        container: "25575/tcp".to_owned(),
+                        host_ip: Some("127.0.0.1".to_owned()),
+                        host_port: Some("49153".to_owned()),
+                    },
+                ],
+                get_ports(
+                    Some(get_port_map(&[
+                        ("25575/tcp", "127.0.0.1", "49153"),
+                        ("25565/tcp", "0.0.0.0", "25565"),
+                    ])),
+                    Some(get_port_map(&[
+                        ("25565/tcp", "0.0.0.0", "25565"),
+                        ("25575/tcp", "127.0.0.1", ""),
+                    ])),
+                )
+            );
+        }
+
+        #[test]
+        fn stopped() {
+            assert_eq!(
+                vec![
+                    PublishedPort {
+                        container: "25565/tcp".to_owned(),
+                        host_ip: Some("0.0.0.0".to_owned()),
+                        host_port: Some("25565".to_owned()),
+                    },
+                    PublishedPort {
+                        container: "25575/tcp".to_owned(),
+                        host_ip: Some("127.0.0.1".to_owned()),
+                        host_port: None,
+                    },
+                ],
+                get_ports(
+                    Some(PortMap::new()),
+                    Some(get_port_map(&[
+                        ("25565/tcp", "0.0.0.0", "25565"),
+                        ("25575/tcp", "127.0.0.1", ""),
+                    ])),
+                )
+            );
+        }
+
+        #[test]
+        fn not_found() {
+            let mut container_provider = get_container_provider();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .returning(|_| Ok(backends::docker::InspectResult::NotFound));
+
+            assert!(container_provider
+                .get_container_ports(&get_config())
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_config_hash() {
+        let container_provider = get_container_provider();
+        let config = get_config();
+        let mut changed_config = get_config();
+        changed_config.port = 25566;
+        let data_path = PathBuf::from("path");
+        let get_hash = |config| {
+            config_hash(
+                &container_provider
+                    .build_container_config(config, &data_path)
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(get_hash(&config), get_hash(&config));
+        assert_ne!(get_hash(&config), get_hash(&changed_config));
+    }
+
+    mod test_has_config_drift {
+        use super::*;
+
+        fn expected_hash() -> String {
+            config_hash(
+                &get_container_provider()
+                    .build_container_config(&get_config(), &PathBuf::from("path"))
+                    .unwrap(),
+            )
+        }
+
+        fn setup(config_hash: Option<String>) -> ContainerProviderImpl<MockDockerBackend> {
+            let mut container_provider = get_container_provider();
+            let labels = config_hash.map(|config_hash| {
+                let mut labels = HashMap::new();
+                labels.insert(CONFIG_HASH_LABEL.to_owned(), config_hash);
+                labels
+            });
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .with(eq("name"))
+                .times(1)
+                .return_once(move |_| {
+                    Ok(backends::docker::InspectResult::Ok(
+                        service::ContainerInspectResponse {
+                            config: Some(service::ContainerConfig {
+                                labels,
+                                ..std::default::Default::default()
+                            }),
+                            ..std::default::Default::default()
+                        },
+                    ))
+                });
+
+            container_provider
+        }
+
+        fn has_config_drift(
+            container_provider: &ContainerProviderImpl<MockDockerBackend>,
+        ) -> Option<bool> {
+            container_provider
+                .has_config_drift(&get_config(), &PathBuf::from("path"))
+                .unwrap()
+        }
+
+        #[test]
+        fn unchanged() {
+            assert_eq!(Some(false), has_config_drift(&setup(Some(expected_hash()))));
+        }
+
+        #[test]
+        fn changed() {
+            assert_eq!(
+                Some(true),
+                has_config_drift(&setup(Some("0000000000000000".to_owned())))
+            );
+        }
+
+        #[test]
+        fn missing_label() {
+            assert_eq!(None, has_config_drift(&setup(None)));
+        }
+
+        #[test]
+        fn not_found() {
+            let mut container_provider = get_container_provider();
+
+            container_provider
+                .docker
+                .expect_inspect_container()
+                .times(1)
+                .returning(|_| Ok(backends::docker::InspectResult::NotFound));
+
+            assert_eq!(Some(false), has_config_drift(&container_provider));
+        }
+    }
+
     #[test]
     fn test_display_container_logs() {
         let config = get_config();
         let mut container_provider = get_container_provider();
+        let get_options = || backends::docker::LogOptions {
+            follow: false,
+            tail: Some(10),
+            since: 1717243200,
+            until: 1717246800,
+            timestamps: true,
+        };
 
         container_provider
             .docker
             .expect_get_container_logs()
-            .with(eq("name"))
+            .with(eq("name"), eq(get_options()))
+            .times(1)
+            .returning(|_, _| {
+                Box::new(
+                    vec![
+                        Result::<LogEntry, DockerError>::Ok(LogEntry::Stdout("test1".to_owned())),
+                        Result::<LogEntry, DockerError>::Ok(LogEntry::Stderr("test2".to_owned())),
+                    ]
+                    .into_iter(),
+                )
+            });
+
+        assert!(container_provider
+            .display_container_logs(&config, get_options())
+            .is_ok());
+    }
+
+    macro_rules! parse_log_line_tests {
+        ($($name:ident: $line:expr, $timestamp:expr, $text:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(
+                    LogLine {
+                        timestamp: $timestamp.map(|timestamp: &str| timestamp.parse().unwrap()),
+                        text: $text.to_owned(),
+                    },
+                    parse_log_line($line)
+                );
+            }
+        )*
+        }
+    }
+
+    parse_log_line_tests! {
+        test_parse_log_line: "2024-06-01T12:00:00.123456789Z [12:00:00] [Server thread/INFO]: Done", Some("2024-06-01T12:00:00.123456789Z"), "[12:00:00] [Server thread/INFO]: Done",
+        test_parse_log_line_empty_text: "2024-06-01T12:00:00Z ", Some("2024-06-01T12:00:00Z"), "",
+        test_parse_log_line_no_timestamp: "[init] Running as uid=1000", None::<&str>, "[init] Running as uid=1000",
+        test_parse_log_line_no_space: "text", None::<&str>, "text",
+    }
+
+    #[test]
+    fn test_get_container_logs() {
+        let config = get_config();
+        let mut container_provider = get_container_provider();
+        let since: DateTime<Utc> = "2024-06-01T12:00:00.5Z".parse().unwrap();
+
+        container_provider
+            .docker
+            .expect_get_container_logs()
+            .with(
+                eq("name"),
+                eq(backends::docker::LogOptions {
+                    follow: false,
+                    tail: Some(20),
+                    since: since.timestamp(),
+                    until: 0,
+                    timestamps: true,
+                }),
+            )
             .times(1)
-            .returning(|_| {
+            .returning(|_, _| {
                 Box::new(
                     vec![
-                        Result::<String, ()>::Ok("test1".to_owned()),
-                        Result::<String, ()>::Ok("test2".to_owned()),
+                        Result::<LogEntry, DockerError>::Ok(LogEntry::Stdout(
+                            "2024-06-01T12:00:00.25Z seen\n".to_owned(),
+                        )),
+                        Result::<LogEntry, DockerError>::Ok(LogEntry::Stdout(
+                            "2024-06-01T12:00:00.75Z first\n2024-06-01T12:00:01Z second\n"
+                                .to_owned(),
+                        )),
                     ]
                     .into_iter(),
                 )
             });
 
-        assert_eq!(Ok(()), container_provider.display_container_logs(&config));
+        assert_eq!(
+            vec!["first", "second"],
+            container_provider
+                .get_container_logs(&config, Some(since), Some(20))
+                .unwrap()
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn test_stream_container_logs() {
+        let config = get_config();
+        let mut container_provider = get_container_provider();
+
+        container_provider
+            .docker
+            .expect_get_container_logs()
+            .with(
+                eq("name"),
+                eq(backends::docker::LogOptions {
+                    follow: true,
+                    tail: Some(0),
+                    timestamps: true,
+                    ..std::default::Default::default()
+                }),
+            )
+            .times(1)
+            .returning(|_, _| {
+                Box::new(
+                    vec![
+                        Ok(LogEntry::Stdout(
+                            "2024-06-01T12:00:00Z first\n2024-06-01T12:00:01Z second\n".to_owned(),
+                        )),
+                        Err(get_docker_error()),
+                    ]
+                    .into_iter(),
+                )
+            });
+
+        let lines: Vec<Result<LogLine, ContainerError>> = container_provider
+            .stream_container_logs(
+                &config,
+                backends::docker::LogOptions {
+                    follow: true,
+                    tail: Some(0),
+                    ..std::default::Default::default()
+                },
+            )
+            .collect();
+        assert_eq!(3, lines.len());
+        assert_eq!("first", lines[0].as_ref().unwrap().text);
+        assert_eq!(
+            Some("2024-06-01T12:00:01Z".parse().unwrap()),
+            lines[1].as_ref().unwrap().timestamp
+        );
+        assert!(lines[2].is_err());
     }
 }
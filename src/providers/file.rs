@@ -1,22 +1,88 @@
-use crate::config::Config;
+use crate::config::{self, Config};
+use chrono::{Datelike, NaiveDateTime};
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
 use std::path;
 
 use crate::providers::backends::filesystem::{self, FilesystemBackend, FilesystemBackendImpl};
 
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const BACKUP_EXTENSION: &str = ".tar.gz";
+const RCON_PASSWORD_FILE: &str = ".rcon-password";
+const RCON_PASSWORD_LENGTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FileError {
+    #[error(transparent)]
+    Filesystem(#[from] filesystem::FilesystemError),
+    #[error(
+        "The property \"{0}\" is managed by minecraft-compose and cannot be set in [properties]"
+    )]
+    ManagedProperty(String),
+    #[error(
+        "No rcon password found in {}, the container may need to be recreated",
+        .0.display()
+    )]
+    MissingRconPassword(path::PathBuf),
+    #[error("The world \"{0}\" does not exist")]
+    WorldNotFound(String),
+    #[error("The backup \"{0}\" does not exist")]
+    BackupNotFound(String),
+    #[error("The backup \"{}\" already exists", .0.display())]
+    BackupExists(path::PathBuf),
+    #[error(
+        "Failed to extract the backup, the previous world was kept with the suffix \"-{suffix}\""
+    )]
+    RestoreFailed {
+        suffix: String,
+        #[source]
+        source: filesystem::FilesystemError,
+    },
+    #[error(
+        "Failed to move the previous world aside, part of it was left with the suffix \"-{suffix}\""
+    )]
+    MoveAsideFailed {
+        suffix: String,
+        #[source]
+        source: filesystem::FilesystemError,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupArchive {
+    pub id: String,
+    pub path: path::PathBuf,
+    pub created: NaiveDateTime,
+}
+
 #[cfg_attr(test, mockall::automock)]
 pub trait FileProvider {
-    fn get_data_path(&self) -> Result<path::PathBuf, ()>;
-    fn create_data_folder(&self) -> Result<(), ()>;
-    fn create_and_populate_server_properties(&self, config: &Config) -> Result<(), ()>;
-    fn sync_datapacks(&self, config: &Config) -> Result<(), ()>;
+    fn get_data_path(&self) -> Result<path::PathBuf, FileError>;
+    fn create_data_folder(&self) -> Result<(), FileError>;
+    fn get_rcon_password(&self, config: &Config) -> Result<String, FileError>;
+    fn create_rcon_password(&self, config: &Config) -> Result<String, FileError>;
+    fn create_and_populate_server_properties(
+        &self,
+        config: &Config,
+        rcon_password: &str,
+    ) -> Result<(), FileError>;
+    fn sync_datapacks(&self, config: &Config) -> Result<(), FileError>;
+    fn sync_mods(&self, config: &Config) -> Result<(), FileError>;
+    fn sync_plugins(&self, config: &Config) -> Result<(), FileError>;
+    fn create_backup(&self, config: &Config) -> Result<path::PathBuf, FileError>;
+    fn list_backups(&self, config: &Config) -> Result<Vec<BackupArchive>, FileError>;
+    fn prune_backups(&self, config: &Config) -> Result<Vec<BackupArchive>, FileError>;
+    fn restore_backup(&self, config: &Config, id: &str) -> Result<BackupArchive, FileError>;
+    fn read_commands_file(&self, file_path: &path::Path) -> Result<String, FileError>;
 }
 
 pub struct FileProviderImpl<T: FilesystemBackend> {
     data_path: path::PathBuf,
     server_properties_path: path::PathBuf,
+    rcon_password_path: path::PathBuf,
     default_properties: HashMap<String, String>,
     filesystem_backend: T,
+    generate_password: fn() -> String,
 }
 
 impl<T: FilesystemBackend> FileProviderImpl<T> {
@@ -28,24 +94,25 @@ impl<T: FilesystemBackend> FileProviderImpl<T> {
         default_properties.insert("server-port".to_owned(), "25565".to_owned());
         default_properties.insert("enable-rcon".to_owned(), "true".to_owned());
         default_properties.insert("rcon.port".to_owned(), "25575".to_owned());
-        default_properties.insert("rcon.password".to_owned(), "minecraft".to_owned());
         default_properties.insert("broadcast-rcon-to-ops".to_owned(), "true".to_owned());
 
         FileProviderImpl {
             data_path,
             server_properties_path,
+            rcon_password_path: path::Path::new(RCON_PASSWORD_FILE).to_owned(),
             default_properties,
             filesystem_backend: filesystem_backend,
+            generate_password,
         }
     }
 }
 
 impl<T: FilesystemBackend> FileProvider for FileProviderImpl<T> {
-    fn get_data_path(&self) -> Result<path::PathBuf, ()> {
-        self.filesystem_backend.canonicalize_path(&self.data_path)
+    fn get_data_path(&self) -> Result<path::PathBuf, FileError> {
+        Ok(self.filesystem_backend.canonicalize_path(&self.data_path)?)
     }
 
-    fn create_data_folder(&self) -> Result<(), ()> {
+    fn create_data_folder(&self) -> Result<(), FileError> {
         if !self.filesystem_backend.directory_exists(&self.data_path) {
             self.filesystem_backend.create_directory(&self.data_path)?;
         }
@@ -53,31 +120,109 @@ impl<T: FilesystemBackend> FileProvider for FileProviderImpl<T> {
         Ok(())
     }
 
-    fn create_and_populate_server_properties(&self, config: &Config) -> Result<(), ()> {
-        let server_properties = match self
+    fn get_rcon_password(&self, config: &Config) -> Result<String, FileError> {
+        if let Some(password) = &config.rcon.password {
+            return Ok(password.clone());
+        }
+
+        if !self
             .filesystem_backend
-            .file_exists(&self.server_properties_path)
+            .file_exists(&self.rcon_password_path)
         {
-            true => self
+            return Err(FileError::MissingRconPassword(
+                self.rcon_password_path.clone(),
+            ));
+        }
+
+        Ok(self
+            .filesystem_backend
+            .read_file(&self.rcon_password_path)?
+            .trim()
+            .to_owned())
+    }
+
+    fn create_rcon_password(&self, config: &Config) -> Result<String, FileError> {
+        if config.rcon.password.is_some()
+            || self
                 .filesystem_backend
-                .read_file(&self.server_properties_path)?,
-            false => "".to_owned(),
-        };
+                .file_exists(&self.rcon_password_path)
+        {
+            return self.get_rcon_password(config);
+        }
 
-        let mut properties_to_remove = HashSet::new();
-        let mut properties_to_set = self.default_properties.clone();
+        let password = (self.generate_password)();
+        self.filesystem_backend
+            .write_private_file(&self.rcon_password_path, &password)?;
+        log::info!(
+            "Generated an rcon password in {}",
+            self.rcon_password_path.display()
+        );
+
+        Ok(password)
+    }
 
-        properties_to_set.insert("level-name".to_owned(), config.world.name.clone());
-        properties_to_set.insert("gamemode".to_owned(), config.world.gamemode.clone());
-        properties_to_set.insert(
+    fn create_and_populate_server_properties(
+        &self,
+        config: &Config,
+        rcon_password: &str,
+    ) -> Result<(), FileError> {
+        let mut properties_to_remove = HashSet::new();
+        let mut managed_properties = self.default_properties.clone();
+
+        managed_properties.insert("rcon.password".to_owned(), rcon_password.to_owned());
+        managed_properties.insert("level-name".to_owned(), config.world.name.clone());
+        managed_properties.insert("gamemode".to_owned(), config.world.gamemode.to_string());
+        // These are only managed when set, so they can otherwise be edited in server.properties
+        if let Some(difficulty) = config.world.difficulty {
+            managed_properties.insert("difficulty".to_owned(), difficulty.to_string());
+        }
+        if let Some(hardcore) = config.world.hardcore {
+            managed_properties.insert("hardcore".to_owned(), hardcore.to_string());
+        }
+        if let Some(pvp) = config.world.pvp {
+            managed_properties.insert("pvp".to_owned(), pvp.to_string());
+        }
+        if let Some(max_players) = config.world.max_players {
+            managed_properties.insert("max-players".to_owned(), max_players.to_string());
+        }
+        if let Some(motd) = &config.world.motd {
+            managed_properties.insert("motd".to_owned(), motd.clone());
+        }
+        managed_properties.insert(
             "allow-flight".to_owned(),
             config.world.allow_flight.to_string(),
         );
+        if config.query.enabled {
+            managed_properties.insert("enable-query".to_owned(), "true".to_owned());
+            managed_properties.insert("query.port".to_owned(), "25565".to_owned());
+        }
         match &config.world.seed {
-            Some(seed) => drop(properties_to_set.insert("level-seed".to_owned(), seed.clone())),
+            Some(seed) => drop(managed_properties.insert("level-seed".to_owned(), seed.clone())),
             None => drop(properties_to_remove.insert("level-seed".to_owned())),
         };
 
+        let mut properties_to_set = HashMap::new();
+        if let Some(properties) = &config.properties {
+            for (key, value) in properties.iter() {
+                if managed_properties.contains_key(key) || properties_to_remove.contains(key) {
+                    return Err(FileError::ManagedProperty(key.to_owned()));
+                }
+
+                properties_to_set.insert(key.to_owned(), value.to_string());
+            }
+        }
+        properties_to_set.extend(managed_properties);
+
+        let server_properties = match self
+            .filesystem_backend
+            .file_exists(&self.server_properties_path)
+        {
+            true => self
+                .filesystem_backend
+                .read_file(&self.server_properties_path)?,
+            false => "".to_owned(),
+        };
+
         let mut keys = properties_to_set
             .keys()
             .clone()
@@ -109,72 +254,369 @@ impl<T: FilesystemBackend> FileProvider for FileProviderImpl<T> {
         Ok(())
     }
 
-    fn sync_datapacks(&self, config: &Config) -> Result<(), ()> {
-        let installed_datapacks_path = self.data_path.join(&config.world.name).join("datapacks");
-        if !self
+    fn sync_datapacks(&self, config: &Config) -> Result<(), FileError> {
+        self.sync_files(
+            "datapack",
+            &self.data_path.join(&config.world.name).join("datapacks"),
+            path::Path::new("datapacks"),
+            &config.datapacks,
+            "zip",
+            true,
+        )
+    }
+
+    fn sync_mods(&self, config: &Config) -> Result<(), FileError> {
+        self.sync_files(
+            "mod",
+            &self.data_path.join("mods"),
+            path::Path::new("mods"),
+            &config.mods,
+            "jar",
+            false,
+        )
+    }
+
+    fn sync_plugins(&self, config: &Config) -> Result<(), FileError> {
+        self.sync_files(
+            "plugin",
+            &self.data_path.join("plugins"),
+            path::Path::new("plugins"),
+            &config.plugins,
+            "jar",
+            false,
+        )
+    }
+
+    fn create_backup(&self, config: &Config) -> Result<path::PathBuf, FileError> {
+        let backup_path = path::Path::new(&config.backup.directory).to_path_buf();
+        if !self.filesystem_backend.directory_exists(&backup_path) {
+            self.filesystem_backend.create_directory(&backup_path)?;
+        }
+
+        let world_entries = get_world_entries(config)
+            .into_iter()
+            .filter(|entry| {
+                self.filesystem_backend
+                    .directory_exists(&self.data_path.join(entry))
+            })
+            .collect::<Vec<path::PathBuf>>();
+
+        if world_entries.is_empty() {
+            return Err(FileError::WorldNotFound(config.world.name.clone()));
+        }
+
+        let archive_path = backup_path.join(format!(
+            "{}-{}{}",
+            config.world.name,
+            chrono::Local::now().format(BACKUP_TIMESTAMP_FORMAT),
+            BACKUP_EXTENSION
+        ));
+        log::trace!(
+            "Archiving {:?} to \"{}\"",
+            world_entries,
+            archive_path.display()
+        );
+
+        // Backups are named to the second, so one taken in the same second as another fails
+        // rather than replacing it
+        match self
             .filesystem_backend
-            .directory_exists(&installed_datapacks_path)
+            .create_archive(&archive_path, &self.data_path, &world_entries)
         {
-            self.filesystem_backend
-                .create_directory(&installed_datapacks_path)?;
+            Ok(()) => Ok(archive_path),
+            Err(err) if err.source.kind() == std::io::ErrorKind::AlreadyExists => {
+                Err(FileError::BackupExists(archive_path))
+            }
+            Err(err) => Err(err.into()),
         }
+    }
+
+    fn list_backups(&self, config: &Config) -> Result<Vec<BackupArchive>, FileError> {
+        let backup_path = path::Path::new(&config.backup.directory).to_path_buf();
+        if !self.filesystem_backend.directory_exists(&backup_path) {
+            return Ok(vec![]);
+        }
+
+        let prefix = format!("{}-", config.world.name);
+        let mut backups = self
+            .filesystem_backend
+            .read_directory(&backup_path)?
+            .into_iter()
+            .filter_map(|entry| {
+                let file_name = entry.file_name()?.to_string_lossy().to_string();
+                let id = file_name
+                    .strip_prefix(&prefix)?
+                    .strip_suffix(BACKUP_EXTENSION)?
+                    .to_owned();
+                let created = NaiveDateTime::parse_from_str(&id, BACKUP_TIMESTAMP_FORMAT).ok()?;
+                Some(BackupArchive {
+                    id,
+                    path: entry,
+                    created,
+                })
+            })
+            .collect::<Vec<BackupArchive>>();
+
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+        Ok(backups)
+    }
+
+    fn prune_backups(&self, config: &Config) -> Result<Vec<BackupArchive>, FileError> {
+        if config.backup.retention.is_empty() {
+            log::warn!("No retention policy is configured, keeping all backups");
+            return Ok(vec![]);
+        }
+
+        let backups = self.list_backups(config)?;
+        let backups_to_keep = select_backups_to_keep(&backups, &config.backup.retention);
+
+        let mut pruned_backups = vec![];
+        for backup in backups {
+            if backups_to_keep.contains(&backup.id) {
+                continue;
+            }
+
+            log::trace!("Deleting backup \"{}\"", backup.path.display());
+            self.filesystem_backend.delete_file(&backup.path)?;
+            pruned_backups.push(backup);
+        }
+
+        Ok(pruned_backups)
+    }
+
+    fn restore_backup(&self, config: &Config, id: &str) -> Result<BackupArchive, FileError> {
+        let backups = self.list_backups(config)?;
+        let backup = match id {
+            "latest" => backups.into_iter().next(),
+            id => backups.into_iter().find(|backup| backup.id == id),
+        };
+        let backup = backup.ok_or_else(|| FileError::BackupNotFound(id.to_owned()))?;
+
+        let suffix = format!(
+            "before-restore-{}",
+            chrono::Local::now().format(BACKUP_TIMESTAMP_FORMAT)
+        );
+        let mut moved_entries = vec![];
+        for entry in get_world_entries(config) {
+            let entry_path = self.data_path.join(&entry);
+            if !self.filesystem_backend.directory_exists(&entry_path) {
+                continue;
+            }
+
+            let moved_path = self
+                .data_path
+                .join(format!("{}-{}", entry.display(), suffix));
+            log::info!(
+                "Moving \"{}\" to \"{}\"",
+                entry_path.display(),
+                moved_path.display()
+            );
+            if let Err(source) = self
+                .filesystem_backend
+                .rename_path(&entry_path, &moved_path)
+            {
+                return Err(self.undo_move_aside(moved_entries, suffix, source));
+            }
+            moved_entries.push((entry_path, moved_path));
+        }
+
+        self.filesystem_backend
+            .extract_archive(&backup.path, &self.data_path)
+            .map_err(|source| FileError::RestoreFailed { suffix, source })?;
+
+        Ok(backup)
+    }
 
-        let installed_datapacks_path = self
+    fn read_commands_file(&self, file_path: &path::Path) -> Result<String, FileError> {
+        Ok(self
             .filesystem_backend
-            .canonicalize_path(&installed_datapacks_path)?;
+            .read_file(&file_path.to_path_buf())?)
+    }
+}
+
+/// Bukkit based servers keep the nether and the end in their own folders
+/// next to the world folder instead of inside of it.
+fn get_world_entries(config: &Config) -> Vec<path::PathBuf> {
+    vec![
+        path::PathBuf::from(&config.world.name),
+        path::PathBuf::from(format!("{}_nether", config.world.name)),
+        path::PathBuf::from(format!("{}_the_end", config.world.name)),
+    ]
+}
+
+/// Selects the ids of the backups to keep, newest first. Each policy keeps the
+/// newest backup in each of the last N days, weeks or months that have a
+/// backup, and a backup is kept if any policy selects it.
+fn select_backups_to_keep(
+    backups: &[BackupArchive],
+    retention: &config::Retention,
+) -> HashSet<String> {
+    let mut sorted_backups = backups.iter().collect::<Vec<&BackupArchive>>();
+    sorted_backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+
+    let mut backups_to_keep = HashSet::new();
+    if let Some(keep_last) = retention.keep_last {
+        for backup in sorted_backups.iter().take(keep_last) {
+            backups_to_keep.insert(backup.id.clone());
+        }
+    }
+
+    keep_newest_per_period(
+        &sorted_backups,
+        retention.keep_daily,
+        |created| created.format("%Y-%m-%d").to_string(),
+        &mut backups_to_keep,
+    );
+    keep_newest_per_period(
+        &sorted_backups,
+        retention.keep_weekly,
+        |created| {
+            let week = created.iso_week();
+            format!("{}-{}", week.year(), week.week())
+        },
+        &mut backups_to_keep,
+    );
+    keep_newest_per_period(
+        &sorted_backups,
+        retention.keep_monthly,
+        |created| created.format("%Y-%m").to_string(),
+        &mut backups_to_keep,
+    );
+
+    backups_to_keep
+}
+
+fn keep_newest_per_period(
+    sorted_backups: &[&BackupArchive],
+    keep_count: Option<usize>,
+    get_period: fn(&NaiveDateTime) -> String,
+    backups_to_keep: &mut HashSet<String>,
+) {
+    let keep_count = match keep_count {
+        Some(keep_count) => keep_count,
+        None => return,
+    };
+
+    let mut periods = HashSet::new();
+    for backup in sorted_backups.iter() {
+        if periods.len() >= keep_count {
+            break;
+        }
+
+        if periods.insert(get_period(&backup.created)) {
+            backups_to_keep.insert(backup.id.clone());
+        }
+    }
+}
+
+impl<T: FilesystemBackend> FileProviderImpl<T> {
+    // Moves the folders already moved aside back so a failed restore leaves the world as it was,
+    // only pointing at the suffix if that fails too
+    fn undo_move_aside(
+        &self,
+        moved_entries: Vec<(path::PathBuf, path::PathBuf)>,
+        suffix: String,
+        source: filesystem::FilesystemError,
+    ) -> FileError {
+        for (entry_path, moved_path) in moved_entries.iter().rev() {
+            log::info!(
+                "Moving \"{}\" back to \"{}\"",
+                moved_path.display(),
+                entry_path.display()
+            );
+            if let Err(err) = self.filesystem_backend.rename_path(moved_path, entry_path) {
+                log::warn!("{}", err);
+                return FileError::MoveAsideFailed { suffix, source };
+            }
+        }
+
+        FileError::Filesystem(source)
+    }
+
+    fn sync_files(
+        &self,
+        kind: &str,
+        installed_path: &path::Path,
+        source_path: &path::Path,
+        files: &Option<HashMap<String, String>>,
+        extension: &str,
+        remove_all_unlisted: bool,
+    ) -> Result<(), FileError> {
+        let installed_path = installed_path.to_path_buf();
+        if !self.filesystem_backend.directory_exists(&installed_path) {
+            self.filesystem_backend.create_directory(&installed_path)?;
+        }
 
-        let datapacks_to_install = match &config.datapacks {
-            Some(datapacks) => datapacks.clone(),
+        let installed_path = self.filesystem_backend.canonicalize_path(&installed_path)?;
+
+        let files_to_install = match files {
+            Some(files) => files.clone(),
             None => HashMap::new(),
         };
 
-        for datapack_path in self
+        for file_path in self
             .filesystem_backend
-            .read_directory(&installed_datapacks_path)?
+            .read_directory(&installed_path)?
             .iter()
             .filter(|entry| match entry.file_stem() {
-                None => false,
+                // Mods and plugins keep their config in folders next to the jars, so only
+                // entries with the extension are removed from them
                 Some(stem) => {
-                    !datapacks_to_install.contains_key(&stem.to_string_lossy().to_string())
+                    (remove_all_unlisted
+                        || entry.extension() == Some(std::ffi::OsStr::new(extension)))
+                        && !files_to_install.contains_key(&stem.to_string_lossy().to_string())
                 }
+                None => false,
             })
         {
-            log::trace!("Uninstalling datapack \"{}\"", datapack_path.display());
-            self.filesystem_backend.delete_file(&datapack_path)?;
+            log::trace!("Uninstalling {} \"{}\"", kind, file_path.display());
+            if remove_all_unlisted && self.filesystem_backend.directory_exists(file_path) {
+                self.filesystem_backend.delete_directory(file_path)?;
+            } else {
+                self.filesystem_backend.delete_file(file_path)?;
+            }
         }
 
-        for (datapack_name, datapack_src) in datapacks_to_install.iter() {
-            let datapack_src_path = match self
+        for (file_name, file_src) in files_to_install.iter() {
+            let file_src_path = match self
                 .filesystem_backend
-                .canonicalize_path(&path::Path::new("datapacks").join(datapack_src))
+                .canonicalize_path(&source_path.join(file_src))
             {
                 Ok(path) => path,
-                Err(()) => {
+                Err(_) => {
                     log::warn!(
-                        "Unable to find the source for the datapack \"{}\", skipping",
-                        datapack_name
+                        "Unable to find the source for the {} \"{}\", skipping",
+                        kind,
+                        file_name
                     );
                     continue;
                 }
             };
 
-            let datapack_dest_path =
-                installed_datapacks_path.join(&format!("{}.zip", datapack_name));
+            let file_dest_path = installed_path.join(format!("{}.{}", file_name, extension));
             log::trace!(
                 "Installing \"{}\" from \"{}\" to \"{}\"",
-                datapack_name,
-                datapack_src_path.display(),
-                datapack_dest_path.display(),
+                file_name,
+                file_src_path.display(),
+                file_dest_path.display(),
             );
 
             self.filesystem_backend
-                .copy_file(&datapack_src_path, &datapack_dest_path)?;
+                .copy_file(&file_src_path, &file_dest_path)?;
         }
 
         Ok(())
     }
 }
 
+fn generate_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(RCON_PASSWORD_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
 pub fn new_from_defaults() -> FileProviderImpl<FilesystemBackendImpl> {
     FileProviderImpl::new(filesystem::new_from_defaults())
 }
@@ -185,10 +627,20 @@ mod tests {
 
     use super::*;
     use crate::config;
-    use crate::providers::backends::filesystem::MockFilesystemBackend;
+    use crate::providers::backends::filesystem::{FilesystemError, MockFilesystemBackend};
 
     fn get_file_provider() -> FileProviderImpl<MockFilesystemBackend> {
-        FileProviderImpl::new(MockFilesystemBackend::new())
+        FileProviderImpl {
+            generate_password: || "generated".to_owned(),
+            ..FileProviderImpl::new(MockFilesystemBackend::new())
+        }
+    }
+
+    fn get_filesystem_error() -> FilesystemError {
+        FilesystemError {
+            message: "error".to_owned(),
+            source: std::io::Error::from(std::io::ErrorKind::Other),
+        }
     }
 
     fn get_config() -> Config {
@@ -203,10 +655,14 @@ mod tests {
             },
             world: config::World {
                 name: "world".to_owned(),
-                gamemode: "survival".to_owned(),
+                gamemode: config::Gamemode::Survival,
                 allow_flight: false,
                 ..std::default::Default::default()
             },
+            backup: config::Backup {
+                directory: "backups".to_owned(),
+                ..std::default::Default::default()
+            },
             ..std::default::Default::default()
         }
     }
@@ -242,7 +698,7 @@ mod tests {
                 .times(1)
                 .returning(|_| Ok(()));
 
-            assert_eq!(Ok(()), file_provider.create_data_folder());
+            assert!(file_provider.create_data_folder().is_ok());
         }
 
         #[test]
@@ -261,190 +717,393 @@ mod tests {
                 .expect_create_directory()
                 .times(0);
 
-            assert_eq!(Ok(()), file_provider.create_data_folder());
+            assert!(file_provider.create_data_folder().is_ok());
         }
     }
 
-    mod test_create_and_populate_server_properties {
+    mod test_rcon_password {
         use super::*;
 
+        fn get_config_with_password() -> Config {
+            Config {
+                rcon: config::Rcon {
+                    password: Some("configured".to_owned()),
+                },
+                ..get_config()
+            }
+        }
+
         #[test]
-        fn file_not_exist() {
+        fn get_from_config() {
             let mut file_provider = get_file_provider();
-            let config = get_config();
-
             file_provider
                 .filesystem_backend
                 .expect_file_exists()
-                .with(eq(path::Path::new("data").join("server.properties")))
-                .times(1)
-                .returning(|_| false);
-
-            file_provider
-                .filesystem_backend
-                .expect_write_file()
-                .with(
-                    eq(path::Path::new("data").join("server.properties")),
-                    mockall::predicate::function(|actual_props: &str| {
-                        compare_server_properties(
-                            concat!(
-                                "server-port=25565\n",
-                                "enable-rcon=true\n",
-                                "rcon.port=25575\n",
-                                "rcon.password=minecraft\n",
-                                "broadcast-rcon-to-ops=true\n",
-                                "level-name=world\n",
-                                "gamemode=survival\n",
-                                "allow-flight=false\n",
-                            ),
-                            actual_props,
-                        );
-                        true
-                    }),
-                )
-                .times(1)
-                .returning(|_, _| Ok(()));
+                .times(0);
 
             assert_eq!(
-                Ok(()),
-                file_provider.create_and_populate_server_properties(&config)
+                "configured".to_owned(),
+                file_provider
+                    .get_rcon_password(&get_config_with_password())
+                    .unwrap()
             );
         }
 
         #[test]
-        fn empty_file_exists() {
+        fn get_from_file() {
             let mut file_provider = get_file_provider();
-            let config = get_config();
 
             file_provider
                 .filesystem_backend
                 .expect_file_exists()
-                .with(eq(path::Path::new("data").join("server.properties")))
+                .with(eq(path::Path::new(".rcon-password").to_path_buf()))
                 .times(1)
                 .returning(|_| true);
 
             file_provider
                 .filesystem_backend
                 .expect_read_file()
+                .with(eq(path::Path::new(".rcon-password").to_path_buf()))
                 .times(1)
-                .returning(|_| Ok(String::from("")));
+                .returning(|_| Ok("stored\n".to_owned()));
+
+            assert_eq!(
+                "stored".to_owned(),
+                file_provider.get_rcon_password(&get_config()).unwrap()
+            );
+        }
+
+        #[test]
+        fn get_missing() {
+            let mut file_provider = get_file_provider();
 
             file_provider
                 .filesystem_backend
-                .expect_write_file()
-                .with(
-                    eq(path::Path::new("data").join("server.properties")),
-                    mockall::predicate::function(|actual_props: &str| {
-                        compare_server_properties(
-                            concat!(
-                                "server-port=25565\n",
-                                "enable-rcon=true\n",
-                                "rcon.port=25575\n",
-                                "rcon.password=minecraft\n",
-                                "broadcast-rcon-to-ops=true\n",
-                                "level-name=world\n",
-                                "gamemode=survival\n",
-                                "allow-flight=false\n",
-                            ),
-                            actual_props,
-                        );
-                        true
-                    }),
-                )
+                .expect_file_exists()
                 .times(1)
-                .returning(|_, _| Ok(()));
+                .returning(|_| false);
+
+            assert!(matches!(
+                file_provider.get_rcon_password(&get_config()),
+                Err(FileError::MissingRconPassword(_))
+            ));
+        }
+
+        #[test]
+        fn create_from_config() {
+            let mut file_provider = get_file_provider();
+            file_provider
+                .filesystem_backend
+                .expect_write_private_file()
+                .times(0);
 
             assert_eq!(
-                Ok(()),
-                file_provider.create_and_populate_server_properties(&config)
+                "configured".to_owned(),
+                file_provider
+                    .create_rcon_password(&get_config_with_password())
+                    .unwrap()
             );
         }
 
         #[test]
-        fn non_empty_file_exists() {
+        fn create_existing() {
             let mut file_provider = get_file_provider();
-            let config = get_config();
 
             file_provider
                 .filesystem_backend
                 .expect_file_exists()
-                .with(eq(path::Path::new("data").join("server.properties")))
-                .times(1)
                 .returning(|_| true);
 
             file_provider
                 .filesystem_backend
                 .expect_read_file()
                 .times(1)
-                .returning(|_| {
-                    Ok(String::from(concat!(
-                        "rcon.port=25575\n",
-                        "server-port=25566\n",
-                        "rcon.password=minecraft\n",
-                    )))
-                });
+                .returning(|_| Ok("stored".to_owned()));
 
             file_provider
                 .filesystem_backend
-                .expect_write_file()
-                .with(
-                    eq(path::Path::new("data").join("server.properties")),
-                    mockall::predicate::function(|actual_props: &str| {
-                        compare_server_properties(
-                            concat!(
-                                "server-port=25565\n",
-                                "enable-rcon=true\n",
-                                "rcon.port=25575\n",
-                                "rcon.password=minecraft\n",
-                                "broadcast-rcon-to-ops=true\n",
-                                "level-name=world\n",
-                                "gamemode=survival\n",
-                                "allow-flight=false\n",
-                            ),
-                            actual_props,
-                        );
-                        true
-                    }),
-                )
-                .times(1)
-                .returning(|_, _| Ok(()));
+                .expect_write_private_file()
+                .times(0);
 
             assert_eq!(
-                Ok(()),
-                file_provider.create_and_populate_server_properties(&config)
+                "stored".to_owned(),
+                file_provider.create_rcon_password(&get_config()).unwrap()
             );
         }
 
         #[test]
-        fn test_removes_seed() {
+        fn create_new() {
             let mut file_provider = get_file_provider();
-            let config = get_config();
 
             file_provider
                 .filesystem_backend
                 .expect_file_exists()
-                .with(eq(path::Path::new("data").join("server.properties")))
                 .times(1)
-                .returning(|_| true);
+                .returning(|_| false);
 
             file_provider
                 .filesystem_backend
-                .expect_read_file()
+                .expect_write_private_file()
+                .withf(|path, contents| {
+                    path == path::Path::new(".rcon-password") && contents == "generated"
+                })
                 .times(1)
-                .returning(|_| Ok(String::from("level-seed=test-seed\n")));
+                .returning(|_, _| Ok(()));
 
-            file_provider
-                .filesystem_backend
-                .expect_write_file()
-                .with(
-                    eq(path::Path::new("data").join("server.properties")),
-                    mockall::predicate::function(|actual_props: &str| {
+            assert_eq!(
+                "generated".to_owned(),
+                file_provider.create_rcon_password(&get_config()).unwrap()
+            );
+        }
+
+        #[test]
+        fn create_write_fails() {
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_write_private_file()
+                .times(1)
+                .returning(|_, _| Err(get_filesystem_error()));
+
+            assert!(file_provider.create_rcon_password(&get_config()).is_err());
+        }
+
+        #[test]
+        fn generated_passwords_are_random() {
+            let password = generate_password();
+            assert_eq!(RCON_PASSWORD_LENGTH, password.len());
+            assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
+            assert_ne!(password, generate_password());
+        }
+    }
+
+    mod test_create_and_populate_server_properties {
+        use super::*;
+
+        #[test]
+        fn file_not_exist() {
+            let mut file_provider = get_file_provider();
+            let config = get_config();
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .with(eq(path::Path::new("data").join("server.properties")))
+                .times(1)
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .with(
+                    eq(path::Path::new("data").join("server.properties")),
+                    mockall::predicate::function(|actual_props: &str| {
+                        compare_server_properties(
+                            concat!(
+                                "server-port=25565\n",
+                                "enable-rcon=true\n",
+                                "rcon.port=25575\n",
+                                "rcon.password=password\n",
+                                "broadcast-rcon-to-ops=true\n",
+                                "level-name=world\n",
+                                "gamemode=survival\n",
+                                "allow-flight=false\n",
+                            ),
+                            actual_props,
+                        );
+                        true
+                    }),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider
+                .create_and_populate_server_properties(&config, "password")
+                .is_ok());
+        }
+
+        #[test]
+        fn query_enabled() {
+            let mut file_provider = get_file_provider();
+            let mut config = get_config();
+            config.query.enabled = true;
+            config.query.port = Some(25570);
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .with(
+                    eq(path::Path::new("data").join("server.properties")),
+                    mockall::predicate::function(|actual_props: &str| {
+                        compare_server_properties(
+                            concat!(
+                                "server-port=25565\n",
+                                "enable-rcon=true\n",
+                                "rcon.port=25575\n",
+                                "rcon.password=password\n",
+                                "broadcast-rcon-to-ops=true\n",
+                                "enable-query=true\n",
+                                "query.port=25565\n",
+                                "level-name=world\n",
+                                "gamemode=survival\n",
+                                "allow-flight=false\n",
+                            ),
+                            actual_props,
+                        );
+                        true
+                    }),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider
+                .create_and_populate_server_properties(&config, "password")
+                .is_ok());
+        }
+
+        #[test]
+        fn empty_file_exists() {
+            let mut file_provider = get_file_provider();
+            let config = get_config();
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .with(eq(path::Path::new("data").join("server.properties")))
+                .times(1)
+                .returning(|_| true);
+
+            file_provider
+                .filesystem_backend
+                .expect_read_file()
+                .times(1)
+                .returning(|_| Ok(String::from("")));
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .with(
+                    eq(path::Path::new("data").join("server.properties")),
+                    mockall::predicate::function(|actual_props: &str| {
+                        compare_server_properties(
+                            concat!(
+                                "server-port=25565\n",
+                                "enable-rcon=true\n",
+                                "rcon.port=25575\n",
+                                "rcon.password=password\n",
+                                "broadcast-rcon-to-ops=true\n",
+                                "level-name=world\n",
+                                "gamemode=survival\n",
+                                "allow-flight=false\n",
+                            ),
+                            actual_props,
+                        );
+                        true
+                    }),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider
+                .create_and_populate_server_properties(&config, "password")
+                .is_ok());
+        }
+
+        #[test]
+        fn non_empty_file_exists() {
+            let mut file_provider = get_file_provider();
+            let config = get_config();
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .with(eq(path::Path::new("data").join("server.properties")))
+                .times(1)
+                .returning(|_| true);
+
+            file_provider
+                .filesystem_backend
+                .expect_read_file()
+                .times(1)
+                .returning(|_| {
+                    Ok(String::from(concat!(
+                        "rcon.port=25575\n",
+                        "server-port=25566\n",
+                        "rcon.password=minecraft\n",
+                    )))
+                });
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .with(
+                    eq(path::Path::new("data").join("server.properties")),
+                    mockall::predicate::function(|actual_props: &str| {
+                        compare_server_properties(
+                            concat!(
+                                "server-port=25565\n",
+                                "enable-rcon=true\n",
+                                "rcon.port=25575\n",
+                                "rcon.password=password\n",
+                                "broadcast-rcon-to-ops=true\n",
+                                "level-name=world\n",
+                                "gamemode=survival\n",
+                                "allow-flight=false\n",
+                            ),
+                            actual_props,
+                        );
+                        true
+                    }),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider
+                .create_and_populate_server_properties(&config, "password")
+                .is_ok());
+        }
+
+        #[test]
+        fn test_removes_seed() {
+            let mut file_provider = get_file_provider();
+            let config = get_config();
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .with(eq(path::Path::new("data").join("server.properties")))
+                .times(1)
+                .returning(|_| true);
+
+            file_provider
+                .filesystem_backend
+                .expect_read_file()
+                .times(1)
+                .returning(|_| Ok(String::from("level-seed=test-seed\n")));
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .with(
+                    eq(path::Path::new("data").join("server.properties")),
+                    mockall::predicate::function(|actual_props: &str| {
                         compare_server_properties(
                             concat!(
                                 "server-port=25565\n",
                                 "enable-rcon=true\n",
                                 "rcon.port=25575\n",
-                                "rcon.password=minecraft\n",
+                                "rcon.password=password\n",
                                 "broadcast-rcon-to-ops=true\n",
                                 "level-name=world\n",
                                 "gamemode=survival\n",
@@ -456,191 +1115,1220 @@ mod tests {
                     }),
                 )
                 .times(1)
-                .returning(|_, _| Ok(()));
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider
+                .create_and_populate_server_properties(&config, "password")
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_writes_world_settings() {
+        let mut file_provider = get_file_provider();
+        let mut config = get_config();
+        config.world.gamemode = config::Gamemode::Creative;
+        config.world.difficulty = Some(config::Difficulty::Hard);
+        config.world.hardcore = Some(true);
+        config.world.pvp = Some(false);
+        config.world.max_players = Some(5);
+        config.world.motd = Some("Welcome".to_owned());
+
+        file_provider
+            .filesystem_backend
+            .expect_file_exists()
+            .with(eq(path::Path::new("data").join("server.properties")))
+            .times(1)
+            .returning(|_| true);
+
+        file_provider
+            .filesystem_backend
+            .expect_read_file()
+            .times(1)
+            .returning(|_| Ok(String::from("difficulty=easy\nhardcore=false\n")));
+
+        file_provider
+            .filesystem_backend
+            .expect_write_file()
+            .with(
+                eq(path::Path::new("data").join("server.properties")),
+                mockall::predicate::function(|actual_props: &str| {
+                    compare_server_properties(
+                        concat!(
+                            "server-port=25565\n",
+                            "enable-rcon=true\n",
+                            "rcon.port=25575\n",
+                            "rcon.password=password\n",
+                            "broadcast-rcon-to-ops=true\n",
+                            "level-name=world\n",
+                            "gamemode=creative\n",
+                            "difficulty=hard\n",
+                            "hardcore=true\n",
+                            "pvp=false\n",
+                            "max-players=5\n",
+                            "motd=Welcome\n",
+                            "allow-flight=false\n",
+                        ),
+                        actual_props,
+                    );
+                    true
+                }),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(file_provider
+            .create_and_populate_server_properties(&config, "password")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_keeps_unset_world_settings() {
+        let mut file_provider = get_file_provider();
+        let config = get_config();
+
+        file_provider
+            .filesystem_backend
+            .expect_file_exists()
+            .with(eq(path::Path::new("data").join("server.properties")))
+            .times(1)
+            .returning(|_| true);
+
+        file_provider
+            .filesystem_backend
+            .expect_read_file()
+            .times(1)
+            .returning(|_| Ok(String::from("difficulty=hard\nhardcore=true\n")));
+
+        file_provider
+            .filesystem_backend
+            .expect_write_file()
+            .with(
+                eq(path::Path::new("data").join("server.properties")),
+                mockall::predicate::function(|actual_props: &str| {
+                    compare_server_properties(
+                        concat!(
+                            "difficulty=hard\n",
+                            "hardcore=true\n",
+                            "server-port=25565\n",
+                            "enable-rcon=true\n",
+                            "rcon.port=25575\n",
+                            "rcon.password=password\n",
+                            "broadcast-rcon-to-ops=true\n",
+                            "level-name=world\n",
+                            "gamemode=survival\n",
+                            "allow-flight=false\n",
+                        ),
+                        actual_props,
+                    );
+                    true
+                }),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(file_provider
+            .create_and_populate_server_properties(&config, "password")
+            .is_ok());
+    }
+
+    mod test_properties {
+        use super::*;
+
+        #[test]
+        fn merges_properties() {
+            let mut file_provider = get_file_provider();
+            let mut config = get_config();
+            let mut properties = HashMap::new();
+            properties.insert(
+                "view-distance".to_owned(),
+                config::PropertyValue::Integer(12),
+            );
+            properties.insert("pvp".to_owned(), config::PropertyValue::Boolean(false));
+            properties.insert(
+                "motd".to_owned(),
+                config::PropertyValue::String("Hello world".to_owned()),
+            );
+            config.properties = Some(properties);
+
+            file_provider
+                .filesystem_backend
+                .expect_file_exists()
+                .with(eq(path::Path::new("data").join("server.properties")))
+                .times(1)
+                .returning(|_| true);
+
+            file_provider
+                .filesystem_backend
+                .expect_read_file()
+                .times(1)
+                .returning(|_| {
+                    Ok(String::from(concat!(
+                        "#Minecraft server properties\n",
+                        "view-distance=10\n",
+                        "max-players=20\n",
+                    )))
+                });
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .with(
+                    eq(path::Path::new("data").join("server.properties")),
+                    mockall::predicate::function(|actual_props: &str| {
+                        compare_server_properties(
+                            concat!(
+                                "#Minecraft server properties\n",
+                                "view-distance=12\n",
+                                "max-players=20\n",
+                                "pvp=false\n",
+                                "motd=Hello world\n",
+                                "server-port=25565\n",
+                                "enable-rcon=true\n",
+                                "rcon.port=25575\n",
+                                "rcon.password=password\n",
+                                "broadcast-rcon-to-ops=true\n",
+                                "level-name=world\n",
+                                "gamemode=survival\n",
+                                "allow-flight=false\n",
+                            ),
+                            actual_props,
+                        );
+                        true
+                    }),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider
+                .create_and_populate_server_properties(&config, "password")
+                .is_ok());
+        }
+
+        macro_rules! managed_property_tests {
+            ($($name:ident: $key:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let mut file_provider = get_file_provider();
+                    let mut config = get_config();
+                    let mut properties = HashMap::new();
+                    properties.insert(
+                        $key.to_owned(),
+                        config::PropertyValue::String("value".to_owned()),
+                    );
+                    config.properties = Some(properties);
+
+                    file_provider
+                        .filesystem_backend
+                        .expect_write_file()
+                        .times(0);
+
+                    assert!(matches!(
+                        file_provider.create_and_populate_server_properties(&config, "password"),
+                        Err(FileError::ManagedProperty(_))
+                    ));
+                }
+            )*
+            }
+        }
+
+        managed_property_tests! {
+            rejects_rcon_port: "rcon.port",
+            rejects_rcon_password: "rcon.password",
+            rejects_server_port: "server-port",
+            rejects_level_name: "level-name",
+            rejects_level_seed: "level-seed",
+            rejects_gamemode: "gamemode",
+        }
+
+        #[test]
+        fn rejects_world_setting() {
+            let mut file_provider = get_file_provider();
+            let mut config = get_config();
+            config.world.motd = Some("Welcome".to_owned());
+            let mut properties = HashMap::new();
+            properties.insert(
+                "motd".to_owned(),
+                config::PropertyValue::String("Hello world".to_owned()),
+            );
+            config.properties = Some(properties);
+
+            file_provider
+                .filesystem_backend
+                .expect_write_file()
+                .times(0);
+
+            assert!(matches!(
+                file_provider.create_and_populate_server_properties(&config, "password"),
+                Err(FileError::ManagedProperty(_))
+            ));
+        }
+    }
+
+    mod test_sync_datapacks {
+        use super::*;
+
+        fn get_datapacks_config() -> Config {
+            let mut datapacks = HashMap::new();
+            datapacks.insert("datapack1".to_owned(), "source_path_1".to_owned());
+            datapacks.insert("datapack2".to_owned(), "source_path_2".to_owned());
+
+            let mut config = get_config();
+            config.datapacks = Some(datapacks);
+            config
+        }
+
+        fn expect_canonicalize_paths(
+            filesystem_backend: &mut MockFilesystemBackend,
+            paths: Vec<path::PathBuf>,
+        ) {
+            for path in paths.iter() {
+                filesystem_backend
+                    .expect_canonicalize_path()
+                    .with(eq(path.clone()))
+                    .times(1)
+                    .returning(|p| Ok(p.clone()));
+            }
+        }
+
+        fn expect_copy_files(
+            filesystem_backend: &mut MockFilesystemBackend,
+            paths: Vec<(path::PathBuf, path::PathBuf)>,
+        ) {
+            for (src, dest) in paths.iter() {
+                filesystem_backend
+                    .expect_copy_file()
+                    .with(eq(src.clone()), eq(dest.clone()))
+                    .times(1)
+                    .returning(|_, _| Ok(()));
+            }
+        }
+
+        fn installs_datapacks(
+            world_datapacks_path: path::PathBuf,
+            config: Config,
+            mut file_provider: FileProviderImpl<MockFilesystemBackend>,
+        ) {
+            expect_canonicalize_paths(
+                &mut file_provider.filesystem_backend,
+                vec![
+                    path::Path::new("data").join("world").join("datapacks"),
+                    path::Path::new("datapacks").join("source_path_1"),
+                    path::Path::new("datapacks").join("source_path_2"),
+                ],
+            );
+
+            expect_copy_files(
+                &mut file_provider.filesystem_backend,
+                vec![
+                    (
+                        path::Path::new("datapacks").join("source_path_1"),
+                        world_datapacks_path.join("datapack1.zip"),
+                    ),
+                    (
+                        path::Path::new("datapacks").join("source_path_2"),
+                        world_datapacks_path.join("datapack2.zip"),
+                    ),
+                ],
+            );
+
+            assert!(file_provider.sync_datapacks(&config).is_ok());
+        }
+
+        #[test]
+        fn no_installed_datapacks_directory_exists() {
+            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
+            let config = get_datapacks_config();
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| true);
+
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| Ok(vec![]));
+
+            installs_datapacks(world_datapacks_path, config, file_provider);
+        }
+
+        #[test]
+        fn no_installed_datapacks_directory_does_not_exist() {
+            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
+            let config = get_datapacks_config();
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_directory()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| Ok(vec![]));
+
+            installs_datapacks(world_datapacks_path, config, file_provider);
+        }
+
+        #[test]
+        fn expected_datapack_installed() {
+            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
+            let config = get_datapacks_config();
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| true);
+
+            let world_datapacks_path_clone = world_datapacks_path.clone();
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(move |_| Ok(vec![world_datapacks_path_clone.join("datapack1.zip")]));
+
+            installs_datapacks(world_datapacks_path, config, file_provider);
+        }
+
+        #[test]
+        fn unexpected_datapack_installed() {
+            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
+            let config = get_datapacks_config();
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| true);
+
+            let world_datapacks_path_clone = world_datapacks_path.clone();
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(move |_| Ok(vec![world_datapacks_path_clone.join("datapack3.zip")]));
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.join("datapack3.zip")))
+                .times(1)
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .with(eq(world_datapacks_path.join("datapack3.zip")))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            installs_datapacks(world_datapacks_path, config, file_provider);
+        }
+
+        #[test]
+        fn unexpected_datapack_folder_installed() {
+            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
+            let config = get_datapacks_config();
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(|_| true);
+
+            let world_datapacks_path_clone = world_datapacks_path.clone();
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(world_datapacks_path.clone()))
+                .times(1)
+                .returning(move |_| {
+                    Ok(vec![
+                        world_datapacks_path_clone.join("datapack3"),
+                        world_datapacks_path_clone.join("notes.txt"),
+                    ])
+                });
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.join("datapack3")))
+                .times(1)
+                .returning(|_| true);
+
+            let world_datapacks_path_clone = world_datapacks_path.clone();
+            file_provider
+                .filesystem_backend
+                .expect_delete_directory()
+                .withf(move |path| path == world_datapacks_path_clone.join("datapack3"))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(world_datapacks_path.join("notes.txt")))
+                .times(1)
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .with(eq(world_datapacks_path.join("notes.txt")))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            installs_datapacks(world_datapacks_path, config, file_provider);
+        }
+    }
+
+    mod test_sync_mods {
+        use super::*;
+
+        fn get_mods_config() -> Config {
+            let mut mods = HashMap::new();
+            mods.insert("mod1".to_owned(), "mod1-1.0.0.jar".to_owned());
+
+            let mut config = get_config();
+            config.mods = Some(mods);
+            config
+        }
+
+        fn setup(installed_files: Vec<path::PathBuf>) -> FileProviderImpl<MockFilesystemBackend> {
+            let mods_path = path::Path::new("data").join("mods");
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(mods_path.clone()))
+                .times(1)
+                .returning(|_| true);
+
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(mods_path.clone()))
+                .times(1)
+                .returning(move |_| Ok(installed_files.clone()));
+
+            file_provider
+                .filesystem_backend
+                .expect_canonicalize_path()
+                .times(2)
+                .returning(|p| Ok(p.clone()));
+
+            file_provider
+                .filesystem_backend
+                .expect_copy_file()
+                .with(
+                    eq(path::Path::new("mods").join("mod1-1.0.0.jar")),
+                    eq(mods_path.join("mod1.jar")),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            file_provider
+        }
+
+        #[test]
+        fn installs_mods() {
+            let config = get_mods_config();
+            let file_provider = setup(vec![]);
+
+            assert!(file_provider.sync_mods(&config).is_ok());
+        }
+
+        #[test]
+        fn removes_unexpected_mods() {
+            let mods_path = path::Path::new("data").join("mods");
+            let config = get_mods_config();
+            let mut file_provider =
+                setup(vec![mods_path.join("mod1.jar"), mods_path.join("mod2.jar")]);
+
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .with(eq(mods_path.join("mod2.jar")))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            assert!(file_provider.sync_mods(&config).is_ok());
+        }
+
+        #[test]
+        fn ignores_non_jar_entries() {
+            let mods_path = path::Path::new("data").join("mods");
+            let config = get_mods_config();
+            let mut file_provider =
+                setup(vec![mods_path.join("config"), mods_path.join("notes.txt")]);
+
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .times(0);
+
+            assert!(file_provider.sync_mods(&config).is_ok());
+        }
+    }
+
+    mod test_sync_plugins {
+        use super::*;
+
+        #[test]
+        fn installs_plugins() {
+            let plugins_path = path::Path::new("data").join("plugins");
+            let mut plugins = HashMap::new();
+            plugins.insert("plugin1".to_owned(), "plugin1.jar".to_owned());
+            let mut config = get_config();
+            config.plugins = Some(plugins);
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .with(eq(plugins_path.clone()))
+                .times(1)
+                .returning(|_| false);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_directory()
+                .with(eq(plugins_path.clone()))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            let plugins_path_clone = plugins_path.clone();
+            file_provider
+                .filesystem_backend
+                .expect_read_directory()
+                .with(eq(plugins_path.clone()))
+                .times(1)
+                .returning(move |_| {
+                    Ok(vec![
+                        plugins_path_clone.join("plugin2.jar"),
+                        plugins_path_clone.join("plugin2"),
+                    ])
+                });
+
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .with(eq(plugins_path.join("plugin2.jar")))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            file_provider
+                .filesystem_backend
+                .expect_canonicalize_path()
+                .times(2)
+                .returning(|p| Ok(p.clone()));
+
+            file_provider
+                .filesystem_backend
+                .expect_copy_file()
+                .with(
+                    eq(path::Path::new("plugins").join("plugin1.jar")),
+                    eq(plugins_path.join("plugin1.jar")),
+                )
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(file_provider.sync_plugins(&config).is_ok());
+        }
+    }
+
+    mod test_create_backup {
+        use super::*;
+
+        fn is_backup_archive(archive_path: &path::Path) -> bool {
+            let file_name = archive_path.file_name().unwrap().to_string_lossy();
+            archive_path.parent() == Some(path::Path::new("backups"))
+                && file_name.starts_with("world-")
+                && file_name.ends_with(".tar.gz")
+        }
+
+        fn setup(
+            existing_directories: Vec<path::PathBuf>,
+        ) -> FileProviderImpl<MockFilesystemBackend> {
+            let mut file_provider = get_file_provider();
+
+            file_provider
+                .filesystem_backend
+                .expect_directory_exists()
+                .returning(move |p| existing_directories.contains(p));
+
+            file_provider
+        }
+
+        #[test]
+        fn archives_world() {
+            let config = get_config();
+            let mut file_provider = setup(vec![
+                path::PathBuf::from("backups"),
+                path::Path::new("data").join("world"),
+            ]);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_archive()
+                .withf(|archive_path, base_path, entries| {
+                    is_backup_archive(archive_path)
+                        && base_path == path::Path::new("data")
+                        && entries == [path::PathBuf::from("world")]
+                })
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+
+            assert!(is_backup_archive(
+                &file_provider.create_backup(&config).unwrap()
+            ));
+        }
+
+        #[test]
+        fn archives_separate_dimensions() {
+            let config = get_config();
+            let mut file_provider = setup(vec![
+                path::PathBuf::from("backups"),
+                path::Path::new("data").join("world"),
+                path::Path::new("data").join("world_nether"),
+                path::Path::new("data").join("world_the_end"),
+            ]);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_archive()
+                .withf(|_, _, entries| {
+                    entries
+                        == [
+                            path::PathBuf::from("world"),
+                            path::PathBuf::from("world_nether"),
+                            path::PathBuf::from("world_the_end"),
+                        ]
+                })
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+
+            assert!(file_provider.create_backup(&config).is_ok());
+        }
+
+        #[test]
+        fn creates_backup_directory() {
+            let config = get_config();
+            let mut file_provider = setup(vec![path::Path::new("data").join("world")]);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_directory()
+                .with(eq(path::PathBuf::from("backups")))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            file_provider
+                .filesystem_backend
+                .expect_create_archive()
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+
+            assert!(file_provider.create_backup(&config).is_ok());
+        }
+
+        #[test]
+        fn world_does_not_exist() {
+            let config = get_config();
+            let mut file_provider = setup(vec![path::PathBuf::from("backups")]);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_archive()
+                .times(0);
+
+            assert!(matches!(
+                file_provider.create_backup(&config),
+                Err(FileError::WorldNotFound(_))
+            ));
+        }
+
+        #[test]
+        fn archive_fails() {
+            let config = get_config();
+            let mut file_provider = setup(vec![
+                path::PathBuf::from("backups"),
+                path::Path::new("data").join("world"),
+            ]);
+
+            file_provider
+                .filesystem_backend
+                .expect_create_archive()
+                .times(1)
+                .returning(|_, _, _| Err(get_filesystem_error()));
 
-            assert_eq!(
-                Ok(()),
-                file_provider.create_and_populate_server_properties(&config)
-            );
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .times(0);
+
+            assert!(matches!(
+                file_provider.create_backup(&config),
+                Err(FileError::Filesystem(_))
+            ));
         }
-    }
 
-    mod test_sync_datapacks {
-        use super::*;
+        #[test]
+        fn backup_already_exists() {
+            let config = get_config();
+            let mut file_provider = setup(vec![
+                path::PathBuf::from("backups"),
+                path::Path::new("data").join("world"),
+            ]);
 
-        fn get_datapacks_config() -> Config {
-            let mut datapacks = HashMap::new();
-            datapacks.insert("datapack1".to_owned(), "source_path_1".to_owned());
-            datapacks.insert("datapack2".to_owned(), "source_path_2".to_owned());
+            file_provider
+                .filesystem_backend
+                .expect_create_archive()
+                .times(1)
+                .returning(|_, _, _| {
+                    Err(FilesystemError {
+                        message: "error".to_owned(),
+                        source: std::io::Error::from(std::io::ErrorKind::AlreadyExists),
+                    })
+                });
 
-            let mut config = get_config();
-            config.datapacks = Some(datapacks);
-            config
-        }
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .times(0);
 
-        fn expect_canonicalize_paths(
-            filesystem_backend: &mut MockFilesystemBackend,
-            paths: Vec<path::PathBuf>,
-        ) {
-            for path in paths.iter() {
-                filesystem_backend
-                    .expect_canonicalize_path()
-                    .with(eq(path.clone()))
-                    .times(1)
-                    .returning(|p| Ok(p.clone()));
-            }
+            assert!(matches!(
+                file_provider.create_backup(&config),
+                Err(FileError::BackupExists(path)) if is_backup_archive(&path)
+            ));
         }
+    }
 
-        fn expect_copy_files(
-            filesystem_backend: &mut MockFilesystemBackend,
-            paths: Vec<(path::PathBuf, path::PathBuf)>,
-        ) {
-            for (src, dest) in paths.iter() {
-                filesystem_backend
-                    .expect_copy_file()
-                    .with(eq(src.clone()), eq(dest.clone()))
-                    .times(1)
-                    .returning(|_, _| Ok(()));
-            }
+    fn get_backup(id: &str) -> BackupArchive {
+        BackupArchive {
+            id: id.to_owned(),
+            path: path::Path::new("backups").join(format!("world-{}.tar.gz", id)),
+            created: NaiveDateTime::parse_from_str(id, BACKUP_TIMESTAMP_FORMAT).unwrap(),
         }
+    }
 
-        fn installs_datapacks(
-            world_datapacks_path: path::PathBuf,
-            config: Config,
-            mut file_provider: FileProviderImpl<MockFilesystemBackend>,
-        ) {
-            expect_canonicalize_paths(
+    fn expect_backups(filesystem_backend: &mut MockFilesystemBackend, ids: Vec<&str>) {
+        let entries = ids
+            .iter()
+            .map(|id| path::Path::new("backups").join(format!("world-{}.tar.gz", id)))
+            .collect::<Vec<path::PathBuf>>();
+
+        filesystem_backend
+            .expect_directory_exists()
+            .with(eq(path::PathBuf::from("backups")))
+            .returning(|_| true);
+
+        filesystem_backend
+            .expect_read_directory()
+            .with(eq(path::PathBuf::from("backups")))
+            .times(1)
+            .returning(move |_| Ok(entries.clone()));
+    }
+
+    mod test_list_backups {
+        use super::*;
+
+        #[test]
+        fn lists_newest_first() {
+            let config = get_config();
+            let mut file_provider = get_file_provider();
+            expect_backups(
                 &mut file_provider.filesystem_backend,
-                vec![
-                    path::Path::new("data").join("world").join("datapacks"),
-                    path::Path::new("datapacks").join("source_path_1"),
-                    path::Path::new("datapacks").join("source_path_2"),
-                ],
+                vec!["20211014-120000", "20211016-120000", "20211015-120000"],
             );
 
-            expect_copy_files(
-                &mut file_provider.filesystem_backend,
+            assert_eq!(
                 vec![
-                    (
-                        path::Path::new("datapacks").join("source_path_1"),
-                        world_datapacks_path.join("datapack1.zip"),
-                    ),
-                    (
-                        path::Path::new("datapacks").join("source_path_2"),
-                        world_datapacks_path.join("datapack2.zip"),
-                    ),
+                    get_backup("20211016-120000"),
+                    get_backup("20211015-120000"),
+                    get_backup("20211014-120000"),
                 ],
+                file_provider.list_backups(&config).unwrap()
             );
-
-            assert_eq!(Ok(()), file_provider.sync_datapacks(&config));
         }
 
         #[test]
-        fn no_installed_datapacks_directory_exists() {
-            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
-            let config = get_datapacks_config();
+        fn ignores_other_files() {
+            let config = get_config();
             let mut file_provider = get_file_provider();
 
             file_provider
                 .filesystem_backend
                 .expect_directory_exists()
-                .with(eq(world_datapacks_path.clone()))
-                .times(1)
                 .returning(|_| true);
 
             file_provider
                 .filesystem_backend
                 .expect_read_directory()
-                .with(eq(world_datapacks_path.clone()))
                 .times(1)
-                .returning(|_| Ok(vec![]));
+                .returning(|_| {
+                    Ok(vec![
+                        path::Path::new("backups").join("world-20211016-120000.tar.gz"),
+                        path::Path::new("backups").join("other-20211016-120000.tar.gz"),
+                        path::Path::new("backups").join("world-latest.tar.gz"),
+                        path::Path::new("backups").join("world-20211016-120000.zip"),
+                        path::Path::new("backups").join("notes.txt"),
+                    ])
+                });
 
-            installs_datapacks(world_datapacks_path, config, file_provider);
+            assert_eq!(
+                vec![get_backup("20211016-120000")],
+                file_provider.list_backups(&config).unwrap()
+            );
         }
 
         #[test]
-        fn no_installed_datapacks_directory_does_not_exist() {
-            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
-            let config = get_datapacks_config();
+        fn no_backup_directory() {
+            let config = get_config();
             let mut file_provider = get_file_provider();
 
             file_provider
                 .filesystem_backend
                 .expect_directory_exists()
-                .with(eq(world_datapacks_path.clone()))
-                .times(1)
                 .returning(|_| false);
 
+            assert!(file_provider.list_backups(&config).unwrap().is_empty());
+        }
+    }
+
+    mod test_select_backups_to_keep {
+        use super::*;
+
+        fn get_backups() -> Vec<BackupArchive> {
+            vec![
+                get_backup("20211016-180000"),
+                get_backup("20211016-120000"),
+                get_backup("20211015-120000"),
+                get_backup("20211011-120000"),
+                get_backup("20211010-120000"),
+                get_backup("20210930-120000"),
+                get_backup("20210815-120000"),
+            ]
+        }
+
+        macro_rules! select_backups_to_keep_tests {
+            ($($name:ident: $retention:expr, $expected:expr;)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let expected = $expected
+                        .iter()
+                        .map(|id: &&str| id.to_string())
+                        .collect::<HashSet<String>>();
+                    assert_eq!(expected, select_backups_to_keep(&get_backups(), &$retention));
+                }
+            )*
+            }
+        }
+
+        select_backups_to_keep_tests! {
+            keep_nothing: config::Retention::default(), [] as [&str; 0];
+            keep_last: config::Retention {
+                keep_last: Some(2),
+                ..std::default::Default::default()
+            }, ["20211016-180000", "20211016-120000"];
+            keep_daily: config::Retention {
+                keep_daily: Some(3),
+                ..std::default::Default::default()
+            }, ["20211016-180000", "20211015-120000", "20211011-120000"];
+            keep_weekly: config::Retention {
+                keep_weekly: Some(2),
+                ..std::default::Default::default()
+            }, ["20211016-180000", "20211010-120000"];
+            keep_monthly: config::Retention {
+                keep_monthly: Some(3),
+                ..std::default::Default::default()
+            }, ["20211016-180000", "20210930-120000", "20210815-120000"];
+            keep_combined: config::Retention {
+                keep_last: Some(1),
+                keep_daily: Some(2),
+                keep_monthly: Some(2),
+                ..std::default::Default::default()
+            }, ["20211016-180000", "20211015-120000", "20210930-120000"];
+            keep_more_than_available: config::Retention {
+                keep_last: Some(10),
+                ..std::default::Default::default()
+            }, [
+                "20211016-180000",
+                "20211016-120000",
+                "20211015-120000",
+                "20211011-120000",
+                "20211010-120000",
+                "20210930-120000",
+                "20210815-120000",
+            ];
+        }
+    }
+
+    mod test_prune_backups {
+        use super::*;
+
+        #[test]
+        fn deletes_unkept_backups() {
+            let mut config = get_config();
+            config.backup.retention.keep_last = Some(1);
+            let mut file_provider = get_file_provider();
+            expect_backups(
+                &mut file_provider.filesystem_backend,
+                vec!["20211016-120000", "20211015-120000"],
+            );
+
             file_provider
                 .filesystem_backend
-                .expect_create_directory()
-                .with(eq(world_datapacks_path.clone()))
+                .expect_delete_file()
+                .with(eq(get_backup("20211015-120000").path))
                 .times(1)
                 .returning(|_| Ok(()));
 
+            assert_eq!(
+                vec![get_backup("20211015-120000")],
+                file_provider.prune_backups(&config).unwrap()
+            );
+        }
+
+        #[test]
+        fn no_retention_policy() {
+            let config = get_config();
+            let mut file_provider = get_file_provider();
+
             file_provider
                 .filesystem_backend
                 .expect_read_directory()
-                .with(eq(world_datapacks_path.clone()))
-                .times(1)
-                .returning(|_| Ok(vec![]));
+                .times(0);
 
-            installs_datapacks(world_datapacks_path, config, file_provider);
+            file_provider
+                .filesystem_backend
+                .expect_delete_file()
+                .times(0);
+
+            assert!(file_provider.prune_backups(&config).unwrap().is_empty());
         }
+    }
 
-        #[test]
-        fn expected_datapack_installed() {
-            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
-            let config = get_datapacks_config();
+    mod test_restore_backup {
+        use super::*;
+
+        fn setup(existing_entries: Vec<&'static str>) -> FileProviderImpl<MockFilesystemBackend> {
             let mut file_provider = get_file_provider();
+            expect_backups(
+                &mut file_provider.filesystem_backend,
+                vec!["20211016-120000", "20211015-120000"],
+            );
 
+            let existing_paths = existing_entries
+                .iter()
+                .map(|entry| path::Path::new("data").join(entry))
+                .collect::<Vec<path::PathBuf>>();
             file_provider
                 .filesystem_backend
                 .expect_directory_exists()
-                .with(eq(world_datapacks_path.clone()))
+                .returning(move |p| existing_paths.contains(p));
+
+            file_provider
+        }
+
+        fn is_moved_aside(src: &path::Path, dest: &path::Path, entry: &str) -> bool {
+            src == path::Path::new("data").join(entry)
+                && dest.parent() == Some(path::Path::new("data"))
+                && dest
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with(&format!("{}-before-restore-", entry))
+        }
+
+        #[test]
+        fn moves_world_aside_and_extracts() {
+            let config = get_config();
+            let mut file_provider = setup(vec!["world", "world_nether"]);
+            let mut sequence = mockall::Sequence::new();
+
+            file_provider
+                .filesystem_backend
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(src, dest, "world"))
                 .times(1)
-                .returning(|_| true);
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
 
-            let world_datapacks_path_clone = world_datapacks_path.clone();
             file_provider
                 .filesystem_backend
-                .expect_read_directory()
-                .with(eq(world_datapacks_path.clone()))
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(src, dest, "world_nether"))
                 .times(1)
-                .returning(move |_| Ok(vec![world_datapacks_path_clone.join("datapack1.zip")]));
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
 
-            installs_datapacks(world_datapacks_path, config, file_provider);
+            file_provider
+                .filesystem_backend
+                .expect_extract_archive()
+                .withf(|archive_path, dest_path| {
+                    archive_path == get_backup("20211015-120000").path
+                        && dest_path == path::Path::new("data")
+                })
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
+
+            assert_eq!(
+                get_backup("20211015-120000"),
+                file_provider
+                    .restore_backup(&config, "20211015-120000")
+                    .unwrap()
+            );
         }
 
         #[test]
-        fn unexpected_datapack_installed() {
-            let world_datapacks_path = path::Path::new("data").join("world").join("datapacks");
-            let config = get_datapacks_config();
-            let mut file_provider = get_file_provider();
+        fn restores_latest() {
+            let config = get_config();
+            let mut file_provider = setup(vec![]);
 
             file_provider
                 .filesystem_backend
-                .expect_directory_exists()
-                .with(eq(world_datapacks_path.clone()))
+                .expect_extract_archive()
+                .withf(|archive_path, dest_path| {
+                    archive_path == get_backup("20211016-120000").path
+                        && dest_path == path::Path::new("data")
+                })
                 .times(1)
-                .returning(|_| true);
+                .returning(|_, _| Ok(()));
+
+            assert_eq!(
+                get_backup("20211016-120000"),
+                file_provider.restore_backup(&config, "latest").unwrap()
+            );
+        }
+
+        #[test]
+        fn move_aside_fails() {
+            let config = get_config();
+            let mut file_provider = setup(vec!["world", "world_nether"]);
+            let mut sequence = mockall::Sequence::new();
 
-            let world_datapacks_path_clone = world_datapacks_path.clone();
             file_provider
                 .filesystem_backend
-                .expect_read_directory()
-                .with(eq(world_datapacks_path.clone()))
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(src, dest, "world"))
                 .times(1)
-                .returning(move |_| Ok(vec![world_datapacks_path_clone.join("datapack3.zip")]));
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
 
             file_provider
                 .filesystem_backend
-                .expect_delete_file()
-                .with(eq(world_datapacks_path.join("datapack3.zip")))
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(src, dest, "world_nether"))
                 .times(1)
-                .returning(|_| Ok(()));
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Err(get_filesystem_error()));
 
-            installs_datapacks(world_datapacks_path, config, file_provider);
+            file_provider
+                .filesystem_backend
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(dest, src, "world"))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
+
+            file_provider
+                .filesystem_backend
+                .expect_extract_archive()
+                .times(0);
+
+            assert!(matches!(
+                file_provider.restore_backup(&config, "20211015-120000"),
+                Err(FileError::Filesystem(_))
+            ));
+        }
+
+        #[test]
+        fn move_back_fails() {
+            let config = get_config();
+            let mut file_provider = setup(vec!["world", "world_nether"]);
+            let mut sequence = mockall::Sequence::new();
+
+            file_provider
+                .filesystem_backend
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(src, dest, "world"))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
+
+            file_provider
+                .filesystem_backend
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(src, dest, "world_nether"))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Err(get_filesystem_error()));
+
+            file_provider
+                .filesystem_backend
+                .expect_rename_path()
+                .withf(|src, dest| is_moved_aside(dest, src, "world"))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Err(get_filesystem_error()));
+
+            assert!(matches!(
+                file_provider.restore_backup(&config, "20211015-120000"),
+                Err(FileError::MoveAsideFailed { suffix, .. }) if suffix.starts_with("before-restore-")
+            ));
+        }
+
+        #[test]
+        fn backup_not_found() {
+            let config = get_config();
+            let mut file_provider = setup(vec!["world"]);
+
+            file_provider
+                .filesystem_backend
+                .expect_rename_path()
+                .times(0);
+
+            file_provider
+                .filesystem_backend
+                .expect_extract_archive()
+                .times(0);
+
+            assert!(matches!(
+                file_provider.restore_backup(&config, "20200101-000000"),
+                Err(FileError::BackupNotFound(_))
+            ));
         }
     }
 }
@@ -1,8 +1,37 @@
 use crate::config::Config;
 use crate::providers::backends::input::{
-    InputBackend, InputBackendFactory, InputBackendFactoryImpl, InputResponse,
+    InputBackend, InputBackendFactory, InputBackendFactoryImpl, InputError, InputResponse,
 };
-use crate::providers::backends::rcon::{RconBackend, RconBackendFactory, RconBackendFactoryImpl};
+use crate::providers::backends::ping::{
+    self, PingBackend, PingBackendImpl, PingError, ServerStatus,
+};
+use crate::providers::backends::query::{
+    self, QueryBackend, QueryBackendImpl, QueryError, QueryStatus,
+};
+use crate::providers::backends::rcon::{
+    RconBackend, RconBackendFactory, RconBackendFactoryImpl, RconError,
+};
+use std::time::Duration;
+
+const COUNTDOWN_WARNINGS: [u64; 11] = [600, 300, 120, 60, 30, 10, 5, 4, 3, 2, 1];
+
+#[derive(Debug, thiserror::Error)]
+pub enum GameError {
+    #[error(transparent)]
+    Rcon(#[from] RconError),
+    #[error(transparent)]
+    Input(#[from] InputError),
+    #[error(transparent)]
+    Ping(#[from] PingError),
+    #[error(transparent)]
+    Query(#[from] QueryError),
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct PlayerCount {
+    pub online: u32,
+    pub max: u32,
+}
 
 #[cfg_attr(test, mockall::automock)]
 pub trait GameProvider {
@@ -11,34 +40,75 @@ pub trait GameProvider {
         config: &Config,
         host: &str,
         port: &str,
-    ) -> Result<(), ()>;
+        password: &str,
+    ) -> Result<(), GameError>;
     fn run_rcon_commands(
         &self,
         host: &str,
         port: &str,
+        password: &str,
         commands: Vec<String>,
-    ) -> Result<Vec<String>, ()>;
+    ) -> Result<Vec<String>, GameError>;
+    fn exec_rcon_commands(
+        &self,
+        host: &str,
+        port: &str,
+        password: &str,
+        commands: &[String],
+    ) -> Result<Vec<Result<String, GameError>>, GameError>;
+    fn broadcast_stop_countdown(
+        &self,
+        host: &str,
+        port: &str,
+        password: &str,
+        seconds: u64,
+    ) -> Result<(), GameError>;
+    fn get_player_count(
+        &self,
+        host: &str,
+        port: &str,
+        password: &str,
+    ) -> Result<Option<PlayerCount>, GameError>;
+    fn ping_server(&self, host: &str, port: u16) -> Result<ServerStatus, GameError>;
+    fn query_server(&self, host: &str, port: u16) -> Result<QueryStatus, GameError>;
+    fn read_piped_input(&self) -> Result<Option<String>, GameError>;
 }
 
 pub struct GameProviderImpl<
     RconBackendFactoryType: RconBackendFactory,
     InputBackendFactoryType: InputBackendFactory,
+    PingBackendType: PingBackend,
+    QueryBackendType: QueryBackend,
 > {
     rcon_backend_factory: RconBackendFactoryType,
     input_backend_factory: InputBackendFactoryType,
+    ping_backend: PingBackendType,
+    query_backend: QueryBackendType,
+    sleep: fn(Duration),
 }
 
-impl<RconBackendFactoryType: RconBackendFactory, InputBackendFactoryType: InputBackendFactory>
-    GameProvider for GameProviderImpl<RconBackendFactoryType, InputBackendFactoryType>
+impl<
+        RconBackendFactoryType: RconBackendFactory,
+        InputBackendFactoryType: InputBackendFactory,
+        PingBackendType: PingBackend,
+        QueryBackendType: QueryBackend,
+    > GameProvider
+    for GameProviderImpl<
+        RconBackendFactoryType,
+        InputBackendFactoryType,
+        PingBackendType,
+        QueryBackendType,
+    >
 {
     fn run_interactive_rcon_session(
         &self,
         config: &Config,
         host: &str,
         port: &str,
-    ) -> Result<(), ()> {
+        password: &str,
+    ) -> Result<(), GameError> {
         log::trace!("Establishing rcon connection to {}:{}", host, port);
-        let mut rcon_backend = self.rcon_backend_factory.create(host, port)?;
+        let mut rcon_backend = self.rcon_backend_factory.create(host, port, password)?;
         let mut input_backend = self.input_backend_factory.create();
         loop {
             match input_backend.get_line(&format!("[{}] > ", config.name)) {
@@ -51,7 +121,7 @@ impl<RconBackendFactoryType: RconBackendFactory, InputBackendFactoryType: InputB
                     Ok(())
                 }
                 Ok(InputResponse::EndOfInput) => break,
-                Err(()) => Err(()),
+                Err(err) => Err(err),
             }?
         }
 
@@ -62,22 +132,159 @@ impl<RconBackendFactoryType: RconBackendFactory, InputBackendFactoryType: InputB
         &self,
         host: &str,
         port: &str,
+        password: &str,
         commands: Vec<String>,
-    ) -> Result<Vec<String>, ()> {
+    ) -> Result<Vec<String>, GameError> {
         let mut responses = vec![];
-        let mut rcon_backend = self.rcon_backend_factory.create(host, port)?;
+        let mut rcon_backend = self.rcon_backend_factory.create(host, port, password)?;
         for command in commands {
             responses.push(rcon_backend.cmd(&command)?);
         }
 
         Ok(responses)
     }
+
+    fn exec_rcon_commands(
+        &self,
+        host: &str,
+        port: &str,
+        password: &str,
+        commands: &[String],
+    ) -> Result<Vec<Result<String, GameError>>, GameError> {
+        let mut responses = vec![];
+        let mut rcon_backend = self.rcon_backend_factory.create(host, port, password)?;
+        for command in commands {
+            let response = rcon_backend.cmd(command).map_err(GameError::from);
+            let failed = response.is_err();
+            responses.push(response);
+            if failed {
+                break;
+            }
+        }
+
+        Ok(responses)
+    }
+
+    fn broadcast_stop_countdown(
+        &self,
+        host: &str,
+        port: &str,
+        password: &str,
+        seconds: u64,
+    ) -> Result<(), GameError> {
+        let countdown = get_countdown(seconds);
+        if countdown.is_empty() {
+            return Ok(());
+        }
+
+        let mut rcon_backend = self.rcon_backend_factory.create(host, port, password)?;
+        for (remaining, wait) in countdown {
+            log::info!("Stopping in {}", format_remaining_time(remaining));
+            rcon_backend.cmd(&format!(
+                "say The server will stop in {}",
+                format_remaining_time(remaining)
+            ))?;
+            (self.sleep)(Duration::from_secs(wait));
+        }
+
+        Ok(())
+    }
+
+    fn get_player_count(
+        &self,
+        host: &str,
+        port: &str,
+        password: &str,
+    ) -> Result<Option<PlayerCount>, GameError> {
+        let mut rcon_backend = self.rcon_backend_factory.create(host, port, password)?;
+        let response = rcon_backend.cmd("list")?;
+        log::trace!("Player list response: {}", response);
+        Ok(parse_player_count(&response))
+    }
+
+    fn ping_server(&self, host: &str, port: u16) -> Result<ServerStatus, GameError> {
+        Ok(self.ping_backend.ping(host, port)?)
+    }
+
+    fn query_server(&self, host: &str, port: u16) -> Result<QueryStatus, GameError> {
+        Ok(self.query_backend.query(host, port)?)
+    }
+
+    // Reading a terminal would wait for input that was never meant to be given, so only piped
+    // input is read
+    fn read_piped_input(&self) -> Result<Option<String>, GameError> {
+        let mut input_backend = self.input_backend_factory.create();
+        if input_backend.is_terminal() {
+            return Ok(None);
+        }
+
+        Ok(Some(input_backend.read_to_end()?))
+    }
 }
 
-pub fn new_from_defaults() -> GameProviderImpl<RconBackendFactoryImpl, InputBackendFactoryImpl> {
+/// Parses the response to the list command, which is "There are 1 of a max of
+/// 20 players online: ..." on current versions and "There are 1/20 players
+/// online: ..." on older ones. Formatting codes are ignored.
+fn parse_player_count(response: &str) -> Option<PlayerCount> {
+    let response = ping::strip_formatting_codes(response);
+    let counts = response
+        .trim_start()
+        .strip_prefix("There are ")?
+        .split(" players online")
+        .next()?;
+    let (online, max) = match counts.split_once(" of a max of ") {
+        Some(counts) => counts,
+        None => counts.split_once('/')?,
+    };
+
+    Some(PlayerCount {
+        online: online.trim().parse().ok()?,
+        max: max.trim().parse().ok()?,
+    })
+}
+
+/// Builds the warnings to broadcast as pairs of the seconds remaining and the
+/// seconds to wait before the next warning.
+fn get_countdown(seconds: u64) -> Vec<(u64, u64)> {
+    if seconds == 0 {
+        return vec![];
+    }
+
+    let mut warnings = vec![seconds];
+    warnings.extend(
+        COUNTDOWN_WARNINGS
+            .iter()
+            .filter(|warning| **warning < seconds),
+    );
+
+    warnings
+        .iter()
+        .enumerate()
+        .map(|(i, remaining)| (*remaining, remaining - warnings.get(i + 1).unwrap_or(&0)))
+        .collect()
+}
+
+fn format_remaining_time(seconds: u64) -> String {
+    match seconds {
+        60 => "1 minute".to_owned(),
+        s if s > 60 && s % 60 == 0 => format!("{} minutes", s / 60),
+        1 => "1 second".to_owned(),
+        s => format!("{} seconds", s),
+    }
+}
+
+pub fn new_from_defaults() -> GameProviderImpl<
+    RconBackendFactoryImpl,
+    InputBackendFactoryImpl,
+    PingBackendImpl,
+    QueryBackendImpl,
+> {
     GameProviderImpl {
         rcon_backend_factory: RconBackendFactoryImpl {},
         input_backend_factory: InputBackendFactoryImpl {},
+        ping_backend: ping::new_from_defaults(),
+        query_backend: query::new_from_defaults(),
+        sleep: std::thread::sleep,
     }
 }
 
@@ -86,9 +293,24 @@ mod tests {
     use super::*;
     use crate::config;
     use crate::providers::backends::input::{MockInputBackend, MockInputBackendFactory};
+    use crate::providers::backends::ping::MockPingBackend;
+    use crate::providers::backends::query::MockQueryBackend;
     use crate::providers::backends::rcon::{MockRconBackend, MockRconBackendFactory};
     use mockall::{predicate::eq, Sequence};
 
+    fn get_rcon_error() -> RconError {
+        RconError {
+            message: "error".to_owned(),
+            source: rcon::Error::Auth,
+        }
+    }
+
+    fn get_input_error() -> InputError {
+        InputError(rustyline::error::ReadlineError::Io(std::io::Error::from(
+            std::io::ErrorKind::Other,
+        )))
+    }
+
     fn get_config() -> Config {
         Config {
             name: "name".to_owned(),
@@ -107,15 +329,18 @@ mod tests {
         use super::*;
 
         fn setup(
-            input_responses: Vec<Result<InputResponse, ()>>,
+            input_responses: Vec<Result<InputResponse, InputError>>,
             rcon_inputs: Vec<String>,
-            rcon_responses: Vec<Result<String, ()>>,
-        ) -> GameProviderImpl<MockRconBackendFactory, MockInputBackendFactory> {
+            rcon_responses: Vec<Result<String, RconError>>,
+        ) -> GameProviderImpl<
+            MockRconBackendFactory,
+            MockInputBackendFactory,
+            MockPingBackend,
+            MockQueryBackend,
+        > {
             let mut rcon_sequence = Sequence::new();
             let mut mock_rcon_backend = MockRconBackend::new();
-            for rcon_idx in 0..rcon_inputs.len() {
-                let expected_input = rcon_inputs[rcon_idx].to_owned();
-                let response = rcon_responses[rcon_idx].clone();
+            for (expected_input, response) in rcon_inputs.into_iter().zip(rcon_responses) {
                 mock_rcon_backend
                     .expect_cmd()
                     .times(1)
@@ -127,9 +352,9 @@ mod tests {
             let mut mock_rcon_factory = MockRconBackendFactory::new();
             mock_rcon_factory
                 .expect_create()
-                .with(eq("host"), eq("port"))
+                .with(eq("host"), eq("port"), eq("password"))
                 .times(1)
-                .return_once(move |_, _| Ok(mock_rcon_backend));
+                .return_once(move |_, _, _| Ok(mock_rcon_backend));
 
             let mut input_sequence = Sequence::new();
             let mut mock_input_backend = MockInputBackend::new();
@@ -138,7 +363,7 @@ mod tests {
                     .expect_get_line()
                     .times(1)
                     .with(eq("[name] > "))
-                    .return_once(move |_| input_response.clone())
+                    .return_once(move |_| input_response)
                     .in_sequence(&mut input_sequence);
             }
 
@@ -152,6 +377,9 @@ mod tests {
             GameProviderImpl {
                 rcon_backend_factory: mock_rcon_factory,
                 input_backend_factory: mock_input_factory,
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
             }
         }
 
@@ -162,30 +390,31 @@ mod tests {
             let mut mock_rcon_factory = MockRconBackendFactory::new();
             mock_rcon_factory
                 .expect_create()
-                .with(eq("host"), eq("port"))
+                .with(eq("host"), eq("port"), eq("password"))
                 .times(1)
-                .return_once(move |_, _| Err(()));
+                .return_once(move |_, _, _| Err(get_rcon_error()));
 
             let game_provider = GameProviderImpl {
                 rcon_backend_factory: mock_rcon_factory,
                 input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
             };
 
-            assert_eq!(
-                Err(()),
-                game_provider.run_interactive_rcon_session(&config, "host", "port")
-            );
+            assert!(game_provider
+                .run_interactive_rcon_session(&config, "host", "port", "password")
+                .is_err());
         }
 
         #[test]
         fn error_reading_input() {
             let config = get_config();
-            let game_provider = setup(vec![Err(())], vec![], vec![]);
+            let game_provider = setup(vec![Err(get_input_error())], vec![], vec![]);
 
-            assert_eq!(
-                Err(()),
-                game_provider.run_interactive_rcon_session(&config, "host", "port")
-            );
+            assert!(game_provider
+                .run_interactive_rcon_session(&config, "host", "port", "password")
+                .is_err());
         }
 
         #[test]
@@ -194,13 +423,12 @@ mod tests {
             let game_provider = setup(
                 vec![Ok(InputResponse::Input("test".to_owned()))],
                 vec!["test".to_owned()],
-                vec![Err(())],
+                vec![Err(get_rcon_error())],
             );
 
-            assert_eq!(
-                Err(()),
-                game_provider.run_interactive_rcon_session(&config, "host", "port")
-            );
+            assert!(game_provider
+                .run_interactive_rcon_session(&config, "host", "port", "password")
+                .is_err());
         }
 
         #[test]
@@ -216,10 +444,9 @@ mod tests {
                 vec![Ok("response1".to_owned()), Ok("response2".to_owned())],
             );
 
-            assert_eq!(
-                Ok(()),
-                game_provider.run_interactive_rcon_session(&config, "host", "port")
-            );
+            assert!(game_provider
+                .run_interactive_rcon_session(&config, "host", "port", "password")
+                .is_ok());
         }
     }
 
@@ -228,13 +455,16 @@ mod tests {
 
         fn setup(
             rcon_inputs: Vec<String>,
-            rcon_responses: Vec<Result<String, ()>>,
-        ) -> GameProviderImpl<MockRconBackendFactory, MockInputBackendFactory> {
+            rcon_responses: Vec<Result<String, RconError>>,
+        ) -> GameProviderImpl<
+            MockRconBackendFactory,
+            MockInputBackendFactory,
+            MockPingBackend,
+            MockQueryBackend,
+        > {
             let mut rcon_sequence = Sequence::new();
             let mut mock_rcon_backend = MockRconBackend::new();
-            for rcon_idx in 0..rcon_inputs.len() {
-                let expected_input = rcon_inputs[rcon_idx].to_owned();
-                let response = rcon_responses[rcon_idx].clone();
+            for (expected_input, response) in rcon_inputs.into_iter().zip(rcon_responses) {
                 mock_rcon_backend
                     .expect_cmd()
                     .times(1)
@@ -246,13 +476,16 @@ mod tests {
             let mut mock_rcon_factory = MockRconBackendFactory::new();
             mock_rcon_factory
                 .expect_create()
-                .with(eq("host"), eq("port"))
+                .with(eq("host"), eq("port"), eq("password"))
                 .times(1)
-                .return_once(move |_, _| Ok(mock_rcon_backend));
+                .return_once(move |_, _, _| Ok(mock_rcon_backend));
 
             GameProviderImpl {
                 rcon_backend_factory: mock_rcon_factory,
                 input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
             }
         }
 
@@ -261,29 +494,30 @@ mod tests {
             let mut mock_rcon_factory = MockRconBackendFactory::new();
             mock_rcon_factory
                 .expect_create()
-                .with(eq("host"), eq("port"))
+                .with(eq("host"), eq("port"), eq("password"))
                 .times(1)
-                .return_once(move |_, _| Err(()));
+                .return_once(move |_, _, _| Err(get_rcon_error()));
 
             let game_provider = GameProviderImpl {
                 rcon_backend_factory: mock_rcon_factory,
                 input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
             };
 
-            assert_eq!(
-                Err(()),
-                game_provider.run_rcon_commands("host", "port", vec![])
-            );
+            assert!(game_provider
+                .run_rcon_commands("host", "port", "password", vec![])
+                .is_err());
         }
 
         #[test]
         fn error_running_command() {
-            let game_provider = setup(vec!["test".to_owned()], vec![Err(())]);
+            let game_provider = setup(vec!["test".to_owned()], vec![Err(get_rcon_error())]);
 
-            assert_eq!(
-                Err(()),
-                game_provider.run_rcon_commands("host", "port", vec!["test".to_owned()])
-            );
+            assert!(game_provider
+                .run_rcon_commands("host", "port", "password", vec!["test".to_owned()])
+                .is_err());
         }
 
         #[test]
@@ -294,13 +528,398 @@ mod tests {
             );
 
             assert_eq!(
-                Ok(vec!["resp1".to_owned(), "resp2".to_owned()]),
-                game_provider.run_rcon_commands(
+                vec!["resp1".to_owned(), "resp2".to_owned()],
+                game_provider
+                    .run_rcon_commands(
+                        "host",
+                        "port",
+                        "password",
+                        vec!["cmd1".to_owned(), "cmd2".to_owned()]
+                    )
+                    .unwrap()
+            );
+        }
+    }
+
+    mod test_exec_rcon_commands {
+        use super::*;
+
+        fn setup(
+            rcon_inputs: Vec<&'static str>,
+            rcon_responses: Vec<Result<String, RconError>>,
+        ) -> GameProviderImpl<
+            MockRconBackendFactory,
+            MockInputBackendFactory,
+            MockPingBackend,
+            MockQueryBackend,
+        > {
+            let mut rcon_sequence = Sequence::new();
+            let mut mock_rcon_backend = MockRconBackend::new();
+            for (input, response) in rcon_inputs.into_iter().zip(rcon_responses) {
+                mock_rcon_backend
+                    .expect_cmd()
+                    .times(1)
+                    .withf(move |cmd| cmd == input)
+                    .return_once(move |_| response)
+                    .in_sequence(&mut rcon_sequence);
+            }
+
+            let mut mock_rcon_factory = MockRconBackendFactory::new();
+            mock_rcon_factory
+                .expect_create()
+                .with(eq("host"), eq("port"), eq("password"))
+                .times(1)
+                .return_once(move |_, _, _| Ok(mock_rcon_backend));
+
+            GameProviderImpl {
+                rcon_backend_factory: mock_rcon_factory,
+                input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
+            }
+        }
+
+        #[test]
+        fn error_on_connect() {
+            let mut mock_rcon_factory = MockRconBackendFactory::new();
+            mock_rcon_factory
+                .expect_create()
+                .times(1)
+                .return_once(move |_, _, _| Err(get_rcon_error()));
+
+            let game_provider = GameProviderImpl {
+                rcon_backend_factory: mock_rcon_factory,
+                input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
+            };
+
+            assert!(game_provider
+                .exec_rcon_commands("host", "port", "password", &["cmd1".to_owned()])
+                .is_err());
+        }
+
+        #[test]
+        fn stops_at_first_error() {
+            let game_provider = setup(
+                vec!["cmd1", "cmd2"],
+                vec![Ok("resp1".to_owned()), Err(get_rcon_error())],
+            );
+
+            let responses = game_provider
+                .exec_rcon_commands(
                     "host",
                     "port",
-                    vec!["cmd1".to_owned(), "cmd2".to_owned()]
+                    "password",
+                    &["cmd1".to_owned(), "cmd2".to_owned(), "cmd3".to_owned()],
                 )
+                .unwrap();
+            assert_eq!(2, responses.len());
+            assert_eq!("resp1", responses[0].as_ref().unwrap());
+            assert!(matches!(responses[1], Err(GameError::Rcon(_))));
+        }
+
+        #[test]
+        fn success() {
+            let game_provider = setup(
+                vec!["cmd1", "cmd2"],
+                vec![Ok("resp1".to_owned()), Ok("".to_owned())],
             );
+
+            let responses = game_provider
+                .exec_rcon_commands(
+                    "host",
+                    "port",
+                    "password",
+                    &["cmd1".to_owned(), "cmd2".to_owned()],
+                )
+                .unwrap()
+                .into_iter()
+                .collect::<Result<Vec<String>, GameError>>()
+                .unwrap();
+            assert_eq!(vec!["resp1".to_owned(), "".to_owned()], responses);
         }
     }
+
+    mod test_broadcast_stop_countdown {
+        use super::*;
+
+        macro_rules! get_countdown_tests {
+            ($($name:ident: $seconds:expr, $expected:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!($expected.to_vec(), get_countdown($seconds));
+                }
+            )*
+            }
+        }
+
+        get_countdown_tests! {
+            countdown_none: 0, [] as [(u64, u64); 0],
+            countdown_one: 1, [(1, 1)],
+            countdown_on_warning: 10, [(10, 5), (5, 1), (4, 1), (3, 1), (2, 1), (1, 1)],
+            countdown_between_warnings: 45, [(45, 15), (30, 20), (10, 5), (5, 1), (4, 1), (3, 1), (2, 1), (1, 1)],
+            countdown_minutes: 180, [(180, 60), (120, 60), (60, 30), (30, 20), (10, 5), (5, 1), (4, 1), (3, 1), (2, 1), (1, 1)],
+        }
+
+        macro_rules! format_remaining_time_tests {
+            ($($name:ident: $seconds:expr, $expected:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!($expected, format_remaining_time($seconds));
+                }
+            )*
+            }
+        }
+
+        format_remaining_time_tests! {
+            format_one_second: 1, "1 second",
+            format_seconds: 30, "30 seconds",
+            format_one_minute: 60, "1 minute",
+            format_minutes: 300, "5 minutes",
+            format_uneven_minutes: 90, "90 seconds",
+        }
+
+        #[test]
+        fn broadcasts_warnings() {
+            let mut rcon_sequence = Sequence::new();
+            let mut mock_rcon_backend = MockRconBackend::new();
+            for expected_input in [
+                "say The server will stop in 5 seconds",
+                "say The server will stop in 4 seconds",
+                "say The server will stop in 3 seconds",
+                "say The server will stop in 2 seconds",
+                "say The server will stop in 1 second",
+            ] {
+                mock_rcon_backend
+                    .expect_cmd()
+                    .times(1)
+                    .withf(move |input| input == expected_input)
+                    .returning(|_| Ok("".to_owned()))
+                    .in_sequence(&mut rcon_sequence);
+            }
+
+            let mut mock_rcon_factory = MockRconBackendFactory::new();
+            mock_rcon_factory
+                .expect_create()
+                .with(eq("host"), eq("port"), eq("password"))
+                .times(1)
+                .return_once(move |_, _, _| Ok(mock_rcon_backend));
+
+            let game_provider = GameProviderImpl {
+                rcon_backend_factory: mock_rcon_factory,
+                input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
+            };
+
+            assert!(game_provider
+                .broadcast_stop_countdown("host", "port", "password", 5)
+                .is_ok());
+        }
+
+        #[test]
+        fn no_warning() {
+            let mut mock_rcon_factory = MockRconBackendFactory::new();
+            mock_rcon_factory.expect_create().times(0);
+
+            let game_provider = GameProviderImpl {
+                rcon_backend_factory: mock_rcon_factory,
+                input_backend_factory: MockInputBackendFactory::new(),
+                ping_backend: MockPingBackend::new(),
+                query_backend: MockQueryBackend::new(),
+                sleep: |_| {},
+            };
+
+            assert!(game_provider
+                .broadcast_stop_countdown("host", "port", "password", 0)
+                .is_ok());
+        }
+    }
+
+    macro_rules! parse_player_count_tests {
+        ($($name:ident: $response:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, parse_player_count($response));
+            }
+        )*
+        }
+    }
+
+    parse_player_count_tests! {
+        test_parse_player_count: "There are 2 of a max of 20 players online: alice, bob", Some(PlayerCount { online: 2, max: 20 }),
+        test_parse_player_count_empty: "There are 0 of a max of 20 players online: ", Some(PlayerCount { online: 0, max: 20 }),
+        test_parse_player_count_legacy: "There are 1/10 players online:\nalice", Some(PlayerCount { online: 1, max: 10 }),
+        test_parse_player_count_colored: "There are \u{a7}c3\u{a7}r of a max of \u{a7}c20\u{a7}r players online: a, b, c", Some(PlayerCount { online: 3, max: 20 }),
+        test_parse_player_count_unknown: "Unknown command", None,
+    }
+
+    #[test]
+    fn test_get_player_count() {
+        let mut rcon_backend_factory = MockRconBackendFactory::new();
+        rcon_backend_factory
+            .expect_create()
+            .with(eq("host"), eq("port"), eq("password"))
+            .times(1)
+            .returning(|_, _, _| {
+                let mut rcon_backend = MockRconBackend::new();
+                rcon_backend
+                    .expect_cmd()
+                    .with(eq("list"))
+                    .times(1)
+                    .returning(|_| {
+                        Ok("There are 1 of a max of 20 players online: alice".to_owned())
+                    });
+                Ok(rcon_backend)
+            });
+
+        let game_provider = GameProviderImpl {
+            rcon_backend_factory,
+            input_backend_factory: MockInputBackendFactory::new(),
+            ping_backend: MockPingBackend::new(),
+            query_backend: MockQueryBackend::new(),
+            sleep: |_| (),
+        };
+
+        assert_eq!(
+            Some(PlayerCount { online: 1, max: 20 }),
+            game_provider
+                .get_player_count("host", "port", "password")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_ping_server() {
+        let mut ping_backend = MockPingBackend::new();
+        ping_backend
+            .expect_ping()
+            .with(eq("127.0.0.1"), eq(25565))
+            .times(1)
+            .returning(|_, _| {
+                Ok(ServerStatus {
+                    version: "1.20.4".to_owned(),
+                    protocol: 765,
+                    motd: "A Minecraft Server".to_owned(),
+                    online: 1,
+                    max: 20,
+                    latency_ms: 3,
+                })
+            });
+
+        let game_provider = GameProviderImpl {
+            rcon_backend_factory: MockRconBackendFactory::new(),
+            input_backend_factory: MockInputBackendFactory::new(),
+            ping_backend,
+            query_backend: MockQueryBackend::new(),
+            sleep: |_| (),
+        };
+
+        let status = game_provider.ping_server("127.0.0.1", 25565).unwrap();
+        assert_eq!("1.20.4", status.version);
+        assert_eq!(1, status.online);
+    }
+
+    #[test]
+    fn test_query_server() {
+        let mut query_backend = MockQueryBackend::new();
+        query_backend
+            .expect_query()
+            .with(eq("127.0.0.1"), eq(25565))
+            .times(1)
+            .returning(|_, _| {
+                Ok(QueryStatus {
+                    online: 1,
+                    max: 20,
+                    players: vec!["alice".to_owned()],
+                    ..std::default::Default::default()
+                })
+            });
+
+        let game_provider = GameProviderImpl {
+            rcon_backend_factory: MockRconBackendFactory::new(),
+            input_backend_factory: MockInputBackendFactory::new(),
+            ping_backend: MockPingBackend::new(),
+            query_backend,
+            sleep: |_| (),
+        };
+
+        let status = game_provider.query_server("127.0.0.1", 25565).unwrap();
+        assert_eq!(vec!["alice".to_owned()], status.players);
+    }
+
+    fn get_input_game_provider(
+        is_terminal: bool,
+        input: Option<Result<String, InputError>>,
+    ) -> GameProviderImpl<
+        MockRconBackendFactory,
+        MockInputBackendFactory,
+        MockPingBackend,
+        MockQueryBackend,
+    > {
+        let mut mock_input_backend = MockInputBackend::new();
+        mock_input_backend
+            .expect_is_terminal()
+            .times(1)
+            .return_const(is_terminal);
+        match input {
+            Some(input) => {
+                mock_input_backend
+                    .expect_read_to_end()
+                    .times(1)
+                    .return_once(move || input);
+            }
+            None => {
+                mock_input_backend.expect_read_to_end().times(0);
+            }
+        }
+
+        let mut mock_input_factory = MockInputBackendFactory::new();
+        mock_input_factory
+            .expect_create()
+            .times(1)
+            .return_once(move || mock_input_backend);
+
+        GameProviderImpl {
+            rcon_backend_factory: MockRconBackendFactory::new(),
+            input_backend_factory: mock_input_factory,
+            ping_backend: MockPingBackend::new(),
+            query_backend: MockQueryBackend::new(),
+            sleep: |_| (),
+        }
+    }
+
+    #[test]
+    fn test_read_piped_input() {
+        let game_provider = get_input_game_provider(false, Some(Ok("list\n".to_owned())));
+
+        assert_eq!(
+            Some("list\n".to_owned()),
+            game_provider.read_piped_input().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_piped_input_terminal() {
+        let game_provider = get_input_game_provider(true, None);
+
+        assert_eq!(None, game_provider.read_piped_input().unwrap());
+    }
+
+    #[test]
+    fn test_read_piped_input_error() {
+        let game_provider = get_input_game_provider(false, Some(Err(get_input_error())));
+
+        assert!(matches!(
+            game_provider.read_piped_input(),
+            Err(GameError::Input(_))
+        ));
+    }
 }
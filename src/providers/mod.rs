@@ -2,3 +2,4 @@ pub mod backends;
 pub mod container;
 pub mod file;
 pub mod game;
+pub mod notification;
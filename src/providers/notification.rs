@@ -0,0 +1,285 @@
+use crate::config::{Config, Webhook, WebhookFormat};
+use crate::events::GameEvent;
+use crate::providers::backends::webhook::{self, WebhookBackend, WebhookBackendImpl, WebhookError};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+// The delays before each retry of a failed webhook
+const RETRY_DELAYS: [u64; 3] = [1, 2, 4];
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationError {
+    #[error(transparent)]
+    Webhook(#[from] WebhookError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Notification {
+    ContainerStarted,
+    ContainerStopped,
+    Game(GameEvent),
+}
+
+impl Notification {
+    // The name of the notification, as used for the events of each webhook in the config
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::ContainerStarted => "container_started",
+            Notification::ContainerStopped => "container_stopped",
+            Notification::Game(event) => event.kind(),
+        }
+    }
+}
+
+impl std::fmt::Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notification::ContainerStarted => write!(f, "The server container was started"),
+            Notification::ContainerStopped => write!(f, "The server container was stopped"),
+            Notification::Game(event) => event.fmt(f),
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+pub trait NotificationProvider {
+    fn send_notification(
+        &self,
+        config: &Config,
+        webhook: &Webhook,
+        notification: &Notification,
+        time: DateTime<Utc>,
+    ) -> Result<(), NotificationError>;
+}
+
+pub struct NotificationProviderImpl<WebhookBackendType: WebhookBackend> {
+    webhook_backend: WebhookBackendType,
+    sleep: fn(Duration),
+}
+
+impl<WebhookBackendType: WebhookBackend> NotificationProvider
+    for NotificationProviderImpl<WebhookBackendType>
+{
+    fn send_notification(
+        &self,
+        config: &Config,
+        webhook: &Webhook,
+        notification: &Notification,
+        time: DateTime<Utc>,
+    ) -> Result<(), NotificationError> {
+        log::debug!("Sending the {} notification", notification.kind());
+        let payload = build_payload(&config.name, webhook.format, notification, time);
+
+        let mut delays = RETRY_DELAYS.iter();
+        loop {
+            match self.webhook_backend.post(&webhook.url, &payload) {
+                Ok(()) => return Ok(()),
+                Err(err) if err.is_retryable() => match delays.next() {
+                    Some(delay) => {
+                        log::debug!("{}, retrying in {} seconds", err, delay);
+                        (self.sleep)(Duration::from_secs(*delay));
+                    }
+                    None => return Err(err.into()),
+                },
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+fn build_payload(
+    server: &str,
+    format: WebhookFormat,
+    notification: &Notification,
+    time: DateTime<Utc>,
+) -> serde_json::Value {
+    let text = format!("[{}] {}", server, notification);
+    match format {
+        WebhookFormat::Json => {
+            let mut payload = match notification {
+                Notification::Game(event) => serde_json::to_value(event).unwrap_or_default(),
+                _ => serde_json::json!({ "type": notification.kind() }),
+            };
+            payload["server"] = server.into();
+            payload["time"] = time.to_rfc3339().into();
+            payload["message"] = notification.to_string().into();
+            payload
+        }
+        WebhookFormat::Discord => serde_json::json!({ "content": text }),
+        WebhookFormat::Slack => serde_json::json!({ "text": text }),
+    }
+}
+
+pub fn new_from_defaults() -> NotificationProviderImpl<WebhookBackendImpl> {
+    NotificationProviderImpl {
+        webhook_backend: webhook::new_from_defaults(),
+        sleep: std::thread::sleep,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::backends::webhook::MockWebhookBackend;
+    use chrono::TimeZone;
+    use mockall::predicate::{always, eq};
+
+    fn get_notification_provider() -> NotificationProviderImpl<MockWebhookBackend> {
+        NotificationProviderImpl {
+            webhook_backend: MockWebhookBackend::new(),
+            sleep: |_| {},
+        }
+    }
+
+    fn get_config() -> Config {
+        Config {
+            name: "name".to_owned(),
+            ..std::default::Default::default()
+        }
+    }
+
+    fn get_webhook(format: WebhookFormat) -> Webhook {
+        Webhook {
+            url: "https://example.com/hook".to_owned(),
+            format,
+            events: None,
+        }
+    }
+
+    fn get_time() -> DateTime<Utc> {
+        Utc.timestamp(1717243200, 0)
+    }
+
+    fn get_status_error(status: u16) -> WebhookError {
+        WebhookError::Status {
+            host: "https://example.com".to_owned(),
+            status,
+        }
+    }
+
+    macro_rules! build_payload_tests {
+        ($($name:ident: $format:expr, $notification:expr, $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!($expected, build_payload("name", $format, &$notification, get_time()));
+            }
+        )*
+        }
+    }
+
+    build_payload_tests! {
+        test_payload_json_lifecycle: WebhookFormat::Json, Notification::ContainerStarted, serde_json::json!({
+            "type": "container_started",
+            "server": "name",
+            "time": "2024-06-01T12:00:00+00:00",
+            "message": "The server container was started",
+        }),
+        test_payload_json_game_event: WebhookFormat::Json, Notification::Game(GameEvent::Join { player: "Steve".to_owned() }), serde_json::json!({
+            "type": "join",
+            "player": "Steve",
+            "server": "name",
+            "time": "2024-06-01T12:00:00+00:00",
+            "message": "Steve joined the game",
+        }),
+        test_payload_discord: WebhookFormat::Discord, Notification::ContainerStopped, serde_json::json!({
+            "content": "[name] The server container was stopped",
+        }),
+        test_payload_slack: WebhookFormat::Slack, Notification::Game(GameEvent::Crash { report: None }), serde_json::json!({
+            "text": "[name] The server crashed",
+        }),
+    }
+
+    #[test]
+    fn test_send_notification() {
+        let mut notification_provider = get_notification_provider();
+
+        notification_provider
+            .webhook_backend
+            .expect_post()
+            .with(
+                eq("https://example.com/hook"),
+                eq(serde_json::json!({ "text": "[name] The server container was started" })),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(notification_provider
+            .send_notification(
+                &get_config(),
+                &get_webhook(WebhookFormat::Slack),
+                &Notification::ContainerStarted,
+                get_time(),
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_send_notification_retries() {
+        let mut notification_provider = get_notification_provider();
+        let mut attempts = 0;
+
+        notification_provider
+            .webhook_backend
+            .expect_post()
+            .with(eq("https://example.com/hook"), always())
+            .times(3)
+            .returning(move |_, _| {
+                attempts += 1;
+                match attempts {
+                    1 => Err(get_status_error(500)),
+                    2 => Err(get_status_error(429)),
+                    _ => Ok(()),
+                }
+            });
+
+        assert!(notification_provider
+            .send_notification(
+                &get_config(),
+                &get_webhook(WebhookFormat::Json),
+                &Notification::ContainerStarted,
+                get_time(),
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_send_notification_gives_up() {
+        let mut notification_provider = get_notification_provider();
+
+        notification_provider
+            .webhook_backend
+            .expect_post()
+            .times(RETRY_DELAYS.len() + 1)
+            .returning(|_, _| Err(get_status_error(503)));
+
+        assert!(notification_provider
+            .send_notification(
+                &get_config(),
+                &get_webhook(WebhookFormat::Json),
+                &Notification::ContainerStopped,
+                get_time(),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_send_notification_not_retryable() {
+        let mut notification_provider = get_notification_provider();
+
+        notification_provider
+            .webhook_backend
+            .expect_post()
+            .times(1)
+            .returning(|_, _| Err(get_status_error(404)));
+
+        assert!(notification_provider
+            .send_notification(
+                &get_config(),
+                &get_webhook(WebhookFormat::Json),
+                &Notification::ContainerStopped,
+                get_time(),
+            )
+            .is_err());
+    }
+}
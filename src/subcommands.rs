@@ -1,35 +1,279 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::args::{EventsArgs, LogTail, LogsArgs, OutputFormat, ServerAddress};
 use crate::config;
+use crate::events::{self, GameEvent, TimedEvent};
 use crate::providers::{
     self,
-    container::{ContainerState, GameState},
+    backends::ping::ServerStatus,
+    container::{ContainerError, ContainerState, GameState, PublishedPort},
+    file::FileError,
+    game::{GameError, PlayerCount},
+    notification::Notification,
 };
 
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError {
+    #[error("{0}")]
+    InvalidState(String),
+    #[error("{message}")]
+    Container {
+        message: String,
+        #[source]
+        source: ContainerError,
+    },
+    #[error("{message}")]
+    File {
+        message: String,
+        #[source]
+        source: FileError,
+    },
+    #[error("{message}")]
+    Game {
+        message: String,
+        #[source]
+        source: GameError,
+    },
+    #[error("{0}")]
+    Timeout(String),
+}
+
+impl CommandError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandError::InvalidState(_) => 3,
+            CommandError::Container { .. } => 4,
+            CommandError::File { .. } => 5,
+            CommandError::Game { .. } => 6,
+            CommandError::Timeout(_) => 7,
+        }
+    }
+}
+
+fn container_error(message: &str) -> impl FnOnce(ContainerError) -> CommandError + '_ {
+    move |source| CommandError::Container {
+        message: message.to_owned(),
+        source,
+    }
+}
+
+fn file_error(message: &str) -> impl FnOnce(FileError) -> CommandError + '_ {
+    move |source| CommandError::File {
+        message: message.to_owned(),
+        source,
+    }
+}
+
+fn game_error(message: &str) -> impl FnOnce(GameError) -> CommandError + '_ {
+    move |source| CommandError::Game {
+        message: message.to_owned(),
+        source,
+    }
+}
+
+fn invalid_state(message: &str) -> CommandError {
+    CommandError::InvalidState(message.to_owned())
+}
+
+const WAIT_POLL_INTERVAL: u64 = 2;
+const WAIT_LOG_TAIL: u64 = 20;
+const WATCH_POLL_INTERVAL: u64 = 10;
+const DEFAULT_LOG_TAIL: u64 = 10;
+
+// Where the watcher is in the log, and whether the container was running and logged that it
+// was stopping when it was last checked
+#[derive(Debug, PartialEq)]
+struct WatchState {
+    since: chrono::DateTime<chrono::Utc>,
+    running: bool,
+    stop_logged: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct StatusReport {
+    name: String,
+    container: &'static str,
+    game: Option<&'static str>,
+    image: Option<String>,
+    started_at: Option<String>,
+    uptime_seconds: Option<i64>,
+    ports: Vec<PublishedPort>,
+    rcon_address: Option<String>,
+    players: Option<PlayerCount>,
+    server: Option<ServerStatus>,
+    config_changed: Option<bool>,
+    server_type: String,
+    version: String,
+    #[serde(skip)]
+    state: ContainerState,
+}
+
+fn container_state_name(state: &ContainerState) -> &'static str {
+    match state {
+        ContainerState::Unknown => "unknown",
+        ContainerState::NotFound => "not_found",
+        ContainerState::Stopped => "stopped",
+        ContainerState::Running(_) => "running",
+    }
+}
+
+fn game_state_name(state: &GameState) -> &'static str {
+    match state {
+        GameState::Unknown => "unknown",
+        GameState::Starting => "starting",
+        GameState::Running => "running",
+        GameState::Unhealthy => "unhealthy",
+    }
+}
+
+// The status exit codes start at 10 to stay clear of the error exit codes
+fn status_exit_code(state: &ContainerState) -> i32 {
+    match state {
+        ContainerState::Running(GameState::Running) => 0,
+        ContainerState::Running(GameState::Starting) => 10,
+        ContainerState::Running(GameState::Unknown) => 11,
+        ContainerState::Stopped => 12,
+        ContainerState::NotFound => 13,
+        ContainerState::Unknown => 14,
+        ContainerState::Running(GameState::Unhealthy) => 15,
+    }
+}
+
+// Startup milestones and problems are shown while waiting for the server, the rest of the log
+// only with --verbose
+fn is_progress_log_line(line: &str) -> bool {
+    [
+        "[init]",
+        "Starting minecraft server",
+        "Preparing level",
+        "Done (",
+        "/WARN]",
+        " WARN]",
+        "/ERROR]",
+        " ERROR]",
+        "Exception",
+    ]
+    .iter()
+    .any(|pattern| line.contains(pattern))
+}
+
+// A server bound to all interfaces is reached over loopback
+fn server_address(config: &config::Config, port: i32) -> Result<(&str, u16), CommandError> {
+    let host = match config.host.as_str() {
+        "" | "0.0.0.0" | "::" => "127.0.0.1",
+        host => host,
+    };
+    let port = u16::try_from(port)
+        .map_err(|_| invalid_state(&format!("The port {} is not valid", port)))?;
+    Ok((host, port))
+}
+
+fn log_options(
+    logs_args: &LogsArgs,
+    now: chrono::DateTime<chrono::Utc>,
+) -> providers::backends::docker::LogOptions {
+    let since = logs_args
+        .since
+        .as_ref()
+        .map_or(0, |since| since.timestamp(now));
+    let until = logs_args
+        .until
+        .as_ref()
+        .map_or(0, |until| until.timestamp(now));
+    providers::backends::docker::LogOptions {
+        follow: !logs_args.no_follow,
+        tail: match logs_args.tail {
+            Some(LogTail::Lines(lines)) => Some(lines),
+            Some(LogTail::All) => None,
+            // A time range shows all of its lines unless a tail is given
+            None if since == 0 && until == 0 => Some(DEFAULT_LOG_TAIL),
+            None => None,
+        },
+        since,
+        until,
+        timestamps: logs_args.timestamps,
+    }
+}
+
+fn events_log_options(
+    events_args: &EventsArgs,
+    now: chrono::DateTime<chrono::Utc>,
+) -> providers::backends::docker::LogOptions {
+    let since = events_args
+        .since
+        .as_ref()
+        .map_or(0, |since| since.timestamp(now));
+    let until = events_args
+        .until
+        .as_ref()
+        .map_or(0, |until| until.timestamp(now));
+    providers::backends::docker::LogOptions {
+        follow: !events_args.no_follow,
+        // Following only shows new events unless a time range is given
+        tail: if events_args.no_follow || since != 0 || until != 0 {
+            None
+        } else {
+            Some(0)
+        },
+        since,
+        until,
+        timestamps: true,
+    }
+}
+
+fn format_uptime(seconds: i64) -> String {
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    );
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, _) => format!("{}m {}s", minutes, seconds),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
 pub struct SubCommands<
     T1: providers::container::ContainerProvider,
     T2: providers::file::FileProvider,
     T3: providers::game::GameProvider,
+    T4: providers::notification::NotificationProvider,
 > {
     container_provider: T1,
     file_provider: T2,
     game_provider: T3,
+    notification_provider: T4,
+    sleep: fn(Duration),
 }
 
-pub fn new_from_defaults() -> Result<
-    SubCommands<
-        providers::container::ContainerProviderImpl<providers::backends::docker::DockerBackendImpl>,
-        providers::file::FileProviderImpl<providers::backends::filesystem::FilesystemBackendImpl>,
-        providers::game::GameProviderImpl<
-            providers::backends::rcon::RconBackendFactoryImpl,
-            providers::backends::input::InputBackendFactoryImpl,
-        >,
+pub type DefaultSubCommands<T> = SubCommands<
+    providers::container::ContainerProviderImpl<T>,
+    providers::file::FileProviderImpl<providers::backends::filesystem::FilesystemBackendImpl>,
+    providers::game::GameProviderImpl<
+        providers::backends::rcon::RconBackendFactoryImpl,
+        providers::backends::input::InputBackendFactoryImpl,
+        providers::backends::ping::PingBackendImpl,
+        providers::backends::query::QueryBackendImpl,
     >,
-    (),
-> {
-    Ok(SubCommands {
-        container_provider: providers::container::new_from_defaults()?,
+    providers::notification::NotificationProviderImpl<
+        providers::backends::webhook::WebhookBackendImpl,
+    >,
+>;
+
+pub fn new_from_defaults<T: providers::backends::docker::DockerBackend>(
+    docker_backend: T,
+) -> DefaultSubCommands<T> {
+    SubCommands {
+        container_provider: providers::container::new_from_backend(docker_backend),
         file_provider: providers::file::new_from_defaults(),
         game_provider: providers::game::new_from_defaults(),
-    })
+        notification_provider: providers::notification::new_from_defaults(),
+        sleep: std::thread::sleep,
+    }
 }
 
 impl<
@@ -37,457 +281,3279 @@ impl<
         T1: providers::container::ContainerProvider,
         T2: providers::file::FileProvider,
         T3: providers::game::GameProvider,
-    > SubCommands<T1, T2, T3>
+        T4: providers::notification::NotificationProvider,
+    > SubCommands<T1, T2, T3, T4>
 {
-    pub fn up(&self, config: &config::Config) -> Result<(), ()> {
+    pub fn up(
+        &self,
+        config: &config::Config,
+        recreate: bool,
+        warn: Option<u64>,
+        timeout: u64,
+        wait: Option<u64>,
+    ) -> Result<(), CommandError> {
+        if recreate
+            && self.get_container_status(config)? != ContainerState::NotFound
+            && self.has_config_drift(config)? == Some(true)
+        {
+            log::info!("The config has changed, recreating the container");
+            self.down(config, warn, timeout)?;
+        }
+
         self.create(config)?;
-        self.start(config)
+        self.start(config, wait)
     }
 
-    pub fn down(&self, config: &config::Config) -> Result<(), ()> {
-        self.stop(config)?;
+    pub fn down(
+        &self,
+        config: &config::Config,
+        warn: Option<u64>,
+        timeout: u64,
+    ) -> Result<(), CommandError> {
+        self.stop(config, warn, timeout)?;
         self.destroy(config)
     }
 
-    pub fn create(&self, config: &config::Config) -> Result<(), ()> {
-        if self.container_provider.get_container_status(&config)? != ContainerState::NotFound {
-            log::warn!("Container already exists");
+    pub fn create(&self, config: &config::Config) -> Result<(), CommandError> {
+        if self.get_container_status(config)? != ContainerState::NotFound {
+            if self.has_config_drift(config)? == Some(true) {
+                log::warn!("Container already exists but the config has changed, run up --recreate to apply it");
+            } else {
+                log::warn!("Container already exists");
+            }
             return Ok(());
         }
 
-        if let Err(()) = self.file_provider.create_data_folder() {
-            log::error!("Failed to create data folder");
-            return Err(());
-        }
+        self.file_provider
+            .create_data_folder()
+            .map_err(file_error("Failed to create data folder"))?;
 
-        let data_path = self.file_provider.get_data_path().or_else(|_| {
-            log::error!("Failed to get the data path");
-            Err(())
-        })?;
+        let data_path = self
+            .file_provider
+            .get_data_path()
+            .map_err(file_error("Failed to get the data path"))?;
 
-        if let Err(()) = self.container_provider.create_container(config, &data_path) {
-            log::error!("Failed to create the container");
-            return Err(());
-        }
+        self.container_provider
+            .create_container(config, &data_path)
+            .map_err(container_error("Failed to create the container"))?;
 
         log::info!("Created the server container {}", config.name);
         Ok(())
     }
 
-    pub fn destroy(&self, config: &config::Config) -> Result<(), ()> {
-        if self.container_provider.get_container_status(&config)? != ContainerState::Stopped {
-            log::error!("Container is not stopped");
-            return Err(());
+    pub fn destroy(&self, config: &config::Config) -> Result<(), CommandError> {
+        if self.get_container_status(config)? != ContainerState::Stopped {
+            return Err(invalid_state("Container is not stopped"));
         }
 
-        if let Err(()) = self.container_provider.delete_container(&config) {
-            log::error!("Failed to delete the container");
-            return Err(());
-        }
+        self.container_provider
+            .delete_container(config)
+            .map_err(container_error("Failed to delete the container"))?;
 
         log::info!("Destroyed the server container {}", config.name);
         Ok(())
     }
 
-    pub fn start(&self, config: &config::Config) -> Result<(), ()> {
-        if self.container_provider.get_container_status(&config)? != ContainerState::Stopped {
-            log::error!("Container is not stopped");
-            return Err(());
+    pub fn start(&self, config: &config::Config, wait: Option<u64>) -> Result<(), CommandError> {
+        if self.get_container_status(config)? != ContainerState::Stopped {
+            return Err(invalid_state("Container is not stopped"));
         }
 
-        if let Err(()) = self.file_provider.create_data_folder() {
-            log::error!("Failed to create data folder");
-            return Err(());
-        }
+        self.file_provider
+            .create_data_folder()
+            .map_err(file_error("Failed to create data folder"))?;
 
-        if let Err(()) = self
+        let rcon_password = self
             .file_provider
-            .create_and_populate_server_properties(&config)
-        {
-            log::error!("Failed to create server.properties");
-            return Err(());
-        }
+            .create_rcon_password(config)
+            .map_err(file_error("Failed to create the rcon password"))?;
 
-        if let Err(()) = self.container_provider.start_container(&config) {
-            log::error!("Failed to start the container");
-            return Err(());
-        }
+        self.file_provider
+            .create_and_populate_server_properties(config, &rcon_password)
+            .map_err(file_error("Failed to create server.properties"))?;
+
+        let started = chrono::Utc::now();
+        self.container_provider
+            .start_container(config)
+            .map_err(container_error("Failed to start the container"))?;
 
         log::info!("Started the server container {}", config.name);
-        Ok(())
+        self.notify(config, Notification::ContainerStarted);
+        match wait {
+            Some(timeout) => self.wait_for_server(config, started, timeout),
+            None => Ok(()),
+        }
     }
 
-    pub fn stop(&self, config: &config::Config) -> Result<(), ()> {
-        if !matches!(
-            self.container_provider.get_container_status(&config)?,
-            ContainerState::Running(_)
-        ) {
-            log::warn!("Container is not running");
-            return Ok(());
-        }
+    fn wait_for_server(
+        &self,
+        config: &config::Config,
+        since: chrono::DateTime<chrono::Utc>,
+        timeout: u64,
+    ) -> Result<(), CommandError> {
+        log::info!("Waiting up to {}s for the server to start", timeout);
+        let mut since = since;
+        let mut waited = 0;
+        loop {
+            since = self.show_new_logs(config, since);
+            // Only the runtimes that don't run healthchecks on their own do anything here
+            if let Err(err) = self.container_provider.run_container_healthcheck(config) {
+                log::debug!("Failed to run the healthcheck: {}", err);
+            }
+            match self.get_container_status(config)? {
+                ContainerState::Running(GameState::Running) => break,
+                // Without a healthcheck the server is up once it answers a ping
+                ContainerState::Running(GameState::Unknown)
+                    if self.ping_server(config, None).is_ok() =>
+                {
+                    break
+                }
+                ContainerState::Running(GameState::Unhealthy) => {
+                    return Err(self.wait_failed(
+                        config,
+                        invalid_state("The server's healthcheck reports that it is unhealthy"),
+                    ));
+                }
+                ContainerState::Stopped | ContainerState::NotFound => {
+                    return Err(self.wait_failed(
+                        config,
+                        invalid_state("The container exited before the server started"),
+                    ));
+                }
+                state => log::debug!("Waiting for the server, the container is {:?}", state),
+            }
 
-        if let Err(()) = self.container_provider.stop_container(&config) {
-            log::error!("Failed to start the container");
-            return Err(());
+            if waited >= timeout {
+                return Err(self.wait_failed(
+                    config,
+                    CommandError::Timeout(format!("The server did not start within {}s", timeout)),
+                ));
+            }
+            (self.sleep)(Duration::from_secs(WAIT_POLL_INTERVAL));
+            waited += WAIT_POLL_INTERVAL;
         }
 
-        log::info!("Stopped the server container {}", config.name);
+        log::info!("The server is running");
         Ok(())
     }
 
-    pub fn status(&self, config: &config::Config) -> Result<(), ()> {
-        match self.container_provider.get_container_status(&config) {
-            Ok(status) => {
-                match status {
-                    providers::container::ContainerState::Unknown => {
-                        log::info!("The state of the container is unkown");
-                    }
-                    providers::container::ContainerState::NotFound => {
-                        log::info!("The container does not exist");
-                    }
-                    providers::container::ContainerState::Running(game_state) => {
-                        log::info!("The container is currently running");
-                        match game_state {
-                            providers::container::GameState::Unknown => {
-                                log::info!("The state of the server is unknown")
-                            }
-                            providers::container::GameState::Starting => {
-                                log::info!("The server is starting")
-                            }
-                            providers::container::GameState::Running => {
-                                log::info!("The server is running")
-                            }
-                        };
-                    }
-                    providers::container::ContainerState::Stopped => {
-                        log::info!("The container is currently stopped");
-                    }
-                };
-                Ok(())
-            }
-            Err(()) => {
-                log::error!("Failed to get container status");
-                Err(())
+    // Shows the log lines written after since, returning the time of the last one
+    fn show_new_logs(
+        &self,
+        config: &config::Config,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> chrono::DateTime<chrono::Utc> {
+        match self
+            .container_provider
+            .get_container_logs(config, Some(since), None)
+        {
+            Ok(lines) => lines.into_iter().fold(since, |since, line| {
+                if is_progress_log_line(&line.text) {
+                    log::info!("{}", line.text);
+                } else {
+                    log::debug!("{}", line.text);
+                }
+                line.timestamp.unwrap_or(since)
+            }),
+            Err(err) => {
+                log::debug!("Failed to get the logs: {}", err);
+                since
             }
         }
     }
 
-    pub fn console(&self, config: &config::Config) -> Result<(), ()> {
-        if self.container_provider.get_container_status(&config)?
-            != ContainerState::Running(GameState::Running)
+    // Shows the end of the log, which usually explains why the server did not start
+    fn wait_failed(&self, config: &config::Config, err: CommandError) -> CommandError {
+        match self
+            .container_provider
+            .get_container_logs(config, None, Some(WAIT_LOG_TAIL))
         {
-            log::error!("Game server is not running");
-            return Err(());
+            Ok(lines) if !lines.is_empty() => {
+                log::error!("The last lines of the server log were:");
+                lines.iter().for_each(|line| log::error!("{}", line.text));
+            }
+            Ok(_) => (),
+            Err(err) => log::debug!("Failed to get the logs: {}", err),
         }
+        err
+    }
 
-        let (rcon_host, rcon_port) = self
-            .container_provider
-            .get_container_rcon_address(&config)
-            .or_else(|_| {
-                log::error!("Failed to get rcon address");
-                return Err(());
-            })?;
+    pub fn stop(
+        &self,
+        config: &config::Config,
+        warn: Option<u64>,
+        timeout: u64,
+    ) -> Result<(), CommandError> {
+        let game_state = match self.get_container_status(config)? {
+            ContainerState::Running(game_state) => game_state,
+            _ => {
+                log::warn!("Container is not running");
+                return Ok(());
+            }
+        };
+
+        if game_state == GameState::Running {
+            if let Err(err) = self.stop_game(config, warn) {
+                log::warn!(
+                    "Failed to stop the game gracefully, stopping the container: {}",
+                    err
+                );
+            }
+        }
 
-        self.game_provider
-            .run_interactive_rcon_session(&config, &rcon_host, &rcon_port)
-            .or_else(|_| {
-                log::error!("Failed to establish interactive rcon session");
-                return Err(());
-            })?;
+        // The container is stopped through docker even when the game was
+        // stopped over rcon, since docker would otherwise restart it. Docker
+        // waits up to the timeout for the container to exit before killing it.
+        self.container_provider
+            .stop_container(config, timeout)
+            .map_err(container_error("Failed to stop the container"))?;
 
+        log::info!("Stopped the server container {}", config.name);
+        self.notify(config, Notification::ContainerStopped);
         Ok(())
     }
 
-    pub fn sync_datapacks(&self, config: &config::Config) -> Result<(), ()> {
-        if let Err(()) = self.file_provider.sync_datapacks(&config) {
-            log::error!("Failed to sync datapacks");
-            return Err(());
-        }
+    fn stop_game(&self, config: &config::Config, warn: Option<u64>) -> Result<(), CommandError> {
+        let (rcon_host, rcon_port, rcon_password) = self.get_rcon_details(config)?;
 
-        if self.container_provider.get_container_status(&config)?
-            != ContainerState::Running(GameState::Running)
-        {
-            return Ok(());
+        if let Some(warn) = warn {
+            self.game_provider
+                .broadcast_stop_countdown(&rcon_host, &rcon_port, &rcon_password, warn)
+                .map_err(game_error("Failed to warn players"))?;
         }
 
-        log::info!("The game is running, attempting to reload datapacks");
-
-        let (rcon_host, rcon_port) = self
-            .container_provider
-            .get_container_rcon_address(&config)
-            .or_else(|_| {
-                log::error!("Failed to get rcon address");
-                return Err(());
-            })?;
-
+        log::info!("Saving the world and stopping the game");
         self.game_provider
             .run_rcon_commands(
                 &rcon_host,
                 &rcon_port,
-                vec!["reload".to_owned(), "datapack list".to_owned()],
+                &rcon_password,
+                vec!["save-all flush".to_owned(), "stop".to_owned()],
             )
-            .or_else(|_| {
-                log::error!("Failed to run rcon commands");
-                return Err(());
-            })?
-            .iter()
-            .for_each(|response| println!("{:?}", response));
+            .map_err(game_error("Failed to run rcon commands"))?;
 
         Ok(())
     }
 
-    pub fn logs(&self, config: &config::Config) -> Result<(), ()> {
-        self.container_provider.display_container_logs(&config)
+    fn get_container_status(
+        &self,
+        config: &config::Config,
+    ) -> Result<ContainerState, CommandError> {
+        self.container_provider
+            .get_container_status(config)
+            .map_err(container_error("Failed to get container status"))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use mockall::predicate::eq;
+    fn get_rcon_details(
+        &self,
+        config: &config::Config,
+    ) -> Result<(String, String, String), CommandError> {
+        let (rcon_host, rcon_port) = self
+            .container_provider
+            .get_container_rcon_address(config)
+            .map_err(container_error("Failed to get rcon address"))?;
 
-    use super::*;
-    use crate::providers::container::MockContainerProvider;
-    use crate::providers::file::MockFileProvider;
-    use crate::providers::game::MockGameProvider;
+        let rcon_password = self
+            .file_provider
+            .get_rcon_password(config)
+            .map_err(file_error("Failed to get the rcon password"))?;
 
-    fn get_subcommands() -> SubCommands<MockContainerProvider, MockFileProvider, MockGameProvider> {
-        SubCommands {
-            container_provider: MockContainerProvider::new(),
-            file_provider: MockFileProvider::new(),
-            game_provider: MockGameProvider::new(),
-        }
+        Ok((rcon_host, rcon_port, rcon_password))
     }
 
-    fn get_config() -> config::Config {
-        config::Config {
-            name: "name".to_owned(),
-            host: "0.0.0.0".to_owned(),
-            port: 25565,
-            server: config::Server {
-                version: "1.17.1".to_owned(),
-                server_type: config::ServerType::Vanilla,
-                ..std::default::Default::default()
-            },
-            ..std::default::Default::default()
+    // Returns None when the container can't be compared to the config, which is only a warning
+    // so containers created without a config hash can still be managed
+    fn has_config_drift(&self, config: &config::Config) -> Result<Option<bool>, CommandError> {
+        let data_path = self
+            .file_provider
+            .get_data_path()
+            .map_err(file_error("Failed to get the data path"))?;
+
+        let drift = self
+            .container_provider
+            .has_config_drift(config, &data_path)
+            .map_err(container_error("Failed to compare the container config"))?;
+        if drift.is_none() {
+            log::warn!(
+                "Unable to tell whether the config has changed, the container has no config hash"
+            );
         }
+        Ok(drift)
     }
 
-    #[test]
-    fn test_create() {
-        let mut subcommands = get_subcommands();
-        let config = get_config();
-        let path = std::path::PathBuf::new();
-        let path_clone = path.clone();
-
-        subcommands
-            .container_provider
-            .expect_get_container_status()
-            .with(eq(config.clone()))
-            .returning(|_| Ok(ContainerState::NotFound));
+    fn ping_server(
+        &self,
+        config: &config::Config,
+        address: Option<&ServerAddress>,
+    ) -> Result<ServerStatus, CommandError> {
+        let (host, port) = match address {
+            Some(address) => (address.host.as_str(), address.port),
+            None => server_address(config, config.port)?,
+        };
 
-        subcommands
-            .file_provider
-            .expect_create_data_folder()
-            .times(1)
-            .return_const(Ok(()));
+        self.game_provider
+            .ping_server(host, port)
+            .map_err(game_error("Failed to ping the server"))
+    }
 
-        subcommands
-            .file_provider
-            .expect_get_data_path()
-            .times(1)
-            .return_const(Ok(path));
+    fn get_status(&self, config: &config::Config) -> Result<StatusReport, CommandError> {
+        let mut state = self.get_container_status(config)?;
+        let exists = state != ContainerState::NotFound;
 
-        subcommands
+        let image = self
             .container_provider
-            .expect_create_container()
-            .with(eq(config.clone()), eq(path_clone))
-            .returning(|_, _| Ok(()));
+            .get_container_image(config)
+            .map_err(container_error("Failed to get the container image"))?;
+        // The container keeps the loader it was created with until it is recreated
+        let server_type = self
+            .container_provider
+            .get_container_server_type(config)
+            .map_err(container_error("Failed to get the container server type"))?
+            .unwrap_or_else(|| config.server.server_type.to_string());
+        let config_changed = if exists {
+            self.has_config_drift(config)?
+        } else {
+            Some(false)
+        };
+        let ports = if exists {
+            self.container_provider
+                .get_container_ports(config)
+                .map_err(container_error("Failed to get the container ports"))?
+        } else {
+            vec![]
+        };
+
+        let mut started_at = None;
+        let mut rcon_address = None;
+        let mut players = None;
+        let mut server = None;
+        if let ContainerState::Running(game_state) = &state {
+            server = self
+                .ping_server(config, None)
+                .map_err(|err| log::debug!("{}", err))
+                .ok();
+            // Without a healthcheck the game state is unknown, but answering a ping shows it is up
+            if *game_state == GameState::Unknown && server.is_some() {
+                state = ContainerState::Running(GameState::Running);
+            }
+        }
+        if let ContainerState::Running(game_state) = &state {
+            started_at = self
+                .container_provider
+                .get_container_started_at(config)
+                .map_err(container_error("Failed to get the container start time"))?;
+            rcon_address = self
+                .container_provider
+                .get_container_rcon_address(config)
+                .ok()
+                .map(|(host, port)| format!("{}:{}", host, port));
+            if let Some(server) = &server {
+                players = Some(PlayerCount {
+                    online: server.online,
+                    max: server.max,
+                });
+            } else if *game_state == GameState::Running {
+                players = self
+                    .get_rcon_details(config)
+                    .and_then(|(rcon_host, rcon_port, rcon_password)| {
+                        self.game_provider
+                            .get_player_count(&rcon_host, &rcon_port, &rcon_password)
+                            .map_err(game_error("Failed to get the player count"))
+                    })
+                    .unwrap_or_else(|err| {
+                        log::debug!("{}", err);
+                        None
+                    });
+            }
+        }
 
-        assert_eq!(Ok(()), subcommands.create(&config));
+        Ok(StatusReport {
+            name: config.name.to_owned(),
+            container: container_state_name(&state),
+            game: match &state {
+                ContainerState::Running(game_state) => Some(game_state_name(game_state)),
+                _ => None,
+            },
+            image,
+            uptime_seconds: started_at
+                .map(|started_at| (chrono::Utc::now() - started_at).num_seconds().max(0)),
+            started_at: started_at.map(|started_at| started_at.to_rfc3339()),
+            ports,
+            rcon_address,
+            players,
+            server,
+            config_changed,
+            server_type,
+            version: config.server.version.to_owned(),
+            state,
+        })
     }
 
-    #[test]
-    fn test_destroy() {
-        let mut subcommands = get_subcommands();
-        let config = get_config();
+    // Returns an exit code for the state of the container and game so scripts can branch on it
+    pub fn status(
+        &self,
+        config: &config::Config,
+        format: OutputFormat,
+    ) -> Result<i32, CommandError> {
+        let report = self.get_status(config)?;
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report)
+                    .expect("The status report is always serializable")
+            );
+            return Ok(status_exit_code(&report.state));
+        }
 
-        subcommands
-            .container_provider
-            .expect_get_container_status()
-            .with(eq(config.clone()))
-            .returning(|_| Ok(ContainerState::Stopped));
+        match &report.state {
+            ContainerState::Unknown => {
+                log::info!("The state of the container is unkown");
+            }
+            ContainerState::NotFound => {
+                log::info!("The container does not exist");
+            }
+            ContainerState::Running(game_state) => {
+                log::info!("The container is currently running");
+                match game_state {
+                    GameState::Unknown => log::info!("The state of the server is unknown"),
+                    GameState::Starting => log::info!("The server is starting"),
+                    GameState::Running => log::info!("The server is running"),
+                    GameState::Unhealthy => log::info!("The server is unhealthy"),
+                };
+            }
+            ContainerState::Stopped => {
+                log::info!("The container is currently stopped");
+            }
+        };
+        if let Some(uptime_seconds) = report.uptime_seconds {
+            log::info!(
+                "The container has been up for {}",
+                format_uptime(uptime_seconds)
+            );
+        }
+        if let Some(server) = &report.server {
+            log::info!(
+                "The server is running Minecraft {} with a latency of {}ms",
+                server.version,
+                server.latency_ms
+            );
+            log::info!("The MOTD is \"{}\"", server.motd);
+        }
+        if let Some(players) = &report.players {
+            log::info!(
+                "There are {} of a max of {} players online",
+                players.online,
+                players.max
+            );
+        }
+        if let Some(image) = &report.image {
+            log::info!("The container image is {}", image);
+        }
+        for port in &report.ports {
+            log::info!(
+                "Port {} is published on {}:{}",
+                port.container,
+                port.host_ip.as_deref().unwrap_or("0.0.0.0"),
+                port.host_port.as_deref().unwrap_or("?")
+            );
+        }
+        if report.config_changed == Some(true) {
+            log::warn!(
+                "The config has changed since the container was created, run up --recreate to apply it"
+            );
+        }
+        log::info!(
+            "The server type is {} for Minecraft {}",
+            config.server.server_type,
+            config.server.version
+        );
+        Ok(status_exit_code(&report.state))
+    }
 
-        subcommands
-            .container_provider
-            .expect_delete_container()
-            .with(eq(config.clone()))
-            .times(1)
-            .returning(|_| Ok(()));
+    pub fn ping(
+        &self,
+        config: &config::Config,
+        address: Option<&ServerAddress>,
+        format: OutputFormat,
+    ) -> Result<(), CommandError> {
+        let server = self.ping_server(config, address)?;
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&server)
+                    .expect("The server status is always serializable")
+            );
+            return Ok(());
+        }
 
-        assert_eq!(Ok(()), subcommands.destroy(&config));
+        log::info!(
+            "The server is running Minecraft {} (protocol {})",
+            server.version,
+            server.protocol
+        );
+        log::info!("The MOTD is \"{}\"", server.motd);
+        log::info!(
+            "There are {} of a max of {} players online",
+            server.online,
+            server.max
+        );
+        log::info!("The latency is {}ms", server.latency_ms);
+        Ok(())
     }
 
-    #[test]
-    fn test_start() {
-        let mut subcommands = get_subcommands();
-        let config = get_config();
+    pub fn players(
+        &self,
+        config: &config::Config,
+        format: OutputFormat,
+    ) -> Result<(), CommandError> {
+        if !config.query.enabled {
+            return Err(invalid_state(
+                "Query is not enabled, set query.enabled in the config and recreate the container",
+            ));
+        }
+        if !matches!(
+            self.get_container_status(config)?,
+            ContainerState::Running(_)
+        ) {
+            return Err(invalid_state("Container is not running"));
+        }
 
-        subcommands
-            .container_provider
-            .expect_get_container_status()
-            .with(eq(config.clone()))
-            .returning(|_| Ok(ContainerState::Stopped));
+        let (host, port) = server_address(config, config.query_port())?;
+        let status = self
+            .game_provider
+            .query_server(host, port)
+            .map_err(game_error("Failed to query the server"))?;
+        if format == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&status)
+                    .expect("The query status is always serializable")
+            );
+            return Ok(());
+        }
 
-        subcommands
-            .file_provider
-            .expect_create_data_folder()
-            .times(1)
-            .returning(|| Ok(()));
+        log::info!(
+            "There are {} of a max of {} players online",
+            status.online,
+            status.max
+        );
+        for player in status.players {
+            println!("{}", player);
+        }
+        Ok(())
+    }
 
-        subcommands
-            .file_provider
-            .expect_create_and_populate_server_properties()
-            .with(eq(config.clone()))
-            .times(1)
-            .returning(|_| Ok(()));
+    pub fn console(&self, config: &config::Config) -> Result<(), CommandError> {
+        if self.get_container_status(config)? != ContainerState::Running(GameState::Running) {
+            return Err(invalid_state("Game server is not running"));
+        }
 
-        subcommands
-            .container_provider
-            .expect_start_container()
-            .with(eq(config.clone()))
-            .times(1)
-            .returning(|_| Ok(()));
+        let (rcon_host, rcon_port, rcon_password) = self.get_rcon_details(config)?;
+
+        self.game_provider
+            .run_interactive_rcon_session(config, &rcon_host, &rcon_port, &rcon_password)
+            .map_err(game_error("Failed to establish interactive rcon session"))?;
 
-        assert_eq!(Ok(()), subcommands.start(&config));
+        Ok(())
     }
 
-    #[test]
-    fn test_stop() {
-        let mut subcommands = get_subcommands();
-        let config = get_config();
+    pub fn exec(
+        &self,
+        config: &config::Config,
+        commands: &[String],
+        input: Option<&str>,
+    ) -> Result<(), CommandError> {
+        let commands = self.read_commands(commands, input)?;
+        if commands.is_empty() {
+            log::warn!("No commands to run");
+            return Ok(());
+        }
 
-        subcommands
-            .container_provider
-            .expect_get_container_status()
-            .with(eq(config.clone()))
-            .returning(|_| Ok(ContainerState::Running(GameState::Unknown)));
+        if self.get_container_status(config)? != ContainerState::Running(GameState::Running) {
+            return Err(invalid_state("Game server is not running"));
+        }
 
-        subcommands
-            .container_provider
-            .expect_stop_container()
-            .with(eq(config.clone()))
-            .times(1)
-            .returning(|_| Ok(()));
+        let (rcon_host, rcon_port, rcon_password) = self.get_rcon_details(config)?;
 
-        assert_eq!(Ok(()), subcommands.stop(&config));
+        let responses = self
+            .game_provider
+            .exec_rcon_commands(&rcon_host, &rcon_port, &rcon_password, &commands)
+            .map_err(game_error("Failed to establish rcon connection"))?;
+
+        for (command, response) in commands.iter().zip(responses) {
+            match response {
+                Ok(response) => println!("{}", response.trim_end()),
+                Err(err) => {
+                    return Err(game_error(&format!(
+                        "Failed to run the command: {}",
+                        command
+                    ))(err));
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    #[test]
-    fn test_console() {
-        let mut subcommands = get_subcommands();
+    // Commands given as arguments are run as they are, while those read from a file or stdin
+    // skip blank lines and # comments
+    fn read_commands(
+        &self,
+        commands: &[String],
+        input: Option<&str>,
+    ) -> Result<Vec<String>, CommandError> {
+        if !commands.is_empty() {
+            return Ok(commands.to_vec());
+        }
+
+        let contents = match input {
+            Some(input) => self
+                .file_provider
+                .read_commands_file(std::path::Path::new(input))
+                .map_err(file_error(&format!(
+                    "Unable to read commands from {}",
+                    input
+                )))?,
+            None => self
+                .game_provider
+                .read_piped_input()
+                .map_err(game_error("Unable to read commands from stdin"))?
+                .ok_or_else(|| {
+                    invalid_state(
+                        "No commands given, pass them as arguments, with --input or on stdin",
+                    )
+                })?,
+        };
+
+        Ok(contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect())
+    }
+
+    pub fn sync_datapacks(&self, config: &config::Config) -> Result<(), CommandError> {
+        self.file_provider
+            .sync_datapacks(config)
+            .map_err(file_error("Failed to sync datapacks"))?;
+
+        if self.get_container_status(config)? != ContainerState::Running(GameState::Running) {
+            return Ok(());
+        }
+
+        log::info!("The game is running, attempting to reload datapacks");
+
+        let (rcon_host, rcon_port, rcon_password) = self.get_rcon_details(config)?;
+
+        self.game_provider
+            .run_rcon_commands(
+                &rcon_host,
+                &rcon_port,
+                &rcon_password,
+                vec!["reload".to_owned(), "datapack list".to_owned()],
+            )
+            .map_err(game_error("Failed to run rcon commands"))?
+            .iter()
+            .for_each(|response| println!("{:?}", response));
+
+        Ok(())
+    }
+
+    pub fn sync_mods(&self, config: &config::Config) -> Result<(), CommandError> {
+        if !config.server.server_type.supports_mods() {
+            log::warn!(
+                "The server type {} does not load mods",
+                config.server.server_type
+            );
+        }
+
+        self.file_provider
+            .sync_mods(config)
+            .map_err(file_error("Failed to sync mods"))?;
+
+        if let ContainerState::Running(_) = self.get_container_status(config)? {
+            log::warn!("The server must be restarted for mod changes to take effect");
+        }
+
+        Ok(())
+    }
+
+    pub fn sync_plugins(&self, config: &config::Config) -> Result<(), CommandError> {
+        if !config.server.server_type.supports_plugins() {
+            log::warn!(
+                "The server type {} does not load plugins",
+                config.server.server_type
+            );
+        }
+
+        self.file_provider
+            .sync_plugins(config)
+            .map_err(file_error("Failed to sync plugins"))?;
+
+        if let ContainerState::Running(_) = self.get_container_status(config)? {
+            log::warn!("The server must be restarted for plugin changes to take effect");
+        }
+
+        Ok(())
+    }
+
+    pub fn backup(&self, config: &config::Config) -> Result<(), CommandError> {
+        let rcon_details = match self.get_container_status(config)? {
+            ContainerState::Running(GameState::Running) => Some(self.get_rcon_details(config)?),
+            ContainerState::Running(_) => {
+                log::warn!("The game is not ready, the backup may be inconsistent");
+                None
+            }
+            _ => None,
+        };
+
+        let backup_result = match &rcon_details {
+            Some((rcon_host, rcon_port, rcon_password)) => {
+                log::info!("The game is running, disabling autosave");
+                self.game_provider
+                    .run_rcon_commands(
+                        rcon_host,
+                        rcon_port,
+                        rcon_password,
+                        vec!["save-off".to_owned(), "save-all flush".to_owned()],
+                    )
+                    .map_err(game_error("Failed to save the world"))
+                    .and_then(|_| {
+                        self.file_provider
+                            .create_backup(config)
+                            .map_err(file_error("Failed to create the backup"))
+                    })
+            }
+            None => self
+                .file_provider
+                .create_backup(config)
+                .map_err(file_error("Failed to create the backup")),
+        };
+
+        if let Some((rcon_host, rcon_port, rcon_password)) = &rcon_details {
+            log::info!("Re-enabling autosave");
+            self.game_provider
+                .run_rcon_commands(
+                    rcon_host,
+                    rcon_port,
+                    rcon_password,
+                    vec!["save-on".to_owned()],
+                )
+                .map_err(game_error(
+                    "Failed to re-enable autosave, run \"save-on\" from the console",
+                ))?;
+        }
+
+        let backup_path = backup_result?;
+        log::info!("Created the backup {}", backup_path.display());
+        Ok(())
+    }
+
+    pub fn list_backups(&self, config: &config::Config) -> Result<(), CommandError> {
+        let backups = self
+            .file_provider
+            .list_backups(config)
+            .map_err(file_error("Failed to list the backups"))?;
+
+        if backups.is_empty() {
+            log::info!("There are no backups");
+        }
+
+        for backup in backups {
+            println!(
+                "{}  {}  {}",
+                backup.id,
+                backup.created.format("%Y-%m-%d %H:%M:%S"),
+                backup.path.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn prune_backups(&self, config: &config::Config) -> Result<(), CommandError> {
+        let pruned_backups = self
+            .file_provider
+            .prune_backups(config)
+            .map_err(file_error("Failed to prune the backups"))?;
+
+        for backup in pruned_backups.iter() {
+            log::info!("Deleted the backup {}", backup.id);
+        }
+        log::info!("Pruned {} backups", pruned_backups.len());
+
+        Ok(())
+    }
+
+    pub fn restore_backup(&self, config: &config::Config, id: &str) -> Result<(), CommandError> {
+        if !matches!(
+            self.get_container_status(config)?,
+            ContainerState::Stopped | ContainerState::NotFound
+        ) {
+            return Err(invalid_state(
+                "The container must be stopped before restoring a backup",
+            ));
+        }
+
+        let backup = self
+            .file_provider
+            .restore_backup(config, id)
+            .map_err(file_error("Failed to restore the backup"))?;
+
+        log::info!("Restored the backup {}", backup.id);
+        Ok(())
+    }
+
+    pub fn logs(&self, config: &config::Config, logs_args: &LogsArgs) -> Result<(), CommandError> {
+        self.container_provider
+            .display_container_logs(config, log_options(logs_args, chrono::Utc::now()))
+            .map_err(container_error("Failed to display the logs"))
+    }
+
+    pub fn events(
+        &self,
+        config: &config::Config,
+        events_args: &EventsArgs,
+    ) -> Result<(), CommandError> {
+        let options = events_log_options(events_args, chrono::Utc::now());
+        for line in self
+            .container_provider
+            .stream_container_logs(config, options)
+        {
+            let line = line.map_err(container_error("Failed to read the logs"))?;
+            let event = match events::parse_log_line(&line.text) {
+                Some(event) => event,
+                None => continue,
+            };
+
+            match (events_args.format, line.timestamp) {
+                (OutputFormat::Json, timestamp) => println!(
+                    "{}",
+                    serde_json::to_string(&TimedEvent::new(timestamp, event))
+                        .expect("Events are always serializable")
+                ),
+                (OutputFormat::Text, Some(timestamp)) => {
+                    println!("{} {}", timestamp.format("%Y-%m-%d %H:%M:%S"), event)
+                }
+                (OutputFormat::Text, None) => println!("{}", event),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn watch(&self, config: &config::Config) -> Result<(), CommandError> {
+        if config.notifications.webhooks.is_empty() {
+            return Err(invalid_state(
+                "No webhooks are configured in the notifications section",
+            ));
+        }
+
+        log::info!("Watching the server {} for events", config.name);
+        let mut state = WatchState {
+            since: chrono::Utc::now(),
+            running: false,
+            stop_logged: false,
+        };
+        loop {
+            self.watch_container(config, &mut state);
+            (self.sleep)(Duration::from_secs(WATCH_POLL_INTERVAL));
+        }
+    }
+
+    // Follows the log while the container is running, and reports a crash when the container
+    // stops without the server logging that it was stopping or crashed
+    fn watch_container(&self, config: &config::Config, state: &mut WatchState) {
+        match self.get_container_status(config) {
+            Ok(ContainerState::Running(_)) => {
+                if !state.running {
+                    state.running = true;
+                    state.stop_logged = false;
+                }
+                self.watch_logs(config, state);
+                log::debug!("The log stream ended, waiting for the container");
+            }
+            Ok(container_state @ (ContainerState::Stopped | ContainerState::NotFound)) => {
+                if state.running && !state.stop_logged {
+                    let event = GameEvent::Crash { report: None };
+                    log::warn!("The container stopped unexpectedly");
+                    self.notify(config, Notification::Game(event));
+                }
+                state.running = false;
+                log::debug!("Waiting for the container, it is {:?}", container_state);
+            }
+            Ok(container_state) => {
+                log::debug!("Waiting for the container, it is {:?}", container_state)
+            }
+            Err(err) => log::warn!("{}", err),
+        }
+    }
+
+    // Sends notifications for the events logged after the state's time until the log stream
+    // ends, moving the state to the time of the last line
+    fn watch_logs(&self, config: &config::Config, state: &mut WatchState) {
+        let options = providers::backends::docker::LogOptions {
+            follow: true,
+            since: state.since.timestamp(),
+            timestamps: true,
+            ..std::default::Default::default()
+        };
+
+        for line in self
+            .container_provider
+            .stream_container_logs(config, options)
+        {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    log::warn!("Failed to read the logs: {}", err);
+                    break;
+                }
+            };
+
+            // The runtime only filters the log to the second, so lines already seen before
+            // reconnecting are sent again
+            match line.timestamp {
+                Some(timestamp) if timestamp <= state.since => continue,
+                Some(timestamp) => state.since = timestamp,
+                None => (),
+            }
+
+            if let Some(event) = events::parse_log_line(&line.text) {
+                if matches!(event, GameEvent::Stopping | GameEvent::Crash { .. }) {
+                    state.stop_logged = true;
+                }
+                log::info!("{}", event);
+                self.notify(config, Notification::Game(event));
+            }
+        }
+    }
+
+    // Failing to notify is only a warning, so it never fails the command that sent it
+    fn notify(&self, config: &config::Config, notification: Notification) {
+        let time = chrono::Utc::now();
+        for webhook in config
+            .notifications
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.is_subscribed(notification.kind()))
+        {
+            if let Err(err) =
+                self.notification_provider
+                    .send_notification(config, webhook, &notification, time)
+            {
+                log::warn!(
+                    "Failed to send the {} notification: {}",
+                    notification.kind(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::providers::backends::ping::PingError;
+    use crate::providers::backends::query::QueryStatus;
+    use crate::providers::backends::rcon::RconError;
+    use crate::providers::container::MockContainerProvider;
+    use crate::providers::file::MockFileProvider;
+    use crate::providers::game::MockGameProvider;
+    use crate::providers::notification::MockNotificationProvider;
+
+    fn get_file_error() -> FileError {
+        FileError::WorldNotFound("world".to_owned())
+    }
+
+    fn get_game_error() -> GameError {
+        GameError::Rcon(RconError {
+            message: "error".to_owned(),
+            source: rcon::Error::Auth,
+        })
+    }
+
+    fn get_ping_error() -> GameError {
+        GameError::Ping(PingError {
+            message: "error".to_owned(),
+            source: std::io::Error::from(std::io::ErrorKind::ConnectionRefused),
+        })
+    }
+
+    fn get_server_status() -> ServerStatus {
+        ServerStatus {
+            version: "1.17.1".to_owned(),
+            protocol: 756,
+            motd: "A Minecraft Server".to_owned(),
+            online: 3,
+            max: 10,
+            latency_ms: 2,
+        }
+    }
+
+    fn get_subcommands() -> SubCommands<
+        MockContainerProvider,
+        MockFileProvider,
+        MockGameProvider,
+        MockNotificationProvider,
+    > {
+        SubCommands {
+            container_provider: MockContainerProvider::new(),
+            file_provider: MockFileProvider::new(),
+            game_provider: MockGameProvider::new(),
+            notification_provider: MockNotificationProvider::new(),
+            sleep: |_| {},
+        }
+    }
+
+    fn get_config() -> config::Config {
+        config::Config {
+            name: "name".to_owned(),
+            host: "0.0.0.0".to_owned(),
+            port: 25565,
+            server: config::Server {
+                version: "1.17.1".to_owned(),
+                server_type: config::ServerType::Vanilla,
+                ..std::default::Default::default()
+            },
+            ..std::default::Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(3, invalid_state("state").exit_code());
+        assert_eq!(5, file_error("file")(get_file_error()).exit_code());
+        assert_eq!(6, game_error("game")(get_game_error()).exit_code());
+        assert_eq!(7, CommandError::Timeout("timeout".to_owned()).exit_code());
+    }
+
+    #[test]
+    fn test_create() {
+        let mut subcommands = get_subcommands();
+        let config = get_config();
+        let path = std::path::PathBuf::new();
+        let path_clone = path.clone();
+
+        subcommands
+            .container_provider
+            .expect_get_container_status()
+            .with(eq(config.clone()))
+            .returning(|_| Ok(ContainerState::NotFound));
+
+        subcommands
+            .file_provider
+            .expect_create_data_folder()
+            .times(1)
+            .returning(|| Ok(()));
+
+        subcommands
+            .file_provider
+            .expect_get_data_path()
+            .times(1)
+            .return_once(move || Ok(path));
+
+        subcommands
+            .container_provider
+            .expect_create_container()
+            .with(eq(config.clone()), eq(path_clone))
+            .returning(|_, _| Ok(()));
+
+        assert!(subcommands.create(&config).is_ok());
+    }
+
+    #[test]
+    fn test_create_already_exists() {
+        let mut subcommands = get_subcommands();
+        let config = get_config();
+
+        subcommands
+            .container_provider
+            .expect_get_container_status()
+            .with(eq(config.clone()))
+            .returning(|_| Ok(ContainerState::Stopped));
+
+        subcommands
+            .file_provider
+            .expect_get_data_path()
+            .returning(|| Ok(std::path::PathBuf::new()));
+
+        subcommands
+            .container_provider
+            .expect_has_config_drift()
+            .with(eq(config.clone()), mockall::predicate::always())
+            .times(1)
+            .returning(|_, _| Ok(Some(true)));
+
+        subcommands
+            .container_provider
+            .expect_create_container()
+            .times(0);
+
+        assert!(subcommands.create(&config).is_ok());
+    }
+
+    mod test_up {
+        use super::*;
+        use mockall::Sequence;
+
+        fn expect_start(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+        ) {
+            subcommands
+                .file_provider
+                .expect_get_data_path()
+                .returning(|| Ok(std::path::PathBuf::new()));
+            subcommands
+                .file_provider
+                .expect_create_data_folder()
+                .returning(|| Ok(()));
+            subcommands
+                .file_provider
+                .expect_create_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+            subcommands
+                .file_provider
+                .expect_create_and_populate_server_properties()
+                .returning(|_, _| Ok(()));
+            subcommands
+                .container_provider
+                .expect_start_container()
+                .times(1)
+                .returning(|_| Ok(()));
+        }
+
+        #[test]
+        fn recreate_with_drift() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+            let mut sequence = Sequence::new();
+
+            // Checked by up, stop and destroy, then create and start.
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(3)
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(ContainerState::Stopped));
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(ContainerState::NotFound));
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(ContainerState::Stopped));
+
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .times(1)
+                .returning(|_, _| Ok(Some(true)));
+            subcommands
+                .container_provider
+                .expect_delete_container()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(()));
+            subcommands
+                .container_provider
+                .expect_create_container()
+                .times(1)
+                .returning(|_, _| Ok(()));
+            expect_start(&mut subcommands);
+
+            assert!(subcommands.up(&config, true, None, 60, None).is_ok());
+        }
+
+        #[test]
+        fn recreate_without_drift() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Stopped));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .returning(|_, _| Ok(Some(false)));
+            subcommands
+                .container_provider
+                .expect_delete_container()
+                .times(0);
+            subcommands
+                .container_provider
+                .expect_create_container()
+                .times(0);
+            expect_start(&mut subcommands);
+
+            assert!(subcommands.up(&config, true, None, 60, None).is_ok());
+        }
+
+        #[test]
+        fn recreate_with_unknown_drift() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Stopped));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .returning(|_, _| Ok(None));
+            subcommands
+                .container_provider
+                .expect_delete_container()
+                .times(0);
+            subcommands
+                .container_provider
+                .expect_create_container()
+                .times(0);
+            expect_start(&mut subcommands);
+
+            assert!(subcommands.up(&config, true, None, 60, None).is_ok());
+        }
+
+        #[test]
+        fn drift_without_recreate() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Stopped));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .returning(|_, _| Ok(Some(true)));
+            subcommands
+                .container_provider
+                .expect_delete_container()
+                .times(0);
+            subcommands
+                .container_provider
+                .expect_create_container()
+                .times(0);
+            expect_start(&mut subcommands);
+
+            assert!(subcommands.up(&config, false, None, 60, None).is_ok());
+        }
+    }
+
+    mod test_status {
+        use super::*;
+
+        #[test]
+        fn running() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+            let started_at = chrono::Utc::now() - chrono::Duration::seconds(90);
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+            subcommands
+                .container_provider
+                .expect_get_container_image()
+                .returning(|_| {
+                    Ok(Some(
+                        "docker.io/itzg/minecraft-server@sha256:abc".to_owned(),
+                    ))
+                });
+            subcommands
+                .container_provider
+                .expect_get_container_server_type()
+                .returning(|_| Ok(Some("Forge 37.0.103".to_owned())));
+            subcommands
+                .file_provider
+                .expect_get_data_path()
+                .returning(|| Ok(std::path::PathBuf::new()));
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .returning(|_, _| Ok(Some(false)));
+            subcommands
+                .container_provider
+                .expect_get_container_ports()
+                .returning(|_| {
+                    Ok(vec![PublishedPort {
+                        container: "25565/tcp".to_owned(),
+                        host_ip: Some("0.0.0.0".to_owned()),
+                        host_port: Some("25565".to_owned()),
+                    }])
+                });
+            subcommands
+                .container_provider
+                .expect_get_container_started_at()
+                .returning(move |_| Ok(Some(started_at)));
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .returning(|_| Ok(("127.0.0.1".to_owned(), "49153".to_owned())));
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .with(eq("127.0.0.1"), eq(25565))
+                .returning(|_, _| Err(get_ping_error()));
+            subcommands
+                .game_provider
+                .expect_get_player_count()
+                .with(eq("127.0.0.1"), eq("49153"), eq("password"))
+                .times(2)
+                .returning(|_, _, _| Ok(Some(PlayerCount { online: 2, max: 20 })));
+
+            let report = subcommands.get_status(&config).unwrap();
+            let uptime_seconds = report.uptime_seconds.unwrap();
+            assert!((90..100).contains(&uptime_seconds));
+            assert_eq!(
+                serde_json::json!({
+                    "name": "name",
+                    "container": "running",
+                    "game": "running",
+                    "image": "docker.io/itzg/minecraft-server@sha256:abc",
+                    "started_at": started_at.to_rfc3339(),
+                    "uptime_seconds": uptime_seconds,
+                    "ports": [{
+                        "container": "25565/tcp",
+                        "host_ip": "0.0.0.0",
+                        "host_port": "25565",
+                    }],
+                    "rcon_address": "127.0.0.1:49153",
+                    "players": { "online": 2, "max": 20 },
+                    "server": null,
+                    "config_changed": false,
+                    "server_type": "Forge 37.0.103",
+                    "version": "1.17.1",
+                }),
+                serde_json::to_value(&report).unwrap()
+            );
+            assert_eq!(0, subcommands.status(&config, OutputFormat::Json).unwrap());
+        }
+
+        #[test]
+        fn player_count_fails() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+            subcommands
+                .container_provider
+                .expect_get_container_image()
+                .returning(|_| Ok(None));
+            subcommands
+                .container_provider
+                .expect_get_container_server_type()
+                .returning(|_| Ok(Some("Vanilla".to_owned())));
+            subcommands
+                .file_provider
+                .expect_get_data_path()
+                .returning(|| Ok(std::path::PathBuf::new()));
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .returning(|_, _| Ok(Some(true)));
+            subcommands
+                .container_provider
+                .expect_get_container_ports()
+                .returning(|_| Ok(vec![]));
+            subcommands
+                .container_provider
+                .expect_get_container_started_at()
+                .returning(|_| Ok(None));
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .returning(|_| Ok(("127.0.0.1".to_owned(), "49153".to_owned())));
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .returning(|_, _| Err(get_ping_error()));
+            subcommands
+                .game_provider
+                .expect_get_player_count()
+                .returning(|_, _, _| Err(get_game_error()));
+
+            let report = subcommands.get_status(&config).unwrap();
+            assert_eq!(None, report.players);
+            assert_eq!(Some(true), report.config_changed);
+        }
+
+        #[test]
+        fn ping_without_healthcheck() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Unknown)));
+            subcommands
+                .container_provider
+                .expect_get_container_image()
+                .returning(|_| Ok(None));
+            subcommands
+                .container_provider
+                .expect_get_container_server_type()
+                .returning(|_| Ok(Some("Vanilla".to_owned())));
+            subcommands
+                .file_provider
+                .expect_get_data_path()
+                .returning(|| Ok(std::path::PathBuf::new()));
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .returning(|_, _| Ok(Some(false)));
+            subcommands
+                .container_provider
+                .expect_get_container_ports()
+                .returning(|_| Ok(vec![]));
+            subcommands
+                .container_provider
+                .expect_get_container_started_at()
+                .returning(|_| Ok(None));
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .returning(|_| Ok(("127.0.0.1".to_owned(), "49153".to_owned())));
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .with(eq("127.0.0.1"), eq(25565))
+                .returning(|_, _| Ok(get_server_status()));
+            subcommands.game_provider.expect_get_player_count().times(0);
+
+            let report = subcommands.get_status(&config).unwrap();
+            assert_eq!(ContainerState::Running(GameState::Running), report.state);
+            assert_eq!(Some(PlayerCount { online: 3, max: 10 }), report.players);
+            assert_eq!(Some(get_server_status()), report.server);
+        }
+
+        #[test]
+        fn not_found() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::NotFound));
+            subcommands
+                .container_provider
+                .expect_get_container_image()
+                .returning(|_| Ok(None));
+            subcommands
+                .container_provider
+                .expect_get_container_server_type()
+                .returning(|_| Ok(None));
+            subcommands
+                .container_provider
+                .expect_has_config_drift()
+                .times(0);
+            subcommands
+                .container_provider
+                .expect_get_container_ports()
+                .times(0);
+            subcommands.game_provider.expect_get_player_count().times(0);
+
+            let report = subcommands.get_status(&config).unwrap();
+            assert_eq!(
+                serde_json::json!({
+                    "name": "name",
+                    "container": "not_found",
+                    "game": null,
+                    "image": null,
+                    "started_at": null,
+                    "uptime_seconds": null,
+                    "ports": [],
+                    "rcon_address": null,
+                    "players": null,
+                    "server": null,
+                    "config_changed": false,
+                    "server_type": "Vanilla",
+                    "version": "1.17.1",
+                }),
+                serde_json::to_value(&report).unwrap()
+            );
+            assert_eq!(13, subcommands.status(&config, OutputFormat::Text).unwrap());
+        }
+
+        macro_rules! status_exit_code_tests {
+            ($($name:ident: $state:expr, $expected:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!($expected, status_exit_code(&$state));
+                }
+            )*
+            }
+        }
+
+        status_exit_code_tests! {
+            exit_code_running: ContainerState::Running(GameState::Running), 0,
+            exit_code_starting: ContainerState::Running(GameState::Starting), 10,
+            exit_code_game_unknown: ContainerState::Running(GameState::Unknown), 11,
+            exit_code_stopped: ContainerState::Stopped, 12,
+            exit_code_not_found: ContainerState::NotFound, 13,
+            exit_code_unknown: ContainerState::Unknown, 14,
+            exit_code_unhealthy: ContainerState::Running(GameState::Unhealthy), 15,
+        }
+
+        macro_rules! format_uptime_tests {
+            ($($name:ident: $seconds:expr, $expected:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!($expected, format_uptime($seconds));
+                }
+            )*
+            }
+        }
+
+        format_uptime_tests! {
+            format_uptime_seconds: 42, "42s",
+            format_uptime_minutes: 125, "2m 5s",
+            format_uptime_hours: 3 * 3600 + 120 + 5, "3h 2m",
+            format_uptime_days: 2 * 86400 + 3600 * 5 + 7, "2d 5h",
+        }
+    }
+
+    mod test_ping {
+        use super::*;
+
+        #[test]
+        fn configured_server() {
+            let mut subcommands = get_subcommands();
+            let mut config = get_config();
+            config.host = "192.168.1.10".to_owned();
+            config.port = 25566;
+
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .with(eq("192.168.1.10"), eq(25566))
+                .times(1)
+                .returning(|_, _| Ok(get_server_status()));
+
+            assert!(subcommands.ping(&config, None, OutputFormat::Text).is_ok());
+        }
+
+        #[test]
+        fn address() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+            let address = ServerAddress {
+                host: "mc.example.com".to_owned(),
+                port: 25570,
+            };
+
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .with(eq("mc.example.com"), eq(25570))
+                .times(1)
+                .returning(|_, _| Ok(get_server_status()));
+
+            assert!(subcommands
+                .ping(&config, Some(&address), OutputFormat::Json)
+                .is_ok());
+        }
+
+        #[test]
+        fn fails() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .with(eq("127.0.0.1"), eq(25565))
+                .times(1)
+                .returning(|_, _| Err(get_ping_error()));
+
+            let result = subcommands.ping(&config, None, OutputFormat::Text);
+            assert_eq!(6, result.unwrap_err().exit_code());
+        }
+    }
+
+    mod test_players {
+        use super::*;
+
+        #[test]
+        fn query_disabled() {
+            let subcommands = get_subcommands();
+            let config = get_config();
+
+            let result = subcommands.players(&config, OutputFormat::Text);
+            assert_eq!(3, result.unwrap_err().exit_code());
+        }
+
+        #[test]
+        fn container_not_running() {
+            let mut subcommands = get_subcommands();
+            let mut config = get_config();
+            config.query.enabled = true;
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Stopped));
+            subcommands.game_provider.expect_query_server().times(0);
+
+            let result = subcommands.players(&config, OutputFormat::Text);
+            assert_eq!(3, result.unwrap_err().exit_code());
+        }
+
+        #[test]
+        fn running() {
+            let mut subcommands = get_subcommands();
+            let mut config = get_config();
+            config.query.enabled = true;
+            config.query.port = Some(25570);
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+            subcommands
+                .game_provider
+                .expect_query_server()
+                .with(eq("127.0.0.1"), eq(25570))
+                .times(1)
+                .returning(|_, _| {
+                    Ok(QueryStatus {
+                        online: 2,
+                        max: 20,
+                        players: vec!["alice".to_owned(), "bob".to_owned()],
+                        ..std::default::Default::default()
+                    })
+                });
+
+            assert!(subcommands.players(&config, OutputFormat::Text).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_destroy() {
+        let mut subcommands = get_subcommands();
+        let config = get_config();
+
+        subcommands
+            .container_provider
+            .expect_get_container_status()
+            .with(eq(config.clone()))
+            .returning(|_| Ok(ContainerState::Stopped));
+
+        subcommands
+            .container_provider
+            .expect_delete_container()
+            .with(eq(config.clone()))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(subcommands.destroy(&config).is_ok());
+    }
+
+    #[test]
+    fn test_start() {
+        let mut subcommands = get_subcommands();
+        let config = get_config();
+
+        subcommands
+            .container_provider
+            .expect_get_container_status()
+            .with(eq(config.clone()))
+            .returning(|_| Ok(ContainerState::Stopped));
+
+        subcommands
+            .file_provider
+            .expect_create_data_folder()
+            .times(1)
+            .returning(|| Ok(()));
+
+        subcommands
+            .file_provider
+            .expect_create_rcon_password()
+            .with(eq(config.clone()))
+            .times(1)
+            .returning(|_| Ok("password".to_owned()));
+
+        subcommands
+            .file_provider
+            .expect_create_and_populate_server_properties()
+            .with(eq(config.clone()), eq("password"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        subcommands
+            .container_provider
+            .expect_start_container()
+            .with(eq(config.clone()))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(subcommands.start(&config, None).is_ok());
+    }
+
+    mod test_wait_for_server {
+        use super::*;
+        use crate::providers::container::LogLine;
+        use mockall::predicate::always;
+        use mockall::Sequence;
+
+        fn get_since() -> chrono::DateTime<chrono::Utc> {
+            "2024-06-01T12:00:00Z".parse().unwrap()
+        }
+
+        fn get_log_line(timestamp: &str, text: &str) -> LogLine {
+            LogLine {
+                timestamp: Some(timestamp.parse().unwrap()),
+                text: text.to_owned(),
+            }
+        }
+
+        fn expect_statuses(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+            statuses: Vec<ContainerState>,
+        ) {
+            subcommands
+                .container_provider
+                .expect_run_container_healthcheck()
+                .times(statuses.len())
+                .returning(|_| Ok(()));
+
+            let mut sequence = Sequence::new();
+            for status in statuses {
+                subcommands
+                    .container_provider
+                    .expect_get_container_status()
+                    .times(1)
+                    .in_sequence(&mut sequence)
+                    .return_once(move |_| Ok(status));
+            }
+        }
+
+        fn expect_no_new_logs(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+        ) {
+            subcommands
+                .container_provider
+                .expect_get_container_logs()
+                .with(always(), always(), eq(None))
+                .returning(|_, _, _| Ok(vec![]));
+        }
+
+        fn expect_log_tail(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+        ) {
+            subcommands
+                .container_provider
+                .expect_get_container_logs()
+                .with(always(), eq(None), eq(Some(WAIT_LOG_TAIL)))
+                .times(1)
+                .returning(|_, _, _| Ok(vec![get_log_line("2024-06-01T12:00:05Z", "crash")]));
+        }
+
+        #[test]
+        fn healthy() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            expect_statuses(
+                &mut subcommands,
+                vec![
+                    ContainerState::Running(GameState::Starting),
+                    ContainerState::Running(GameState::Starting),
+                    ContainerState::Running(GameState::Running),
+                ],
+            );
+
+            // Each poll only asks for the lines after the last one seen
+            let mut sequence = Sequence::new();
+            subcommands
+                .container_provider
+                .expect_get_container_logs()
+                .with(always(), eq(Some(get_since())), eq(None))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _| {
+                    Ok(vec![
+                        get_log_line("2024-06-01T12:00:01Z", "[init] Starting the server"),
+                        get_log_line("2024-06-01T12:00:02Z", "Loading libraries"),
+                    ])
+                });
+            subcommands
+                .container_provider
+                .expect_get_container_logs()
+                .with(
+                    always(),
+                    eq(Some("2024-06-01T12:00:02Z".parse().unwrap())),
+                    eq(None),
+                )
+                .times(2)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _| Ok(vec![]));
+
+            assert!(subcommands
+                .wait_for_server(&config, get_since(), 300)
+                .is_ok());
+        }
+
+        #[test]
+        fn answers_ping_without_healthcheck() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            expect_statuses(
+                &mut subcommands,
+                vec![ContainerState::Running(GameState::Unknown)],
+            );
+            expect_no_new_logs(&mut subcommands);
+            subcommands
+                .game_provider
+                .expect_ping_server()
+                .with(eq("127.0.0.1"), eq(25565))
+                .times(1)
+                .returning(|_, _| Ok(get_server_status()));
+
+            assert!(subcommands
+                .wait_for_server(&config, get_since(), 300)
+                .is_ok());
+        }
+
+        #[test]
+        fn unhealthy() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            expect_statuses(
+                &mut subcommands,
+                vec![
+                    ContainerState::Running(GameState::Starting),
+                    ContainerState::Running(GameState::Unhealthy),
+                ],
+            );
+            expect_no_new_logs(&mut subcommands);
+            expect_log_tail(&mut subcommands);
+
+            let err = subcommands
+                .wait_for_server(&config, get_since(), 300)
+                .unwrap_err();
+            assert!(matches!(err, CommandError::InvalidState(_)));
+        }
+
+        #[test]
+        fn container_exited() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            expect_statuses(&mut subcommands, vec![ContainerState::Stopped]);
+            expect_no_new_logs(&mut subcommands);
+            expect_log_tail(&mut subcommands);
+
+            let err = subcommands
+                .wait_for_server(&config, get_since(), 300)
+                .unwrap_err();
+            assert!(matches!(err, CommandError::InvalidState(_)));
+        }
+
+        #[test]
+        fn timeout() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            // Polled immediately and after each interval up to the timeout
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(3)
+                .returning(|_| Ok(ContainerState::Running(GameState::Starting)));
+            // A healthcheck that can't be run leaves the container starting
+            subcommands
+                .container_provider
+                .expect_run_container_healthcheck()
+                .times(3)
+                .returning(|_| Err(ContainerError::RconAddressNotFound("name".to_owned())));
+            expect_no_new_logs(&mut subcommands);
+            expect_log_tail(&mut subcommands);
+
+            let err = subcommands
+                .wait_for_server(&config, get_since(), 2 * WAIT_POLL_INTERVAL)
+                .unwrap_err();
+            assert_eq!(7, err.exit_code());
+        }
+
+        macro_rules! is_progress_log_line_tests {
+            ($($name:ident: $line:expr, $expected:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!($expected, is_progress_log_line($line));
+                }
+            )*
+            }
+        }
+
+        is_progress_log_line_tests! {
+            progress_init: "[init] Resolving type given VANILLA", true,
+            progress_done: "[12:00:00] [Server thread/INFO]: Done (5.123s)! For help, type \"help\"", true,
+            progress_warn: "[12:00:00] [Server thread/WARN]: Can't keep up!", true,
+            progress_paper_error: "[12:00:00 ERROR]: Could not load plugin", true,
+            progress_info: "[12:00:00] [Worker-Main-1/INFO]: Preparing spawn area: 42%", false,
+        }
+    }
+
+    mod test_stop {
+        use super::*;
+        use mockall::Sequence;
+
+        #[test]
+        fn game_not_running() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .with(eq(config.clone()))
+                .returning(|_| Ok(ContainerState::Running(GameState::Unknown)));
+
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .times(0);
+
+            subcommands
+                .container_provider
+                .expect_stop_container()
+                .with(eq(config.clone()), eq(60))
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(subcommands.stop(&config, None, 60).is_ok());
+        }
+
+        #[test]
+        fn container_not_running() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .with(eq(config.clone()))
+                .returning(|_| Ok(ContainerState::Stopped));
+
+            subcommands
+                .container_provider
+                .expect_stop_container()
+                .times(0);
+
+            assert!(subcommands.stop(&config, Some(60), 60).is_ok());
+        }
+
+        fn setup_running_game(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+        ) {
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .times(1)
+                .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+        }
+
+        #[test]
+        fn game_running() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+            let mut sequence = Sequence::new();
+            setup_running_game(&mut subcommands);
+
+            subcommands
+                .game_provider
+                .expect_broadcast_stop_countdown()
+                .with(eq("host"), eq("port"), eq("password"), eq(30))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _, _| Ok(()));
+
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .with(
+                    eq("host"),
+                    eq("port"),
+                    eq("password"),
+                    eq(vec!["save-all flush".to_owned(), "stop".to_owned()]),
+                )
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _, _| Ok(vec![]));
+
+            subcommands
+                .container_provider
+                .expect_stop_container()
+                .with(eq(config.clone()), eq(120))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(()));
+
+            assert!(subcommands.stop(&config, Some(30), 120).is_ok());
+        }
+
+        #[test]
+        fn game_running_without_warning() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+            setup_running_game(&mut subcommands);
+
+            subcommands
+                .game_provider
+                .expect_broadcast_stop_countdown()
+                .times(0);
+
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .times(1)
+                .returning(|_, _, _, _| Ok(vec![]));
+
+            subcommands
+                .container_provider
+                .expect_stop_container()
+                .with(eq(config.clone()), eq(60))
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(subcommands.stop(&config, None, 60).is_ok());
+        }
+
+        #[test]
+        fn rcon_fails() {
+            let mut subcommands = get_subcommands();
+            let config = get_config();
+            setup_running_game(&mut subcommands);
+
+            subcommands
+                .game_provider
+                .expect_broadcast_stop_countdown()
+                .times(1)
+                .returning(|_, _, _, _| Err(get_game_error()));
+
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .times(0);
+
+            subcommands
+                .container_provider
+                .expect_stop_container()
+                .with(eq(config.clone()), eq(60))
+                .times(1)
+                .returning(|_, _| Ok(()));
+
+            assert!(subcommands.stop(&config, Some(10), 60).is_ok());
+        }
+    }
+
+    mod test_exec {
+        use super::*;
+
+        fn setup(
+            responses: Vec<Result<String, GameError>>,
+        ) -> SubCommands<
+            MockContainerProvider,
+            MockFileProvider,
+            MockGameProvider,
+            MockNotificationProvider,
+        > {
+            let mut subcommands = get_subcommands();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .times(1)
+                .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+
+            subcommands
+                .game_provider
+                .expect_exec_rcon_commands()
+                .withf(|host, port, password, commands| {
+                    host == "host"
+                        && port == "port"
+                        && password == "password"
+                        && commands == ["whitelist add Steve".to_owned(), "op Alex".to_owned()]
+                })
+                .times(1)
+                .return_once(move |_, _, _, _| Ok(responses));
+
+            subcommands
+        }
+
+        fn get_commands() -> Vec<String> {
+            vec!["whitelist add Steve".to_owned(), "op Alex".to_owned()]
+        }
+
+        #[test]
+        fn no_commands() {
+            let mut subcommands = get_subcommands();
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(0);
+
+            subcommands
+                .game_provider
+                .expect_read_piped_input()
+                .times(1)
+                .returning(|| Ok(Some("\n  \n# Nothing to run\n".to_owned())));
+
+            assert!(subcommands.exec(&get_config(), &[], None).is_ok());
+        }
+
+        #[test]
+        fn no_commands_on_terminal() {
+            let mut subcommands = get_subcommands();
+            subcommands
+                .game_provider
+                .expect_read_piped_input()
+                .times(1)
+                .returning(|| Ok(None));
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(0);
+
+            assert_eq!(
+                3,
+                subcommands
+                    .exec(&get_config(), &[], None)
+                    .unwrap_err()
+                    .exit_code()
+            );
+        }
+
+        #[test]
+        fn commands_from_stdin() {
+            let mut subcommands = setup(vec![
+                Ok("Added Steve to the whitelist".to_owned()),
+                Ok("Made Alex a server operator".to_owned()),
+            ]);
+            subcommands
+                .game_provider
+                .expect_read_piped_input()
+                .times(1)
+                .returning(|| {
+                    Ok(Some(
+                        "# Set up the server\nwhitelist add Steve\n\n  op Alex  \n".to_owned(),
+                    ))
+                });
+
+            assert!(subcommands.exec(&get_config(), &[], None).is_ok());
+        }
+
+        #[test]
+        fn commands_from_input_file() {
+            let mut subcommands = setup(vec![
+                Ok("Added Steve to the whitelist".to_owned()),
+                Ok("Made Alex a server operator".to_owned()),
+            ]);
+            subcommands
+                .file_provider
+                .expect_read_commands_file()
+                .withf(|file_path| file_path == std::path::Path::new("commands.txt"))
+                .times(1)
+                .returning(|_| Ok("whitelist add Steve\n# Make Alex an op\nop Alex\n".to_owned()));
+            subcommands.game_provider.expect_read_piped_input().times(0);
+
+            assert!(subcommands
+                .exec(&get_config(), &[], Some("commands.txt"))
+                .is_ok());
+        }
+
+        #[test]
+        fn input_file_fails() {
+            let mut subcommands = get_subcommands();
+            subcommands
+                .file_provider
+                .expect_read_commands_file()
+                .returning(|_| Err(get_file_error()));
+
+            assert_eq!(
+                5,
+                subcommands
+                    .exec(&get_config(), &[], Some("commands.txt"))
+                    .unwrap_err()
+                    .exit_code()
+            );
+        }
+
+        #[test]
+        fn game_not_running() {
+            let mut subcommands = get_subcommands();
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Starting)));
+            subcommands
+                .game_provider
+                .expect_exec_rcon_commands()
+                .times(0);
+
+            assert!(subcommands
+                .exec(&get_config(), &get_commands(), None)
+                .is_err());
+        }
+
+        #[test]
+        fn connection_fails() {
+            let mut subcommands = get_subcommands();
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+            subcommands
+                .game_provider
+                .expect_exec_rcon_commands()
+                .returning(|_, _, _, _| Err(get_game_error()));
+
+            assert!(subcommands
+                .exec(&get_config(), &get_commands(), None)
+                .is_err());
+        }
+
+        #[test]
+        fn command_fails() {
+            let subcommands = setup(vec![
+                Ok("Added Steve to the whitelist".to_owned()),
+                Err(get_game_error()),
+            ]);
+
+            assert!(subcommands
+                .exec(&get_config(), &get_commands(), None)
+                .is_err());
+        }
+
+        #[test]
+        fn success() {
+            let subcommands = setup(vec![
+                Ok("Added Steve to the whitelist".to_owned()),
+                Ok("Made Alex a server operator".to_owned()),
+            ]);
+
+            assert!(subcommands
+                .exec(&get_config(), &get_commands(), None)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_console() {
+        let mut subcommands = get_subcommands();
+        let config = get_config();
+
+        subcommands
+            .container_provider
+            .expect_get_container_status()
+            .with(eq(config.clone()))
+            .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+
+        subcommands
+            .container_provider
+            .expect_get_container_rcon_address()
+            .with(eq(config.clone()))
+            .times(1)
+            .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+
+        subcommands
+            .file_provider
+            .expect_get_rcon_password()
+            .returning(|_| Ok("password".to_owned()));
+
+        subcommands
+            .game_provider
+            .expect_run_interactive_rcon_session()
+            .with(eq(config.clone()), eq("host"), eq("port"), eq("password"))
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        assert!(subcommands.console(&config).is_ok());
+    }
+
+    mod test_sync_datapacks {
+        use super::*;
+
+        #[test]
+        fn game_not_running() {
+            let config = get_config();
+            let mut subcommands = get_subcommands();
+
+            subcommands
+                .file_provider
+                .expect_sync_datapacks()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(ContainerState::Stopped));
+
+            assert!(subcommands.sync_datapacks(&config).is_ok());
+        }
+
+        #[test]
+        fn game_running() {
+            let config = get_config();
+            let mut subcommands = get_subcommands();
+
+            subcommands
+                .file_provider
+                .expect_sync_datapacks()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(()));
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .with(
+                    eq("host"),
+                    eq("port"),
+                    eq("password"),
+                    eq(vec!["reload".to_owned(), "datapack list".to_owned()]),
+                )
+                .times(1)
+                .returning(|_, _, _, _| Ok(vec!["response".to_owned()]));
+
+            assert!(subcommands.sync_datapacks(&config).is_ok());
+        }
+    }
+
+    macro_rules! sync_jars_tests {
+        ($($name:ident: $server_type:expr, $expect_sync:ident, $sync:ident, $container_state:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut config = get_config();
+                config.server.server_type = $server_type;
+                let mut subcommands = get_subcommands();
+
+                subcommands
+                    .file_provider
+                    .$expect_sync()
+                    .with(eq(config.clone()))
+                    .times(1)
+                    .returning(|_| Ok(()));
+
+                subcommands
+                    .container_provider
+                    .expect_get_container_status()
+                    .with(eq(config.clone()))
+                    .times(1)
+                    .returning(|_| Ok($container_state));
+
+                assert!(subcommands.$sync(&config).is_ok());
+            }
+        )*
+        }
+    }
+
+    sync_jars_tests! {
+        test_sync_mods_not_running: config::ServerType::Fabric { loader_version: "0.11.6".to_owned(), installer_version: None }, expect_sync_mods, sync_mods, ContainerState::Stopped;
+        test_sync_mods_running: config::ServerType::Forge { forge_version: "37.0.103".to_owned() }, expect_sync_mods, sync_mods, ContainerState::Running(GameState::Running);
+        test_sync_mods_unsupported_server_type: config::ServerType::Vanilla, expect_sync_mods, sync_mods, ContainerState::NotFound;
+        test_sync_plugins_not_running: config::ServerType::Paper { build: None }, expect_sync_plugins, sync_plugins, ContainerState::Stopped;
+        test_sync_plugins_running: config::ServerType::Spigot, expect_sync_plugins, sync_plugins, ContainerState::Running(GameState::Starting);
+    }
+
+    mod test_backup {
+        use super::*;
+        use mockall::Sequence;
+
+        fn expect_rcon_commands(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+            sequence: &mut Sequence,
+            commands: Vec<&'static str>,
+            result: Result<Vec<String>, GameError>,
+        ) {
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .with(
+                    eq("host"),
+                    eq("port"),
+                    eq("password"),
+                    eq(commands
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect::<Vec<String>>()),
+                )
+                .times(1)
+                .in_sequence(sequence)
+                .return_once(move |_, _, _, _| result);
+        }
+
+        fn setup_running_game(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+        ) {
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(1)
+                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+
+            subcommands
+                .container_provider
+                .expect_get_container_rcon_address()
+                .times(1)
+                .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+
+            subcommands
+                .file_provider
+                .expect_get_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+        }
+
+        #[test]
+        fn game_not_running() {
+            let config = get_config();
+            let mut subcommands = get_subcommands();
+
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(ContainerState::Stopped));
+
+            subcommands
+                .file_provider
+                .expect_create_backup()
+                .with(eq(config.clone()))
+                .times(1)
+                .returning(|_| Ok(std::path::PathBuf::from("backups/world.tar.gz")));
+
+            subcommands
+                .game_provider
+                .expect_run_rcon_commands()
+                .times(0);
+
+            assert!(subcommands.backup(&config).is_ok());
+        }
+
+        #[test]
+        fn game_running() {
+            let config = get_config();
+            let mut subcommands = get_subcommands();
+            let mut sequence = Sequence::new();
+            setup_running_game(&mut subcommands);
+
+            expect_rcon_commands(
+                &mut subcommands,
+                &mut sequence,
+                vec!["save-off", "save-all flush"],
+                Ok(vec![]),
+            );
+
+            subcommands
+                .file_provider
+                .expect_create_backup()
+                .with(eq(config.clone()))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(std::path::PathBuf::from("backups/world.tar.gz")));
+
+            expect_rcon_commands(&mut subcommands, &mut sequence, vec!["save-on"], Ok(vec![]));
+
+            assert!(subcommands.backup(&config).is_ok());
+        }
+
+        #[test]
+        fn backup_fails() {
+            let config = get_config();
+            let mut subcommands = get_subcommands();
+            let mut sequence = Sequence::new();
+            setup_running_game(&mut subcommands);
+
+            expect_rcon_commands(
+                &mut subcommands,
+                &mut sequence,
+                vec!["save-off", "save-all flush"],
+                Ok(vec![]),
+            );
+
+            subcommands
+                .file_provider
+                .expect_create_backup()
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_| Err(get_file_error()));
+
+            expect_rcon_commands(&mut subcommands, &mut sequence, vec!["save-on"], Ok(vec![]));
+
+            assert!(subcommands.backup(&config).is_err());
+        }
+
+        #[test]
+        fn save_fails() {
+            let config = get_config();
+            let mut subcommands = get_subcommands();
+            let mut sequence = Sequence::new();
+            setup_running_game(&mut subcommands);
+
+            expect_rcon_commands(
+                &mut subcommands,
+                &mut sequence,
+                vec!["save-off", "save-all flush"],
+                Err(get_game_error()),
+            );
+
+            subcommands.file_provider.expect_create_backup().times(0);
+
+            expect_rcon_commands(&mut subcommands, &mut sequence, vec!["save-on"], Ok(vec![]));
+
+            assert!(subcommands.backup(&config).is_err());
+        }
+    }
+
+    mod test_restore_backup {
+        use super::*;
+        use crate::providers::file::BackupArchive;
+
+        macro_rules! restore_backup_tests {
+            ($($name:ident: $container_state:expr, $expected_restores:expr, $expected_ok:expr;)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let config = get_config();
+                    let mut subcommands = get_subcommands();
+
+                    subcommands
+                        .container_provider
+                        .expect_get_container_status()
+                        .with(eq(config.clone()))
+                        .times(1)
+                        .returning(|_| Ok($container_state));
+
+                    subcommands
+                        .file_provider
+                        .expect_restore_backup()
+                        .with(eq(config.clone()), eq("20211016-120000"))
+                        .times($expected_restores)
+                        .returning(|_, id| {
+                            Ok(BackupArchive {
+                                id: id.to_owned(),
+                                path: std::path::PathBuf::from("backups"),
+                                created: chrono::NaiveDate::from_ymd(2021, 10, 16).and_hms(12, 0, 0),
+                            })
+                        });
+
+                    assert_eq!($expected_ok, subcommands.restore_backup(&config, "20211016-120000").is_ok());
+                }
+            )*
+            }
+        }
+
+        restore_backup_tests! {
+            stopped: ContainerState::Stopped, 1, true;
+            not_found: ContainerState::NotFound, 1, true;
+            running: ContainerState::Running(GameState::Running), 0, false;
+            starting: ContainerState::Running(GameState::Starting), 0, false;
+            unknown: ContainerState::Unknown, 0, false;
+        }
+    }
+
+    #[test]
+    fn test_prune_backups() {
         let config = get_config();
+        let mut subcommands = get_subcommands();
 
         subcommands
-            .container_provider
-            .expect_get_container_status()
+            .file_provider
+            .expect_prune_backups()
             .with(eq(config.clone()))
-            .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        assert!(subcommands.prune_backups(&config).is_ok());
+    }
+
+    #[test]
+    fn test_logs() {
+        let mut subcommands = get_subcommands();
+        let config = get_config();
 
         subcommands
             .container_provider
-            .expect_get_container_rcon_address()
-            .with(eq(config.clone()))
+            .expect_display_container_logs()
+            .with(
+                eq(config.clone()),
+                eq(providers::backends::docker::LogOptions {
+                    follow: false,
+                    tail: None,
+                    ..std::default::Default::default()
+                }),
+            )
             .times(1)
-            .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+            .returning(|_, _| Ok(()));
 
-        subcommands
-            .game_provider
-            .expect_run_interactive_rcon_session()
-            .with(eq(config.clone()), eq("host"), eq("port"))
-            .times(1)
-            .returning(|_, _, _| Ok(()));
+        assert!(subcommands
+            .logs(
+                &config,
+                &LogsArgs {
+                    no_follow: true,
+                    tail: Some(LogTail::All),
+                    since: None,
+                    until: None,
+                    timestamps: false,
+                }
+            )
+            .is_ok());
+    }
+
+    mod test_log_options {
+        use super::*;
+        use crate::args::LogTime;
+        use chrono::{TimeZone, Utc};
+
+        fn get_logs_args(tail: Option<LogTail>, since: Option<LogTime>) -> LogsArgs {
+            LogsArgs {
+                no_follow: true,
+                tail,
+                since,
+                until: None,
+                timestamps: false,
+            }
+        }
+
+        macro_rules! log_options_tests {
+            ($($name:ident: $tail:expr, $since:expr, $expected_tail:expr, $expected_since:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let now = Utc.timestamp(1717243200, 0);
+                    assert_eq!(
+                        providers::backends::docker::LogOptions {
+                            follow: false,
+                            tail: $expected_tail,
+                            since: $expected_since,
+                            until: 0,
+                            timestamps: false,
+                        },
+                        log_options(&get_logs_args($tail, $since), now)
+                    );
+                }
+            )*
+            }
+        }
 
-        assert_eq!(Ok(()), subcommands.console(&config));
+        log_options_tests! {
+            test_log_options_default: None, None, Some(DEFAULT_LOG_TAIL), 0,
+            test_log_options_tail: Some(LogTail::Lines(50)), None, Some(50), 0,
+            test_log_options_tail_all: Some(LogTail::All), None, None, 0,
+            test_log_options_since: None, Some(LogTime::Ago(60)), None, 1717243140,
+            test_log_options_since_and_tail: Some(LogTail::Lines(5)), Some(LogTime::At(Utc.timestamp(1717243000, 0))), Some(5), 1717243000,
+        }
+
+        macro_rules! events_log_options_tests {
+            ($($name:ident: $no_follow:expr, $since:expr, $expected_tail:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let now = Utc.timestamp(1717243200, 0);
+                    let events_args = EventsArgs {
+                        no_follow: $no_follow,
+                        since: $since,
+                        until: None,
+                        format: OutputFormat::Text,
+                    };
+                    let options = events_log_options(&events_args, now);
+                    assert_eq!(!$no_follow, options.follow);
+                    assert_eq!($expected_tail, options.tail);
+                    assert!(options.timestamps);
+                }
+            )*
+            }
+        }
+
+        events_log_options_tests! {
+            test_events_log_options_follow: false, None, Some(0),
+            test_events_log_options_follow_since: false, Some(LogTime::Ago(60)), None,
+            test_events_log_options_no_follow: true, None, None,
+        }
     }
 
-    mod test_sync_datapacks {
+    mod test_events {
         use super::*;
+        use crate::providers::container::LogLine;
+
+        fn get_events_args(format: OutputFormat) -> EventsArgs {
+            EventsArgs {
+                no_follow: false,
+                since: None,
+                until: None,
+                format,
+            }
+        }
+
+        fn get_options() -> providers::backends::docker::LogOptions {
+            providers::backends::docker::LogOptions {
+                follow: true,
+                tail: Some(0),
+                timestamps: true,
+                ..std::default::Default::default()
+            }
+        }
 
         #[test]
-        fn game_not_running() {
+        fn events() {
+            let mut subcommands = get_subcommands();
             let config = get_config();
+
+            subcommands
+                .container_provider
+                .expect_stream_container_logs()
+                .with(eq(config.clone()), eq(get_options()))
+                .times(1)
+                .returning(|_, _| {
+                    Box::new(
+                        vec![
+                            Ok(LogLine {
+                                timestamp: Some("2024-06-01T12:00:00Z".parse().unwrap()),
+                                text: "[12:00:00] [Server thread/INFO]: Steve joined the game"
+                                    .to_owned(),
+                            }),
+                            Ok(LogLine {
+                                timestamp: None,
+                                text: "[12:00:01] [Server thread/INFO]: Saving players".to_owned(),
+                            }),
+                        ]
+                        .into_iter(),
+                    )
+                });
+
+            assert!(subcommands
+                .events(&config, &get_events_args(OutputFormat::Json))
+                .is_ok());
+        }
+
+        #[test]
+        fn read_fails() {
             let mut subcommands = get_subcommands();
+            let config = get_config();
 
             subcommands
-                .file_provider
-                .expect_sync_datapacks()
-                .with(eq(config.clone()))
+                .container_provider
+                .expect_stream_container_logs()
                 .times(1)
-                .returning(|_| Ok(()));
+                .returning(|_, _| {
+                    Box::new(
+                        vec![Err(ContainerError::RconAddressNotFound("name".to_owned()))]
+                            .into_iter(),
+                    )
+                });
+
+            assert_eq!(
+                4,
+                subcommands
+                    .events(&config, &get_events_args(OutputFormat::Text))
+                    .unwrap_err()
+                    .exit_code()
+            );
+        }
+    }
+
+    mod test_notifications {
+        use super::*;
+        use crate::providers::backends::webhook::WebhookError;
+        use crate::providers::container::LogLine;
+        use crate::providers::notification::NotificationError;
+        use mockall::predicate::always;
+
+        fn get_webhook(events: Option<Vec<&str>>) -> config::Webhook {
+            config::Webhook {
+                url: "https://example.com/hook".to_owned(),
+                format: config::WebhookFormat::Json,
+                events: events.map(|events| events.into_iter().map(str::to_owned).collect()),
+            }
+        }
+
+        fn get_notifications_config() -> config::Config {
+            let mut config = get_config();
+            config.notifications.webhooks =
+                vec![get_webhook(None), get_webhook(Some(vec!["chat"]))];
+            config
+        }
+
+        fn get_since() -> chrono::DateTime<chrono::Utc> {
+            "2024-06-01T12:00:00Z".parse().unwrap()
+        }
+
+        fn get_watch_state(running: bool) -> WatchState {
+            WatchState {
+                since: get_since(),
+                running,
+                stop_logged: false,
+            }
+        }
+
+        fn get_log_line(timestamp: &str, text: &str) -> Result<LogLine, ContainerError> {
+            Ok(LogLine {
+                timestamp: Some(timestamp.parse().unwrap()),
+                text: text.to_owned(),
+            })
+        }
 
+        fn expect_start(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+        ) {
             subcommands
                 .container_provider
                 .expect_get_container_status()
-                .with(eq(config.clone()))
-                .times(1)
                 .returning(|_| Ok(ContainerState::Stopped));
+            subcommands
+                .file_provider
+                .expect_create_data_folder()
+                .returning(|| Ok(()));
+            subcommands
+                .file_provider
+                .expect_create_rcon_password()
+                .returning(|_| Ok("password".to_owned()));
+            subcommands
+                .file_provider
+                .expect_create_and_populate_server_properties()
+                .returning(|_, _| Ok(()));
+            subcommands
+                .container_provider
+                .expect_start_container()
+                .times(1)
+                .returning(|_| Ok(()));
+        }
+
+        #[test]
+        fn start_notifies() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
+            expect_start(&mut subcommands);
+
+            subcommands
+                .notification_provider
+                .expect_send_notification()
+                .with(
+                    eq(config.clone()),
+                    eq(get_webhook(None)),
+                    eq(Notification::ContainerStarted),
+                    always(),
+                )
+                .times(1)
+                .returning(|_, _, _, _| Ok(()));
 
-            assert_eq!(Ok(()), subcommands.sync_datapacks(&config));
+            assert!(subcommands.start(&config, None).is_ok());
         }
 
         #[test]
-        fn game_running() {
-            let config = get_config();
+        fn start_notification_fails() {
             let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
+            expect_start(&mut subcommands);
 
             subcommands
-                .file_provider
-                .expect_sync_datapacks()
-                .with(eq(config.clone()))
+                .notification_provider
+                .expect_send_notification()
                 .times(1)
-                .returning(|_| Ok(()));
+                .returning(|_, _, _, _| {
+                    Err(NotificationError::Webhook(WebhookError::Status {
+                        host: "https://example.com".to_owned(),
+                        status: 404,
+                    }))
+                });
+
+            assert!(subcommands.start(&config, None).is_ok());
+        }
+
+        #[test]
+        fn stop_notifies() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
 
             subcommands
                 .container_provider
                 .expect_get_container_status()
-                .with(eq(config.clone()))
+                .returning(|_| Ok(ContainerState::Running(GameState::Starting)));
+            subcommands
+                .container_provider
+                .expect_stop_container()
                 .times(1)
-                .returning(|_| Ok(ContainerState::Running(GameState::Running)));
+                .returning(|_, _| Ok(()));
+            subcommands
+                .notification_provider
+                .expect_send_notification()
+                .with(
+                    eq(config.clone()),
+                    eq(get_webhook(None)),
+                    eq(Notification::ContainerStopped),
+                    always(),
+                )
+                .times(1)
+                .returning(|_, _, _, _| Ok(()));
+
+            assert!(subcommands.stop(&config, None, 60).is_ok());
+        }
+
+        #[test]
+        fn watch_without_webhooks() {
+            let subcommands = get_subcommands();
+
+            assert_eq!(3, subcommands.watch(&get_config()).unwrap_err().exit_code());
+        }
+
+        #[test]
+        fn watch_logs() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
 
             subcommands
                 .container_provider
-                .expect_get_container_rcon_address()
-                .with(eq(config.clone()))
+                .expect_stream_container_logs()
+                .with(
+                    eq(config.clone()),
+                    eq(providers::backends::docker::LogOptions {
+                        follow: true,
+                        since: get_since().timestamp(),
+                        timestamps: true,
+                        ..std::default::Default::default()
+                    }),
+                )
                 .times(1)
-                .returning(|_| Ok(("host".to_owned(), "port".to_owned())));
+                .returning(|_, _| {
+                    Box::new(
+                        vec![
+                            get_log_line(
+                                "2024-06-01T12:00:00Z",
+                                "[12:00:00] [Server thread/INFO]: Alex joined the game",
+                            ),
+                            get_log_line(
+                                "2024-06-01T12:00:01Z",
+                                "[12:00:01] [Server thread/INFO]: Steve joined the game",
+                            ),
+                            get_log_line(
+                                "2024-06-01T12:00:02Z",
+                                "[12:00:02] [Server thread/INFO]: <Steve> hi",
+                            ),
+                            get_log_line(
+                                "2024-06-01T12:00:03Z",
+                                "[12:00:03] [Server thread/INFO]: Saving players",
+                            ),
+                        ]
+                        .into_iter(),
+                    )
+                });
 
             subcommands
-                .game_provider
-                .expect_run_rcon_commands()
+                .notification_provider
+                .expect_send_notification()
                 .with(
-                    eq("host"),
-                    eq("port"),
-                    eq(vec!["reload".to_owned(), "datapack list".to_owned()]),
+                    eq(config.clone()),
+                    eq(get_webhook(None)),
+                    eq(Notification::Game(GameEvent::Join {
+                        player: "Steve".to_owned(),
+                    })),
+                    always(),
+                )
+                .times(1)
+                .returning(|_, _, _, _| Ok(()));
+            subcommands
+                .notification_provider
+                .expect_send_notification()
+                .with(
+                    eq(config.clone()),
+                    eq(get_webhook(Some(vec!["chat"]))),
+                    eq(Notification::Game(GameEvent::Chat {
+                        player: "Steve".to_owned(),
+                        message: "hi".to_owned(),
+                    })),
+                    always(),
                 )
                 .times(1)
-                .returning(|_, _, _| Ok(vec!["response".to_owned()]));
+                .returning(|_, _, _, _| Ok(()));
+
+            let mut state = get_watch_state(true);
+            subcommands.watch_logs(&config, &mut state);
+            assert_eq!(
+                WatchState {
+                    since: "2024-06-01T12:00:03Z".parse().unwrap(),
+                    running: true,
+                    stop_logged: false,
+                },
+                state
+            );
+        }
+
+        #[test]
+        fn watch_logs_read_fails() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
 
-            assert_eq!(Ok(()), subcommands.sync_datapacks(&config));
+            subcommands
+                .container_provider
+                .expect_stream_container_logs()
+                .times(1)
+                .returning(|_, _| {
+                    Box::new(
+                        vec![
+                            Err(ContainerError::RconAddressNotFound("name".to_owned())),
+                            get_log_line(
+                                "2024-06-01T12:00:01Z",
+                                "[12:00:01] [Server thread/INFO]: Steve joined the game",
+                            ),
+                        ]
+                        .into_iter(),
+                    )
+                });
+
+            let mut state = get_watch_state(true);
+            subcommands.watch_logs(&config, &mut state);
+            assert_eq!(get_watch_state(true), state);
         }
-    }
 
-    #[test]
-    fn test_logs() {
-        let mut subcommands = get_subcommands();
-        let config = get_config();
+        #[test]
+        fn watch_logs_stopping() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
 
-        subcommands
-            .container_provider
-            .expect_display_container_logs()
-            .with(eq(config.clone()))
-            .times(1)
-            .returning(|_| Ok(()));
+            subcommands
+                .container_provider
+                .expect_stream_container_logs()
+                .times(1)
+                .returning(|_, _| {
+                    Box::new(
+                        vec![get_log_line(
+                            "2024-06-01T12:00:01Z",
+                            "[12:00:01] [Server thread/INFO]: Stopping server",
+                        )]
+                        .into_iter(),
+                    )
+                });
+            let mut state = get_watch_state(true);
+            subcommands.watch_logs(&config, &mut state);
+            assert!(state.stop_logged);
+        }
+
+        fn expect_container_states(
+            subcommands: &mut SubCommands<
+                MockContainerProvider,
+                MockFileProvider,
+                MockGameProvider,
+                MockNotificationProvider,
+            >,
+            states: Vec<ContainerState>,
+        ) {
+            let mut states = states.into_iter();
+            subcommands
+                .container_provider
+                .expect_get_container_status()
+                .times(states.len())
+                .returning(move |_| Ok(states.next().unwrap()));
+        }
+
+        #[test]
+        fn watch_container_stops_unexpectedly() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
+            expect_container_states(
+                &mut subcommands,
+                vec![
+                    ContainerState::Running(GameState::Running),
+                    ContainerState::Stopped,
+                    ContainerState::Stopped,
+                ],
+            );
+
+            subcommands
+                .container_provider
+                .expect_stream_container_logs()
+                .times(1)
+                .returning(|_, _| Box::new(vec![].into_iter()));
+            subcommands
+                .notification_provider
+                .expect_send_notification()
+                .with(
+                    eq(config.clone()),
+                    eq(get_webhook(None)),
+                    eq(Notification::Game(GameEvent::Crash { report: None })),
+                    always(),
+                )
+                .times(1)
+                .returning(|_, _, _, _| Ok(()));
+
+            let mut state = get_watch_state(false);
+            subcommands.watch_container(&config, &mut state);
+            assert!(state.running);
+            subcommands.watch_container(&config, &mut state);
+            assert!(!state.running);
+            subcommands.watch_container(&config, &mut state);
+        }
+
+        #[test]
+        fn watch_container_stops_after_logging() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
+            expect_container_states(
+                &mut subcommands,
+                vec![
+                    ContainerState::Running(GameState::Running),
+                    ContainerState::NotFound,
+                ],
+            );
+
+            subcommands
+                .container_provider
+                .expect_stream_container_logs()
+                .times(1)
+                .returning(|_, _| {
+                    Box::new(
+                        vec![get_log_line(
+                            "2024-06-01T12:00:01Z",
+                            "[12:00:01] [Server thread/INFO]: Stopping server",
+                        )]
+                        .into_iter(),
+                    )
+                });
+            let mut state = get_watch_state(false);
+            subcommands.watch_container(&config, &mut state);
+            subcommands.watch_container(&config, &mut state);
+            assert!(!state.running);
+        }
 
-        assert_eq!(Ok(()), subcommands.logs(&config));
+        #[test]
+        fn watch_container_already_stopped() {
+            let mut subcommands = get_subcommands();
+            let config = get_notifications_config();
+            expect_container_states(&mut subcommands, vec![ContainerState::Stopped]);
+
+            let mut state = get_watch_state(false);
+            subcommands.watch_container(&config, &mut state);
+            assert_eq!(get_watch_state(false), state);
+        }
     }
 }